@@ -8,6 +8,7 @@ struct Test1(pub i32);
 
 impl Asset for Test1 {}
 
+#[allow(dead_code)]
 #[derive(HasStableTypeId)]
 struct Test2(pub i32);
 
@@ -1,4 +1,4 @@
-use iridium_ecs::Transform;
+use iridium_ecs::GlobalTransform;
 use iridium_maths::VecN;
 
 /// A rectangle that stores the min and max points.
@@ -20,10 +20,10 @@ impl Rect {
     pub fn bounding_from_vertices(vertices: &[VecN<3>]) -> Self {
         vertices.iter().fold(
             Self {
-                min_x: std::f32::MAX,
-                max_x: std::f32::MIN,
-                min_y: std::f32::MAX,
-                max_y: std::f32::MIN,
+                min_x: f32::MAX,
+                max_x: f32::MIN,
+                min_y: f32::MAX,
+                max_y: f32::MIN,
             },
             |mut rect, vertex| {
                 rect.min_x = rect.min_x.min(vertex.x());
@@ -93,9 +93,9 @@ impl Rect {
         self
     }
 
-    /// Apply a transform to this rect.
+    /// Apply an entity's global transform to this rect.
     #[must_use]
-    pub fn apply_transform(mut self, t: &Transform) -> Self {
+    pub fn apply_transform(mut self, t: &GlobalTransform) -> Self {
         // Rotate the rect.
         self = self.rotate(t.rotation);
 
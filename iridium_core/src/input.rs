@@ -32,7 +32,7 @@ impl ButtonState {
 /// Despite its name, this is not the state of a system,
 /// as there'd be nothing for the system to do.
 ///
-/// It is stored under the name "`SystemState`" in the world.
+/// It is stored as a resource in the world.
 #[derive(Component, InspectorUi, ComponentStorage, HasStableTypeId)]
 pub struct InputState {
     /// The current mouse position in logical pixels.
@@ -1,4 +1,7 @@
-use iridium_ecs::ui::InspectorUiField;
+use iridium_ecs::{
+    storage::{FieldStorage, StoredValue},
+    ui::InspectorUiField,
+};
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 
@@ -368,3 +371,14 @@ impl InspectorUiField for KeyCode {
             });
     }
 }
+
+/// Stored as the name of the key, like `"ArrowUp"`.
+impl FieldStorage for KeyCode {
+    fn to_stored_value(&self) -> StoredValue {
+        StoredValue::String(self.to_string())
+    }
+
+    fn from_stored_value(value: StoredValue) -> Option<Self> {
+        value.as_text()?.parse().ok()
+    }
+}
@@ -1,7 +1,8 @@
 use std::collections::VecDeque;
 
 use iridium_ecs::{
-    storage::{ComponentStorage, StoredComponentField},
+    storage::{ComponentStorage, FieldStorage},
+    systems::SystemError,
     ui::InspectorUi,
 };
 use iridium_ecs_macros::{Component, HasStableTypeId};
@@ -69,14 +70,14 @@ impl ComponentStorage for LogState {
         mut stored: iridium_ecs::storage::StoredComponent,
         _assets: &iridium_assets::Assets,
     ) -> Option<Self> {
-        Some(Self::new(stored.get("max_entries")?.parse().ok()?))
+        Some(Self::new(stored.get("max_entries")?))
     }
 
     fn to_stored(&self) -> iridium_ecs::storage::StoredComponent {
         iridium_ecs::storage::StoredComponent {
             type_name: "LogState".to_string(),
             fields: fast_map! {
-                "max_entries" => StoredComponentField::new(self.max_entries.to_string(), false),
+                "max_entries" => self.max_entries.to_stored_value(),
             },
         }
     }
@@ -116,6 +117,14 @@ impl LogState {
         self.log(LogEntry::new(message, LogType::Error));
     }
 
+    /// Logs the errors returned by the systems,
+    /// with the system and entity they came from.
+    pub fn system_errors(&mut self, errors: impl IntoIterator<Item = SystemError>) {
+        for error in errors {
+            self.error(error.to_string());
+        }
+    }
+
     /// Returns an iterator over the log entries.
     pub fn entries(&self) -> impl Iterator<Item = &LogEntry> {
         self.entries.iter()
@@ -37,22 +37,34 @@ impl Project {
     /// Make sure to drop this **after** anything that it's created on the heap.
     /// This can be done automatically by defining this first,
     /// as local variables are dropped in the reverse order they are defined in.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the library can't be loaded.
     #[must_use]
     pub fn load(path: &str) -> Self {
-        let container: Container<ProjectApi> =
-            unsafe { Container::load(path) }.expect("Failed to load project");
+        Self::try_load(path).expect("Failed to load project")
+    }
+
+    /// Load the project from the library file, like `load`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the library can't be loaded,
+    /// or if it doesn't have the project's functions.
+    pub fn try_load(path: &str) -> Result<Self, dlopen::Error> {
+        let container: Container<ProjectApi> = unsafe { Container::load(path) }?;
 
-        Self {
+        Ok(Self {
             project_settings: container.project_settings(),
             api: container,
-        }
+        })
     }
 
     /// Runs the init system.
     ///
     /// This is where components and systems are registered,
-    /// and also system stages are setup until that's part
-    /// of the scene file.
+    /// along with the systems' ordering constraints.
     pub fn init_system(&self, world: &mut World, assets: &Assets) {
         self.api.init_system(world, assets);
     }
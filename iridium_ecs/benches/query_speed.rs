@@ -0,0 +1,51 @@
+//! Measures how long it takes to query entities.
+//!
+//! This is the hot path for most systems,
+//! so is worth keeping an eye on.
+
+#![allow(missing_docs)]
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use iridium_ecs::{query, Entities, Name, Transform};
+
+fn create_entities(count: usize) -> Entities {
+    let mut entities = Entities::default();
+
+    for i in 0..count {
+        // Half the entities don't have a transform,
+        // so they end up in a different archetype.
+        if i % 2 == 0 {
+            entities.new_entity(None, "Sprite", vec![Transform::default().into()]);
+        } else {
+            entities.new_entity(None, "Empty", vec![]);
+        }
+    }
+
+    entities
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let entities = create_entities(20_000);
+
+    let mut group = c.benchmark_group("Queries");
+
+    group.bench_function("Transform", |b| {
+        b.iter(|| {
+            for (_, transform) in query!(entities, [mut Transform; ]) {
+                *black_box(transform).position.x_mut() += 1.;
+            }
+        });
+    });
+    group.bench_function("Transform and Name", |b| {
+        b.iter(|| {
+            for (_, transform, name) in query!(entities, [mut Transform; Name]) {
+                black_box((transform, name));
+            }
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
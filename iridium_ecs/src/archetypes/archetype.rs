@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use iridium_reflect::StableTypeId;
+
+use crate::{
+    ColumnBorrow, ComponentBox, ComponentColumn, ComponentRef, ComponentTicks, Entity, SystemTicks,
+};
+
+/// The index of an archetype in `Entities`.
+pub type ArchetypeId = usize;
+
+/// Where an entity's components are stored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EntityLocation {
+    /// The archetype the entity is in.
+    pub archetype: ArchetypeId,
+    /// The row of the entity in the archetype's columns.
+    pub row: usize,
+}
+
+/// Stores all the entities that have exactly the same set of component types.
+///
+/// Each component type has its own column,
+/// and an entity's components are all at the same row in each column.
+pub struct Archetype {
+    /// The component types, sorted.
+    types: Vec<StableTypeId>,
+    /// The entity in each row.
+    entities: Vec<Entity>,
+    /// component_type => column
+    columns: HashMap<StableTypeId, Box<dyn ComponentColumn>>,
+}
+
+impl Archetype {
+    /// Creates a new archetype from the empty columns it stores.
+    #[must_use]
+    pub fn new(columns: HashMap<StableTypeId, Box<dyn ComponentColumn>>) -> Self {
+        let mut types = columns.keys().copied().collect::<Vec<_>>();
+        types.sort_unstable();
+
+        Self {
+            types,
+            entities: Vec::new(),
+            columns,
+        }
+    }
+
+    /// The component types stored in this archetype, sorted.
+    #[must_use]
+    pub fn types(&self) -> &[StableTypeId] {
+        &self.types
+    }
+
+    /// The entity in each row.
+    #[must_use]
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// The number of entities in this archetype.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// Whether this archetype has no entities.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /// Whether this archetype stores the given component type.
+    #[must_use]
+    pub fn has_type(&self, component_type: StableTypeId) -> bool {
+        self.types.binary_search(&component_type).is_ok()
+    }
+
+    /// Gets the column of the given component type.
+    #[must_use]
+    pub fn column(&self, component_type: StableTypeId) -> Option<&dyn ComponentColumn> {
+        self.columns.get(&component_type).map(|column| &**column)
+    }
+
+    /// Gets the column of the given component type mutably.
+    pub(crate) fn column_mut(
+        &mut self,
+        component_type: StableTypeId,
+    ) -> Option<&mut (dyn ComponentColumn + 'static)> {
+        self.columns
+            .get_mut(&component_type)
+            .map(|column| &mut **column)
+    }
+
+    /// Gets all the columns in this archetype.
+    pub fn columns(&self) -> impl Iterator<Item = &dyn ComponentColumn> {
+        self.columns.values().map(|column| &**column)
+    }
+
+    /// Gets all the components in a row, in the order of `types`.
+    ///
+    /// Every column is borrowed mutably until the references are dropped.
+    ///
+    /// # Panics
+    ///
+    /// If any of the columns are already borrowed.
+    #[must_use]
+    pub fn row(&self, row: usize, system_ticks: SystemTicks) -> Vec<ComponentRef<'_>> {
+        self.types
+            .iter()
+            .map(|component_type| {
+                ColumnBorrow::new(&*self.columns[component_type], true).get(row, system_ticks)
+            })
+            .collect()
+    }
+
+    /// Adds an entity to the end of the archetype,
+    /// and returns the row it was added at.
+    ///
+    /// There must be exactly one component for each column.
+    pub(crate) fn push(
+        &mut self,
+        entity: Entity,
+        components: Vec<(ComponentBox, ComponentTicks)>,
+    ) -> usize {
+        debug_assert_eq!(components.len(), self.types.len());
+
+        for (component, ticks) in components {
+            self.columns
+                .get_mut(&component.stable_type_id())
+                .expect("Component type not in archetype")
+                .push(component, ticks);
+        }
+
+        self.entities.push(entity);
+
+        self.entities.len() - 1
+    }
+
+    /// Removes the entity at the given row,
+    /// moving the last entity into its place.
+    ///
+    /// Returns the removed components with their ticks,
+    /// and the entity that was moved into the row, if any.
+    pub(crate) fn swap_remove(
+        &mut self,
+        row: usize,
+    ) -> (Vec<(ComponentBox, ComponentTicks)>, Option<Entity>) {
+        let components = self
+            .types
+            .iter()
+            .map(|component_type| {
+                self.columns
+                    .get_mut(component_type)
+                    .expect("Archetype types and columns out of sync")
+                    .swap_remove(row)
+            })
+            .collect();
+
+        self.entities.swap_remove(row);
+
+        (components, self.entities.get(row).copied())
+    }
+
+    /// Removes every entity from this archetype.
+    pub(crate) fn clear(&mut self) {
+        self.entities.clear();
+
+        for column in self.columns.values_mut() {
+            column.clear();
+        }
+    }
+}
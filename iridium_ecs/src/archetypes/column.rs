@@ -0,0 +1,205 @@
+use std::{cell::UnsafeCell, sync::Arc};
+
+use iridium_reflect::StableTypeId;
+
+use crate::{
+    BorrowFlag, BorrowGuard, Component, ComponentBox, ComponentRef, ComponentTicks, SystemTicks,
+};
+
+/// A contiguous column of components that are all the same type.
+///
+/// This is a trait so archetypes can store columns without knowing their type,
+/// but the only implementation is `TypedColumn`.
+///
+/// The components are borrowed through `ColumnBorrow`.
+///
+/// # Safety
+///
+/// The components in `cell` must only be accessed while `borrow_flag` is borrowed,
+/// or through `&mut self`.
+pub unsafe trait ComponentColumn: Send + Sync {
+    /// The number of components in the column.
+    fn len(&self) -> usize;
+
+    /// Whether the column is empty.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a component and its ticks to the end of the column.
+    ///
+    /// The component must be the same type as the column.
+    fn push(&mut self, component: ComponentBox, ticks: ComponentTicks);
+
+    /// Removes the component at the given row,
+    /// replacing it with the last component.
+    fn swap_remove(&mut self, row: usize) -> (ComponentBox, ComponentTicks);
+
+    /// Replaces the component at the given row,
+    /// marking it as changed at the given tick.
+    ///
+    /// Returns the old component.
+    fn replace(&mut self, row: usize, component: ComponentBox, tick: u32) -> ComponentBox;
+
+    /// Gets the cell the component at the given row is stored in.
+    ///
+    /// This should only be used through `ColumnBorrow`.
+    fn cell(&self, row: usize) -> &UnsafeCell<dyn Component>;
+
+    /// Gets the ticks of the component at the given row.
+    fn ticks(&self, row: usize) -> &ComponentTicks;
+
+    /// Gets the flag tracking the borrows of the whole column.
+    fn borrow_flag(&self) -> &BorrowFlag;
+
+    /// The stable type id of the components.
+    fn stable_type_id(&self) -> StableTypeId;
+
+    /// The type name of the components.
+    fn type_name(&self) -> &'static str;
+
+    /// Removes every component in the column.
+    fn clear(&mut self);
+
+    /// Creates an empty column of the same type.
+    fn new_empty(&self) -> Box<dyn ComponentColumn>;
+}
+
+/// A column of components of type `T`.
+///
+/// The components are stored inline in a `Vec`, not boxed individually.
+pub struct TypedColumn<T: Component> {
+    data: Vec<UnsafeCell<T>>,
+    /// The ticks of the component in each row.
+    ticks: Vec<ComponentTicks>,
+    /// Tracks the borrows of the whole column.
+    borrow_flag: BorrowFlag,
+}
+
+// The borrow flag makes sure the components are never aliased mutably.
+unsafe impl<T: Component> Sync for TypedColumn<T> {}
+
+impl<T: Component> Default for TypedColumn<T> {
+    fn default() -> Self {
+        Self {
+            data: Vec::new(),
+            ticks: Vec::new(),
+            borrow_flag: BorrowFlag::new(),
+        }
+    }
+}
+
+impl<T: Component> TypedColumn<T> {
+    /// Creates a new empty column.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+unsafe impl<T: Component> ComponentColumn for TypedColumn<T> {
+    fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    fn push(&mut self, component: ComponentBox, ticks: ComponentTicks) {
+        self.data.push(UnsafeCell::new(component.take::<T>()));
+        self.ticks.push(ticks);
+    }
+
+    fn swap_remove(&mut self, row: usize) -> (ComponentBox, ComponentTicks) {
+        (
+            ComponentBox::new(self.data.swap_remove(row).into_inner()),
+            self.ticks.swap_remove(row),
+        )
+    }
+
+    fn replace(&mut self, row: usize, component: ComponentBox, tick: u32) -> ComponentBox {
+        self.ticks[row].set_changed(tick);
+
+        ComponentBox::new(std::mem::replace(
+            self.data[row].get_mut(),
+            component.take::<T>(),
+        ))
+    }
+
+    fn cell(&self, row: usize) -> &UnsafeCell<dyn Component> {
+        &self.data[row]
+    }
+
+    fn ticks(&self, row: usize) -> &ComponentTicks {
+        &self.ticks[row]
+    }
+
+    fn borrow_flag(&self) -> &BorrowFlag {
+        &self.borrow_flag
+    }
+
+    fn stable_type_id(&self) -> StableTypeId {
+        T::stable_type_id()
+    }
+
+    fn type_name(&self) -> &'static str {
+        T::type_name()
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.ticks.clear();
+    }
+
+    fn new_empty(&self) -> Box<dyn ComponentColumn> {
+        Box::new(Self::new())
+    }
+}
+
+/// A borrow of a whole column,
+/// used to get references to the components in it.
+///
+/// The references share the borrow,
+/// so it's released once this and all of them are dropped.
+pub(crate) struct ColumnBorrow<'a> {
+    column: &'a dyn ComponentColumn,
+    guard: Arc<BorrowGuard<'a>>,
+}
+
+impl<'a> ColumnBorrow<'a> {
+    /// Borrows a column, mutably or immutably.
+    ///
+    /// # Panics
+    ///
+    /// If the column is borrowed mutably,
+    /// or is borrowed at all and `mutable` is true.
+    #[must_use]
+    pub(crate) fn new(column: &'a dyn ComponentColumn, mutable: bool) -> Self {
+        let flag = column.borrow_flag();
+        let guard = if mutable {
+            flag.borrow_mut(column.type_name())
+        } else {
+            flag.borrow(column.type_name())
+        };
+
+        Self {
+            column,
+            guard: Arc::new(guard),
+        }
+    }
+
+    /// Gets a reference to the component at the given row.
+    ///
+    /// The system ticks are used for change detection.
+    ///
+    /// Each row should only be got once,
+    /// as mutable references to the same row would alias.
+    #[must_use]
+    pub(crate) fn get(&self, row: usize, system_ticks: SystemTicks) -> ComponentRef<'a> {
+        ComponentRef::new(
+            self.column.cell(row),
+            self.column.ticks(row),
+            system_ticks,
+            Arc::clone(&self.guard),
+            self.column.stable_type_id(),
+            self.column.type_name(),
+        )
+    }
+}
@@ -0,0 +1,8 @@
+mod archetype;
+pub use archetype::*;
+
+mod column;
+pub use column::*;
+
+mod ticks;
+pub use ticks::*;
@@ -0,0 +1,84 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+/// The ticks a system compares component ticks against.
+///
+/// A component counts as changed if it was changed after `last_run`,
+/// which is the tick the system last ran at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SystemTicks {
+    /// The tick the system last ran at.
+    pub last_run: u32,
+    /// The current tick.
+    pub current: u32,
+}
+
+impl SystemTicks {
+    /// Checks if the tick is after `last_run`.
+    ///
+    /// This handles the ticks wrapping around.
+    #[must_use]
+    pub const fn is_newer(self, tick: u32) -> bool {
+        self.current.wrapping_sub(tick) < self.current.wrapping_sub(self.last_run)
+    }
+}
+
+/// When a component was added and last changed,
+/// measured in the ticks of `Entities`.
+///
+/// The changed tick is atomic, as components are changed through `&Entities`.
+#[derive(Debug)]
+pub struct ComponentTicks {
+    added: u32,
+    changed: AtomicU32,
+}
+
+impl ComponentTicks {
+    /// Creates the ticks for a component added at the given tick.
+    #[must_use]
+    pub const fn new(tick: u32) -> Self {
+        Self {
+            added: tick,
+            changed: AtomicU32::new(tick),
+        }
+    }
+
+    /// The tick the component was added at.
+    #[must_use]
+    pub const fn added(&self) -> u32 {
+        self.added
+    }
+
+    /// The tick the component was last changed at.
+    #[must_use]
+    pub fn changed(&self) -> u32 {
+        self.changed.load(Ordering::Relaxed)
+    }
+
+    /// Marks the component as changed at the given tick.
+    pub fn set_changed(&self, tick: u32) {
+        self.changed.store(tick, Ordering::Relaxed);
+    }
+
+    /// Checks if the component was added since the system last ran.
+    #[must_use]
+    pub const fn is_added(&self, system_ticks: SystemTicks) -> bool {
+        system_ticks.is_newer(self.added)
+    }
+
+    /// Checks if the component was changed since the system last ran.
+    ///
+    /// Adding a component counts as changing it.
+    #[must_use]
+    pub fn is_changed(&self, system_ticks: SystemTicks) -> bool {
+        system_ticks.is_newer(self.changed())
+    }
+}
+
+impl Clone for ComponentTicks {
+    fn clone(&self) -> Self {
+        Self {
+            added: self.added,
+            changed: AtomicU32::new(self.changed()),
+        }
+    }
+}
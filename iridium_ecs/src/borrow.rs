@@ -0,0 +1,422 @@
+use std::{
+    cell::Cell,
+    ops::{Deref, DerefMut},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+#[cfg(debug_assertions)]
+use std::sync::{Mutex, PoisonError};
+
+use iridium_reflect::StableTypeId;
+
+/// The flag value when the data is borrowed mutably.
+const EXCLUSIVE: usize = usize::MAX;
+
+thread_local! {
+    /// The name of the system running on this thread.
+    static CURRENT_SYSTEM: Cell<Option<&'static str>> = const { Cell::new(None) };
+    /// The number of entities the system running on this thread has processed.
+    static PROCESSED_ENTITIES: Cell<usize> = const { Cell::new(0) };
+    /// The tick the system running on this thread last ran at.
+    static LAST_RUN_TICK: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// Gets the name of the system running on this thread,
+/// or `None` if no system is running.
+#[must_use]
+pub fn current_system() -> Option<&'static str> {
+    CURRENT_SYSTEM.with(Cell::get)
+}
+
+/// Marks a system as running on this thread until it's dropped.
+///
+/// This is used to name the systems involved when a borrow fails,
+/// to count the entities a system processes,
+/// and for what counts as changed since the system last ran.
+/// `World::run_systems` enters a scope for each system,
+/// but work a system spreads over other threads needs to enter its own.
+pub struct SystemScope {
+    /// The system that was running before this scope.
+    previous: Option<&'static str>,
+    /// The entities processed by the system that was running before this scope.
+    previous_entities: usize,
+    /// The last run tick of the system that was running before this scope.
+    previous_last_run_tick: Option<u32>,
+}
+
+impl SystemScope {
+    /// Marks the given system as running on this thread.
+    #[must_use]
+    pub fn enter(system: &'static str) -> Self {
+        Self {
+            previous: CURRENT_SYSTEM.with(|current| current.replace(Some(system))),
+            previous_entities: PROCESSED_ENTITIES.with(|processed| processed.replace(0)),
+            previous_last_run_tick: LAST_RUN_TICK.with(Cell::get),
+        }
+    }
+
+    /// Sets the tick the system last ran at,
+    /// so only components changed after it count as changed in `Entities`.
+    ///
+    /// If this is `None`, the last run tick of `Entities` is used.
+    #[must_use]
+    pub fn with_last_run_tick(self, tick: Option<u32>) -> Self {
+        LAST_RUN_TICK.with(|last_run| last_run.set(tick));
+        self
+    }
+
+    /// The tick the system running on this thread last ran at,
+    /// if it was set with `with_last_run_tick`.
+    #[must_use]
+    pub fn last_run_tick() -> Option<u32> {
+        LAST_RUN_TICK.with(Cell::get)
+    }
+
+    /// Adds to the number of entities the system running on this thread has processed,
+    /// which `Systems` records in its stats.
+    pub fn add_processed_entities(count: usize) {
+        PROCESSED_ENTITIES.with(|processed| processed.set(processed.get() + count));
+    }
+
+    /// The number of entities processed since this scope was entered.
+    #[must_use]
+    pub fn processed_entities(&self) -> usize {
+        PROCESSED_ENTITIES.with(Cell::get)
+    }
+}
+
+impl Drop for SystemScope {
+    fn drop(&mut self) {
+        CURRENT_SYSTEM.with(|current| current.set(self.previous));
+        PROCESSED_ENTITIES.with(|processed| processed.set(self.previous_entities));
+        LAST_RUN_TICK.with(|last_run| last_run.set(self.previous_last_run_tick));
+    }
+}
+
+/// An atomic flag tracking the borrows of some data,
+/// like a `RefCell` that can be shared between threads.
+///
+/// Every component column and `ComponentBox` has one,
+/// so components can be borrowed from `&Entities` and `&Resources` safely.
+///
+/// Borrowing data that's already borrowed mutably,
+/// or mutably borrowing data that's already borrowed, panics.
+/// In debug builds, the panic names the systems holding the borrows.
+#[derive(Default)]
+pub struct BorrowFlag {
+    /// The number of immutable borrows, or `EXCLUSIVE` if borrowed mutably.
+    state: AtomicUsize,
+    /// The systems holding borrows, for the panic message.
+    #[cfg(debug_assertions)]
+    borrowers: Mutex<Vec<&'static str>>,
+}
+
+impl BorrowFlag {
+    /// Creates a flag for data that isn't borrowed.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks if the data is borrowed, mutably or immutably.
+    #[must_use]
+    pub fn is_borrowed(&self) -> bool {
+        self.state.load(Ordering::Acquire) != 0
+    }
+
+    /// Checks if the data is borrowed mutably.
+    #[must_use]
+    pub fn is_borrowed_mut(&self) -> bool {
+        self.state.load(Ordering::Acquire) == EXCLUSIVE
+    }
+
+    /// Borrows the data immutably.
+    ///
+    /// `type_name` is the type of the data, for the panic message.
+    ///
+    /// # Panics
+    ///
+    /// If the data is borrowed mutably.
+    #[must_use]
+    pub fn borrow(&self, type_name: &'static str) -> BorrowGuard<'_> {
+        let mut state = self.state.load(Ordering::Acquire);
+        loop {
+            if state == EXCLUSIVE {
+                self.conflict(type_name, false);
+            }
+            assert!(state < EXCLUSIVE - 1, "Too many borrows of {type_name}");
+
+            match self.state.compare_exchange_weak(
+                state,
+                state + 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => break,
+                Err(actual) => state = actual,
+            }
+        }
+
+        BorrowGuard::new(self, false)
+    }
+
+    /// Borrows the data mutably.
+    ///
+    /// `type_name` is the type of the data, for the panic message.
+    ///
+    /// # Panics
+    ///
+    /// If the data is already borrowed.
+    #[must_use]
+    pub fn borrow_mut(&self, type_name: &'static str) -> BorrowGuard<'_> {
+        if self
+            .state
+            .compare_exchange(0, EXCLUSIVE, Ordering::AcqRel, Ordering::Acquire)
+            .is_err()
+        {
+            self.conflict(type_name, true);
+        }
+
+        BorrowGuard::new(self, true)
+    }
+
+    /// Panics because a borrow failed.
+    #[cold]
+    #[track_caller]
+    fn conflict(&self, type_name: &'static str, mutable: bool) -> ! {
+        let kind = if mutable { "mutably" } else { "immutably" };
+
+        #[cfg(debug_assertions)]
+        {
+            let borrowers = self
+                .borrowers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .join(", ");
+
+            panic!(
+                "{type_name} can't be borrowed {kind} by {}, as it's already borrowed by {borrowers}",
+                current_system().unwrap_or("<no system>"),
+            );
+        }
+
+        #[cfg(not(debug_assertions))]
+        panic!("{type_name} can't be borrowed {kind}, as it's already borrowed");
+    }
+}
+
+/// A borrow of data with a `BorrowFlag`,
+/// which is released when this is dropped.
+pub struct BorrowGuard<'a> {
+    flag: &'a BorrowFlag,
+    mutable: bool,
+    /// The system that took the borrow.
+    #[cfg(debug_assertions)]
+    borrower: &'static str,
+}
+
+impl<'a> BorrowGuard<'a> {
+    /// Creates a guard for a borrow that's just been taken.
+    fn new(flag: &'a BorrowFlag, mutable: bool) -> Self {
+        #[cfg(debug_assertions)]
+        let borrower = current_system().unwrap_or("<no system>");
+        #[cfg(debug_assertions)]
+        flag.borrowers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(borrower);
+
+        Self {
+            flag,
+            mutable,
+            #[cfg(debug_assertions)]
+            borrower,
+        }
+    }
+
+    /// Whether this is a mutable borrow.
+    #[must_use]
+    pub const fn is_mutable(&self) -> bool {
+        self.mutable
+    }
+}
+
+impl Drop for BorrowGuard<'_> {
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        {
+            let mut borrowers = self
+                .flag
+                .borrowers
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(index) = borrowers.iter().position(|name| *name == self.borrower) {
+                borrowers.swap_remove(index);
+            }
+        }
+
+        if self.mutable {
+            self.flag.state.store(0, Ordering::Release);
+        } else {
+            self.flag.state.fetch_sub(1, Ordering::Release);
+        }
+    }
+}
+
+/// How a query accesses a component type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// The component is only read.
+    Read(StableTypeId),
+    /// The component is written.
+    Write(StableTypeId),
+}
+
+impl Access {
+    /// The component type.
+    #[must_use]
+    pub const fn type_id(self) -> StableTypeId {
+        match self {
+            Self::Read(type_id) | Self::Write(type_id) => type_id,
+        }
+    }
+
+    /// Whether the component is written.
+    #[must_use]
+    pub const fn is_write(self) -> bool {
+        matches!(self, Self::Write(_))
+    }
+}
+
+/// An immutable reference to a component,
+/// which holds a borrow until it's dropped.
+pub struct Ref<'a, T: ?Sized> {
+    value: &'a T,
+    _guard: Arc<BorrowGuard<'a>>,
+}
+
+impl<'a, T: ?Sized> Ref<'a, T> {
+    /// Creates a reference from a value and the borrow that protects it.
+    pub(crate) const fn new(value: &'a T, guard: Arc<BorrowGuard<'a>>) -> Self {
+        Self {
+            value,
+            _guard: guard,
+        }
+    }
+
+    /// Makes a reference to part of the value, keeping the borrow.
+    #[must_use]
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&T) -> &U) -> Ref<'a, U> {
+        Ref {
+            value: f(this.value),
+            _guard: this._guard,
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for Ref<'_, T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: self.value,
+            _guard: Arc::clone(&self._guard),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for Ref<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+/// A mutable reference to a component,
+/// which holds a mutable borrow until it's dropped.
+pub struct Mut<'a, T: ?Sized> {
+    value: &'a mut T,
+    _guard: Arc<BorrowGuard<'a>>,
+}
+
+impl<'a, T: ?Sized> Mut<'a, T> {
+    /// Creates a reference from a value and the mutable borrow that protects it.
+    pub(crate) fn new(value: &'a mut T, guard: Arc<BorrowGuard<'a>>) -> Self {
+        debug_assert!(guard.is_mutable());
+
+        Self {
+            value,
+            _guard: guard,
+        }
+    }
+
+    /// Makes a reference to part of the value, keeping the borrow.
+    #[must_use]
+    pub fn map<U: ?Sized>(this: Self, f: impl FnOnce(&mut T) -> &mut U) -> Mut<'a, U> {
+        Mut {
+            value: f(this.value),
+            _guard: this._guard,
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for Mut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value
+    }
+}
+
+impl<T: ?Sized> DerefMut for Mut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_borrows() {
+        let flag = BorrowFlag::new();
+
+        let a = flag.borrow("A");
+        let b = flag.borrow("A");
+        assert!(flag.is_borrowed());
+        assert!(!flag.is_borrowed_mut());
+
+        drop((a, b));
+        assert!(!flag.is_borrowed());
+
+        let _c = flag.borrow_mut("A");
+        assert!(flag.is_borrowed_mut());
+    }
+
+    #[test]
+    #[should_panic(expected = "A can't be borrowed mutably")]
+    fn mut_while_borrowed() {
+        let flag = BorrowFlag::new();
+
+        let _a = flag.borrow("A");
+        let _b = flag.borrow_mut("A");
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "by SecondSystem, as it's already borrowed by FirstSystem")]
+    fn conflict_names_systems() {
+        let flag = BorrowFlag::new();
+
+        let _a = {
+            let _scope = SystemScope::enter("FirstSystem");
+            flag.borrow_mut("A")
+        };
+
+        let _scope = SystemScope::enter("SecondSystem");
+        let _b = flag.borrow("A");
+    }
+}
@@ -0,0 +1,128 @@
+use iridium_assets::Assets;
+
+use crate::{
+    storage::{LoadDiagnostic, Prefab},
+    Component, ComponentBox, Entities, Entity, EntityCommand,
+};
+
+/// Sends `EntityCommand`s from a system.
+///
+/// The commands are processed at the end of the stage,
+/// so the changes aren't visible until the next stage.
+///
+/// Get one with `Entities::commands`.
+///
+/// # Examples
+///
+/// ```ignore
+/// let commands = entities.commands("CollisionSystem");
+///
+/// commands.remove_component::<Velocity>(entity);
+/// commands.add_component(entity, Dead);
+/// ```
+#[derive(Clone, Copy)]
+pub struct Commands<'a> {
+    entities: &'a Entities,
+    source: &'static str,
+}
+
+impl<'a> Commands<'a> {
+    /// Creates a `Commands` that sends from the given source.
+    #[must_use]
+    pub const fn new(entities: &'a Entities, source: &'static str) -> Self {
+        Self { entities, source }
+    }
+
+    /// The source the commands are sent from.
+    #[must_use]
+    pub const fn source(&self) -> &'static str {
+        self.source
+    }
+
+    /// Sends a raw `EntityCommand`.
+    pub fn send(&self, cmd: EntityCommand) {
+        self.entities.send_cmd_from(self.source, cmd);
+    }
+
+    /// Creates a new entity with the given name and components.
+    pub fn spawn(&self, name: &str, components: Vec<ComponentBox>) {
+        self.send(EntityCommand::NewEntity(None, name.to_owned(), components));
+    }
+
+    /// Creates a new entity from a prefab, with the given name.
+    ///
+    /// `overrides` replace the prefab's components of the same type,
+    /// like a `Transform` to place it.
+    ///
+    /// # Errors
+    ///
+    /// Returns the problem if one of the prefab's components can't be read,
+    /// in which case nothing is sent.
+    pub fn instantiate(
+        &self,
+        prefab: &Prefab,
+        name: &str,
+        overrides: Vec<ComponentBox>,
+        assets: &Assets,
+    ) -> Result<(), LoadDiagnostic> {
+        let mut components = prefab.instantiate(self.entities, assets)?;
+        for component in overrides {
+            components.retain(|existing| existing.type_name() != component.type_name());
+            components.push(component);
+        }
+
+        self.spawn(name, components);
+        Ok(())
+    }
+
+    /// Deletes an entity and all its descendants.
+    pub fn delete(&self, entity: Entity) {
+        self.send(EntityCommand::DeleteEntity(entity));
+    }
+
+    /// Deletes an entity and all its descendants.
+    ///
+    /// The same as `delete`.
+    pub fn despawn_recursive(&self, entity: Entity) {
+        self.send(EntityCommand::DespawnRecursive(entity));
+    }
+
+    /// Adds a component to an entity,
+    /// if the entity doesn't already have one of the same type.
+    pub fn add_component<T: Component>(&self, entity: Entity, component: T) {
+        self.add_components(entity, vec![ComponentBox::new(component)]);
+    }
+
+    /// Adds components to an entity,
+    /// skipping any it already has.
+    pub fn add_components(&self, entity: Entity, components: Vec<ComponentBox>) {
+        self.send(EntityCommand::AddComponents(entity, components));
+    }
+
+    /// Removes a component from an entity.
+    pub fn remove_component<T: Component>(&self, entity: Entity) {
+        self.send(EntityCommand::RemoveComponents(
+            entity,
+            vec![T::stable_type_id()],
+        ));
+    }
+
+    /// Replaces a component of an entity,
+    /// adding it if the entity doesn't have it.
+    pub fn replace_component<T: Component>(&self, entity: Entity, component: T) {
+        self.send(EntityCommand::ReplaceComponent(
+            entity,
+            ComponentBox::new(component),
+        ));
+    }
+
+    /// Sets the parent of an entity, or removes it if `None`.
+    pub fn set_parent(&self, entity: Entity, parent: Option<Entity>) {
+        self.send(EntityCommand::SetParent(entity, parent));
+    }
+
+    /// Changes the name of an entity.
+    pub fn rename(&self, entity: Entity, name: &str) {
+        self.send(EntityCommand::RenameEntity(entity, name.to_owned()));
+    }
+}
@@ -10,6 +10,27 @@ pub struct Name {
     pub name: String,
 }
 
+/// Marks an entity as an instance of a prefab.
+///
+/// Instances are saved with only the fields that differ from the prefab,
+/// the rest are read from the prefab when loading.
+#[derive(Component, InspectorUi, ComponentStorage, HasStableTypeId)]
+pub struct PrefabInstance {
+    /// The id of the prefab, which is the path of its file.
+    pub prefab: String,
+}
+
+/// The scene an entity was loaded from.
+///
+/// Added when loading, so scenes can be unloaded and saved on their own.
+/// It isn't saved to the entity's own scene,
+/// but is kept when saved to another file, like a snapshot of the whole world.
+#[derive(Component, InspectorUi, ComponentStorage, HasStableTypeId)]
+pub struct SceneOrigin {
+    /// The path of the scene's file.
+    pub scene: String,
+}
+
 /// The position, scale and rotation of an entity.
 #[derive(Component, InspectorUi, ComponentStorage, HasStableTypeId)]
 pub struct Transform {
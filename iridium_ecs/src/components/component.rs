@@ -3,7 +3,7 @@ use std::any::Any;
 use iridium_assets::Assets;
 use iridium_reflect::HasStableTypeId;
 
-use crate::{storage::ComponentStorage, ui::InspectorUi};
+use crate::{storage::ComponentStorage, ui::InspectorUi, ComponentColumn};
 
 /// A trait implemented by components.
 pub trait Component: Send + Sync + Any + ComponentStorage + InspectorUi + HasStableTypeId {
@@ -26,4 +26,9 @@ pub trait Component: Send + Sync + Any + ComponentStorage + InspectorUi + HasSta
     ///
     /// If any of the new assets aren't found.
     fn update_assets(&mut self, assets: &Assets) -> Result<i32, String>;
+
+    /// Creates an empty column that can store this component type.
+    ///
+    /// Used by `Entities` when a new archetype is created.
+    fn empty_column(&self) -> Box<dyn ComponentColumn>;
 }
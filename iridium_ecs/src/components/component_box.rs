@@ -1,24 +1,30 @@
-#![allow(clippy::mut_from_ref)]
-
-use std::cell::UnsafeCell;
+use std::{cell::UnsafeCell, sync::Arc};
 
 use iridium_reflect::StableTypeId;
 
 use super::Component;
+use crate::{BorrowFlag, Mut, Ref};
 
 /// A component.
 ///
 /// This is a wrapper around a type that implements `Component`.
 ///
-/// This ignores Rust's borrow checker as it uses internal mutability.
-///
-/// Even though this goes against Rust's safety rules, it is the user's responsibility to ensure that
-/// the rules are followed. Hopefully this will be fixed in the future.
+/// This uses internal mutability, like a `RefCell`,
+/// so the component can be changed through a shared reference.
+/// The borrows are checked at runtime with a `BorrowFlag`,
+/// and breaking the borrowing rules panics.
 pub struct ComponentBox {
     data: Box<UnsafeCell<dyn Component>>,
+    /// Tracks the borrows of `data`.
+    borrow_flag: BorrowFlag,
+    /// The stable type id of the component,
+    /// stored so it can be read while the component is borrowed.
+    stable_type_id: StableTypeId,
+    /// The type name of the component.
+    type_name: &'static str,
 }
 
-unsafe impl Send for ComponentBox {}
+// The borrow flag makes sure the component is never aliased mutably.
 unsafe impl Sync for ComponentBox {}
 
 impl ComponentBox {
@@ -26,49 +32,81 @@ impl ComponentBox {
     #[must_use]
     pub fn new(component: impl Component + 'static) -> Self {
         Self {
+            stable_type_id: component.dyn_stable_type_id(),
+            type_name: component.dyn_type_name(),
             data: Box::new(UnsafeCell::new(component)),
+            borrow_flag: BorrowFlag::new(),
         }
     }
 
     /// Gets the inner component type.
+    ///
+    /// # Panics
+    ///
+    /// If the component isn't of type `T`.
     #[must_use]
     pub fn take<T: Component + Sized>(self) -> T {
-        unsafe {
-            let ptr = self.data.get() as *const T;
-            let t = ptr.read();
-            std::mem::forget(self);
-            t
-        }
+        self.assert_type::<T>();
+
+        // This frees the box, unlike reading the pointer and forgetting `self`.
+        unsafe { Box::from_raw(Box::into_raw(self.data).cast::<UnsafeCell<T>>()) }.into_inner()
     }
 
     /// Gets a reference to the component as `T`.
+    ///
+    /// # Panics
+    ///
+    /// If the component isn't of type `T`, or it's borrowed mutably.
     #[must_use]
-    pub fn get<T: Component>(&self) -> &T {
-        unsafe { &*self.data.get().cast::<T>() }
+    pub fn get<T: Component>(&self) -> Ref<'_, T> {
+        self.assert_type::<T>();
+
+        let guard = self.borrow_flag.borrow(self.type_name);
+        Ref::new(unsafe { &*self.data.get().cast::<T>() }, Arc::new(guard))
     }
 
     /// Gets a mutable reference to the component as `T`.
+    ///
+    /// # Panics
+    ///
+    /// If the component isn't of type `T`, or it's already borrowed.
     #[must_use]
-    pub fn get_mut<T: Component>(&self) -> &mut T {
-        unsafe { &mut *self.data.get().cast::<T>() }
+    pub fn get_mut<T: Component>(&self) -> Mut<'_, T> {
+        self.assert_type::<T>();
+
+        let guard = self.borrow_flag.borrow_mut(self.type_name);
+        Mut::new(
+            unsafe { &mut *self.data.get().cast::<T>() },
+            Arc::new(guard),
+        )
     }
 
     /// Gets a reference to the component as `dyn Component`.
+    ///
+    /// # Panics
+    ///
+    /// If the component is borrowed mutably.
     #[must_use]
-    pub fn get_trait(&self) -> &dyn Component {
-        unsafe { &*self.data.get() }
+    pub fn get_trait(&self) -> Ref<'_, dyn Component> {
+        let guard = self.borrow_flag.borrow(self.type_name);
+        Ref::new(unsafe { &*self.data.get() }, Arc::new(guard))
     }
 
     /// Gets a mutable reference to the component as `dyn Component`.
+    ///
+    /// # Panics
+    ///
+    /// If the component is already borrowed.
     #[must_use]
-    pub fn get_trait_mut(&self) -> &mut dyn Component {
-        unsafe { &mut *self.data.get() }
+    pub fn get_trait_mut(&self) -> Mut<'_, dyn Component> {
+        let guard = self.borrow_flag.borrow_mut(self.type_name);
+        Mut::new(unsafe { &mut *self.data.get() }, Arc::new(guard))
     }
 
     /// Gets the stable type id of the underlying component.
     #[must_use]
-    pub fn stable_type_id(&self) -> StableTypeId {
-        self.get_trait().dyn_stable_type_id()
+    pub const fn stable_type_id(&self) -> StableTypeId {
+        self.stable_type_id
     }
 
     /// Checks if the component is of the given type.
@@ -79,8 +117,18 @@ impl ComponentBox {
 
     /// Gets the type name of the underlying component.
     #[must_use]
-    pub fn type_name(&self) -> &'static str {
-        self.get_trait().dyn_type_name()
+    pub const fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Panics if the component isn't of type `T`.
+    fn assert_type<T: Component>(&self) {
+        assert!(
+            self.is_type::<T>(),
+            "ComponentBox of {} used as {}",
+            self.type_name,
+            T::type_name()
+        );
     }
 }
 
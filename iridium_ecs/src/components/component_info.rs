@@ -1,6 +1,9 @@
 use iridium_assets::Assets;
 
-use crate::{storage::StoredComponent, ComponentDefault};
+use crate::{
+    storage::{FieldError, StoredComponent},
+    ComponentDefault,
+};
 
 use super::{Component, ComponentBox};
 
@@ -18,7 +21,11 @@ pub struct ComponentInfo {
     #[allow(clippy::type_complexity)]
     pub default: Option<fn(&Assets) -> Result<ComponentBox, String>>,
     /// Tries to create a component from a stored component.
-    pub from_stored: fn(StoredComponent, &Assets) -> Option<ComponentBox>,
+    ///
+    /// Says which field was invalid if it can't.
+    pub from_stored: fn(StoredComponent, &Assets) -> Result<ComponentBox, FieldError>,
+    /// The version of the stored fields, from `ComponentStorage::schema_version`.
+    pub schema_version: u32,
 }
 
 impl ComponentInfo {
@@ -29,6 +36,7 @@ impl ComponentInfo {
             type_name: T::type_name(),
             default: None,
             from_stored: T::from_stored_component,
+            schema_version: T::schema_version(),
         }
     }
 
@@ -41,6 +49,7 @@ impl ComponentInfo {
             type_name: T::type_name(),
             default: Some(|assets| T::default(assets).map(Into::into)),
             from_stored: T::from_stored_component,
+            schema_version: T::schema_version(),
         }
     }
 }
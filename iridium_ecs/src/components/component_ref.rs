@@ -0,0 +1,213 @@
+use std::{cell::UnsafeCell, sync::Arc};
+
+use iridium_reflect::StableTypeId;
+
+use super::Component;
+use crate::{BorrowGuard, ComponentTicks, Mut, Ref, SystemTicks};
+
+/// A reference to a component stored in `Entities`.
+///
+/// This is the borrowed version of `ComponentBox`,
+/// and points into an archetype's column instead of owning the component.
+///
+/// It holds a borrow of the column, taken when it was created,
+/// which is mutable if the component was asked for mutably.
+/// The borrow is released when every reference using it is dropped.
+///
+/// Getting the component mutably marks it as changed.
+pub struct ComponentRef<'a> {
+    cell: &'a UnsafeCell<dyn Component>,
+    ticks: &'a ComponentTicks,
+    system_ticks: SystemTicks,
+    /// The borrow of the column this is in.
+    guard: Arc<BorrowGuard<'a>>,
+    /// The stable type id of the component,
+    /// stored so it can be read without borrowing the component.
+    stable_type_id: StableTypeId,
+    /// The type name of the component.
+    type_name: &'static str,
+}
+
+// The column borrow makes sure the component is never aliased mutably.
+unsafe impl Send for ComponentRef<'_> {}
+unsafe impl Sync for ComponentRef<'_> {}
+
+impl<'a> ComponentRef<'a> {
+    /// Creates a new reference from the cell the component is stored in,
+    /// the ticks stored alongside it,
+    /// the ticks of the system using it,
+    /// and the borrow of the column it's stored in.
+    ///
+    /// The borrow must be of the column `cell` is in.
+    pub(crate) const fn new(
+        cell: &'a UnsafeCell<dyn Component>,
+        ticks: &'a ComponentTicks,
+        system_ticks: SystemTicks,
+        guard: Arc<BorrowGuard<'a>>,
+        stable_type_id: StableTypeId,
+        type_name: &'static str,
+    ) -> Self {
+        Self {
+            cell,
+            ticks,
+            system_ticks,
+            guard,
+            stable_type_id,
+            type_name,
+        }
+    }
+
+    /// Gets when the component was added and last changed.
+    #[must_use]
+    pub const fn ticks(&self) -> &'a ComponentTicks {
+        self.ticks
+    }
+
+    /// Whether the component was borrowed mutably.
+    #[must_use]
+    pub fn is_mutable(&self) -> bool {
+        self.guard.is_mutable()
+    }
+
+    /// Gets a reference to the component as `T`.
+    ///
+    /// # Panics
+    ///
+    /// If the component isn't of type `T`.
+    #[must_use]
+    pub fn get<T: Component>(&self) -> &T {
+        self.assert_type::<T>();
+
+        unsafe { &*self.cell.get().cast::<T>() }
+    }
+
+    /// Gets a mutable reference to the component as `T`,
+    /// and marks it as changed.
+    ///
+    /// # Panics
+    ///
+    /// If the component isn't of type `T`, or wasn't borrowed mutably.
+    #[must_use]
+    pub fn get_mut<T: Component>(&mut self) -> &mut T {
+        self.set_changed();
+        self.get_mut_untracked()
+    }
+
+    /// Gets a mutable reference to the component as `T`,
+    /// without marking it as changed.
+    ///
+    /// Useful for caches that aren't part of the component's data.
+    ///
+    /// # Panics
+    ///
+    /// If the component isn't of type `T`, or wasn't borrowed mutably.
+    #[must_use]
+    pub fn get_mut_untracked<T: Component>(&mut self) -> &mut T {
+        self.assert_type::<T>();
+        self.assert_mutable();
+
+        unsafe { &mut *self.cell.get().cast::<T>() }
+    }
+
+    /// Gets a reference to the component as `dyn Component`.
+    #[must_use]
+    pub fn get_trait(&self) -> &dyn Component {
+        unsafe { &*self.cell.get() }
+    }
+
+    /// Gets a mutable reference to the component as `dyn Component`,
+    /// and marks it as changed.
+    ///
+    /// # Panics
+    ///
+    /// If the component wasn't borrowed mutably.
+    #[must_use]
+    pub fn get_trait_mut(&mut self) -> &mut dyn Component {
+        self.assert_mutable();
+        self.set_changed();
+
+        unsafe { &mut *self.cell.get() }
+    }
+
+    /// Turns this into a reference to the component as `T`,
+    /// which keeps the borrow.
+    ///
+    /// # Panics
+    ///
+    /// If the component isn't of type `T`.
+    #[must_use]
+    pub fn into_ref<T: Component>(self) -> Ref<'a, T> {
+        self.assert_type::<T>();
+
+        Ref::new(unsafe { &*self.cell.get().cast::<T>() }, self.guard)
+    }
+
+    /// Turns this into a mutable reference to the component as `T`,
+    /// which keeps the borrow, and marks it as changed.
+    ///
+    /// # Panics
+    ///
+    /// If the component isn't of type `T`, or wasn't borrowed mutably.
+    #[must_use]
+    pub fn into_mut<T: Component>(self) -> Mut<'a, T> {
+        self.assert_type::<T>();
+        self.assert_mutable();
+        self.set_changed();
+
+        Mut::new(unsafe { &mut *self.cell.get().cast::<T>() }, self.guard)
+    }
+
+    /// Marks the component as changed.
+    pub fn set_changed(&self) {
+        self.ticks.set_changed(self.system_ticks.current);
+    }
+
+    /// Checks if the component was added since the system last ran.
+    #[must_use]
+    pub fn is_added(&self) -> bool {
+        self.ticks.is_added(self.system_ticks)
+    }
+
+    /// Checks if the component was changed since the system last ran.
+    #[must_use]
+    pub fn is_changed(&self) -> bool {
+        self.ticks.is_changed(self.system_ticks)
+    }
+
+    /// Gets the stable type id of the underlying component.
+    #[must_use]
+    pub const fn stable_type_id(&self) -> StableTypeId {
+        self.stable_type_id
+    }
+
+    /// Checks if the component is of the given type.
+    #[must_use]
+    pub fn is_type<T: Component>(&self) -> bool {
+        self.stable_type_id() == T::stable_type_id()
+    }
+
+    /// Gets the type name of the underlying component.
+    #[must_use]
+    pub const fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// Panics if the component isn't of type `T`.
+    fn assert_type<T: Component>(&self) {
+        assert!(
+            self.is_type::<T>(),
+            "ComponentRef of {} used as {}",
+            self.type_name,
+            T::type_name()
+        );
+    }
+
+    /// Panics if the component wasn't borrowed mutably.
+    fn assert_mutable(&self) {
+        assert!(
+            self.is_mutable(),
+            "{} was borrowed immutably, so can't be changed",
+            self.type_name
+        );
+    }
+}
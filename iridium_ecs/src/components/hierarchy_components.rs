@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+
+use iridium_assets::Assets;
+use iridium_ecs_macros::{Component, ComponentStorage, HasStableTypeId};
+use iridium_maths::VecN;
+
+use crate::{
+    storage::{ComponentStorage, StoredComponent},
+    ui::InspectorUi,
+    Entity, Transform,
+};
+
+/// The parent of an entity.
+///
+/// The entity's `Transform` is relative to its parent's.
+///
+/// This is managed by `Entities`, use `Entities::set_parent` to change it,
+/// so the parent's `Children` stay consistent.
+#[derive(Component, ComponentStorage, HasStableTypeId)]
+pub struct Parent {
+    /// The parent entity.
+    ///
+    /// After loading, this isn't valid until `Entities::resolve_parents` is called.
+    #[temporary(crate::Entity::from_raw_parts(u32::MAX, 0))]
+    entity: Entity,
+    /// The persistent id of the parent,
+    /// which is what gets saved.
+    #[string]
+    persistent_id: u128,
+}
+
+impl Parent {
+    /// Creates a parent component.
+    pub(crate) const fn new(entity: Entity, persistent_id: u128) -> Self {
+        Self {
+            entity,
+            persistent_id,
+        }
+    }
+
+    /// The parent entity.
+    #[must_use]
+    pub const fn entity(&self) -> Entity {
+        self.entity
+    }
+
+    /// The persistent id of the parent entity.
+    #[must_use]
+    pub const fn persistent_id(&self) -> u128 {
+        self.persistent_id
+    }
+}
+
+impl InspectorUi for Parent {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("entity");
+        ui.label(self.entity.to_string());
+        ui.end_row();
+    }
+}
+
+/// The children of an entity.
+///
+/// This is managed by `Entities`, and kept consistent with the children's `Parent`.
+///
+/// It isn't saved, as it's rebuilt from the `Parent`s when loading.
+#[derive(Component, HasStableTypeId, Default)]
+pub struct Children {
+    /// The child entities, in the order they were added.
+    entities: Vec<Entity>,
+}
+
+impl Children {
+    /// The child entities, in the order they were added.
+    #[must_use]
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    /// Adds a child, if it isn't already one.
+    pub(crate) fn push(&mut self, entity: Entity) {
+        if !self.entities.contains(&entity) {
+            self.entities.push(entity);
+        }
+    }
+
+    /// Removes a child.
+    pub(crate) fn remove(&mut self, entity: Entity) {
+        self.entities.retain(|child| *child != entity);
+    }
+}
+
+impl ComponentStorage for Children {
+    fn from_stored(_stored: StoredComponent, _assets: &Assets) -> Option<Self> {
+        Some(Self::default())
+    }
+
+    fn to_stored(&self) -> StoredComponent {
+        StoredComponent {
+            type_name: "Children".to_string(),
+            fields: HashMap::new(),
+        }
+    }
+
+    fn is_stored(&self) -> bool {
+        false
+    }
+}
+
+impl InspectorUi for Children {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        for (index, entity) in self.entities.iter().enumerate() {
+            ui.label(index.to_string());
+            ui.label(entity.to_string());
+            ui.end_row();
+        }
+    }
+}
+
+/// The position, scale and rotation of an entity in world space.
+///
+/// This is added automatically to every entity with a `Transform`,
+/// and computed from the `Transform`s of it and its ancestors
+/// while the systems run, see `TRANSFORM_PROPAGATION_SYSTEM`.
+///
+/// It isn't saved, and changing it has no effect.
+///
+/// It also keeps where the entity was before the last fixed step that moved it,
+/// so the renderer can blend between the two with `interpolated`.
+#[derive(Component, HasStableTypeId, Clone, Copy, Debug, PartialEq)]
+pub struct GlobalTransform {
+    /// The position.
+    pub position: VecN<3>,
+    /// The scale.
+    pub scale: VecN<3>,
+    /// The rotation.
+    ///
+    /// This is in radians.
+    pub rotation: f32,
+    /// The position, scale and rotation before the last fixed step.
+    ///
+    /// This is `None` if the entity was last moved outside a fixed step,
+    /// so it isn't interpolated.
+    previous: Option<(VecN<3>, VecN<3>, f32)>,
+}
+
+impl GlobalTransform {
+    /// Computes the global transform of a child with the given local transform.
+    ///
+    /// The child is scaled, rotated then moved by this transform,
+    /// the same way the renderer places vertices.
+    ///
+    /// Rotated children of non-uniformly scaled parents would need skewing,
+    /// which can't be represented, so they keep their shape instead.
+    #[must_use]
+    pub fn child_transform(&self, local: &Transform) -> Self {
+        let sin = self.rotation.sin();
+        let cos = self.rotation.cos();
+
+        let x = local.position.x() * self.scale.x();
+        let y = local.position.y() * self.scale.y();
+        let z = local.position.z() * self.scale.z();
+
+        Self {
+            position: self.position
+                + VecN::new([x.mul_add(cos, y * sin), y.mul_add(cos, -x * sin), z]),
+            scale: VecN::new([
+                self.scale.x() * local.scale.x(),
+                self.scale.y() * local.scale.y(),
+                self.scale.z() * local.scale.z(),
+            ]),
+            rotation: self.rotation + local.rotation,
+            previous: None,
+        }
+    }
+
+    /// Blends from where the entity was before the last fixed step to where it is now.
+    ///
+    /// `alpha` is `FixedTime::alpha`, from 0 for before the step to 1 for now.
+    /// Entities last moved outside a fixed step aren't blended.
+    #[must_use]
+    pub fn interpolated(&self, alpha: f32) -> Self {
+        let Some((position, scale, rotation)) = self.previous else {
+            return *self;
+        };
+
+        Self {
+            position: position + (self.position - position) * alpha,
+            scale: scale + (self.scale - scale) * alpha,
+            rotation: (self.rotation - rotation).mul_add(alpha, rotation),
+            previous: None,
+        }
+    }
+
+    /// The position, scale and rotation, without where it was before.
+    pub(crate) const fn placement(&self) -> (VecN<3>, VecN<3>, f32) {
+        (self.position, self.scale, self.rotation)
+    }
+
+    /// Remembers the current placement as where it was before the next fixed step.
+    pub(crate) fn start_fixed_step(&mut self) {
+        self.previous = Some(self.placement());
+    }
+
+    /// Sets the placement, keeping where it was before the fixed step
+    /// if it's being moved by one.
+    pub(crate) fn move_to(&mut self, global_transform: Self, in_fixed_step: bool) {
+        let previous = if in_fixed_step { self.previous } else { None };
+        *self = Self {
+            previous,
+            ..global_transform
+        };
+    }
+}
+
+impl Default for GlobalTransform {
+    fn default() -> Self {
+        Self::from(&Transform::default())
+    }
+}
+
+impl From<&Transform> for GlobalTransform {
+    fn from(transform: &Transform) -> Self {
+        Self {
+            position: transform.position,
+            scale: transform.scale,
+            rotation: transform.rotation,
+            previous: None,
+        }
+    }
+}
+
+impl ComponentStorage for GlobalTransform {
+    fn from_stored(_stored: StoredComponent, _assets: &Assets) -> Option<Self> {
+        Some(Self::default())
+    }
+
+    fn to_stored(&self) -> StoredComponent {
+        StoredComponent {
+            type_name: "GlobalTransform".to_string(),
+            fields: HashMap::new(),
+        }
+    }
+
+    fn is_stored(&self) -> bool {
+        false
+    }
+}
+
+impl InspectorUi for GlobalTransform {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("position");
+        ui.label(self.position.to_string());
+        ui.end_row();
+
+        ui.label("scale");
+        ui.label(self.scale.to_string());
+        ui.end_row();
+
+        ui.label("rotation");
+        ui.label(self.rotation.to_string());
+        ui.end_row();
+    }
+}
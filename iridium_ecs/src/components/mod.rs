@@ -4,6 +4,9 @@ pub use component_box::*;
 mod basic_components;
 pub use basic_components::*;
 
+mod hierarchy_components;
+pub use hierarchy_components::*;
+
 mod component_info;
 pub use component_info::*;
 
@@ -12,3 +15,6 @@ pub use component::*;
 
 mod component_default;
 pub use component_default::*;
+
+mod component_ref;
+pub use component_ref::*;
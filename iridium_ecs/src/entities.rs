@@ -1,62 +1,134 @@
 #![allow(clippy::mut_from_ref)]
 
-use std::sync::mpsc;
+use std::sync::{mpsc, Mutex, PoisonError};
 
 use iridium_assets::Assets;
 
 use crate::ComponentDefault;
 use iridium_reflect::{HasStableTypeId, StableTypeId};
 
-use super::{Component, ComponentBox, ComponentInfo, Name, Transform};
+use super::{
+    Access, Archetype, ArchetypeId, Children, ColumnBorrow, Commands, Component, ComponentBox,
+    ComponentInfo, ComponentRef, ComponentTicks, Entity, EntityLocation, EntityMeta,
+    GlobalTransform, Mut, Name, Parent, PrefabInstance, QueryFilter, SceneOrigin, SystemScope,
+    SystemTicks, Transform,
+};
 use std::collections::HashMap;
 
 /// A command created in a system, to be ran
 /// by `Entities` at the end of each stage.
+///
+/// `Commands` is an easier way to send these.
 pub enum EntityCommand {
-    /// Delete the entity with the given ID.
-    DeleteEntity(u128),
-    /// Create an entity, maybe with the given id.
+    /// Delete the given entity and all its descendants.
+    DeleteEntity(Entity),
+    /// Create an entity, maybe with the given persistent id.
     NewEntity(Option<u128>, String, Vec<ComponentBox>),
+    /// Add components to an entity,
+    /// skipping any it already has.
+    AddComponents(Entity, Vec<ComponentBox>),
+    /// Remove components from an entity by their type.
+    RemoveComponents(Entity, Vec<StableTypeId>),
+    /// Replace a component of an entity,
+    /// adding it if the entity doesn't have it.
+    ReplaceComponent(Entity, ComponentBox),
+    /// Change the name of an entity.
+    RenameEntity(Entity, String),
+    /// Delete an entity and all its descendants.
+    ///
+    /// The same as `DeleteEntity`.
+    DespawnRecursive(Entity),
+    /// Set the parent of an entity, or remove it if `None`.
+    SetParent(Entity, Option<Entity>),
+}
+
+impl EntityCommand {
+    /// The entity the command acts on,
+    /// `None` if it creates a new entity.
+    #[must_use]
+    pub const fn target(&self) -> Option<Entity> {
+        match self {
+            Self::NewEntity(..) => None,
+            Self::DeleteEntity(entity)
+            | Self::AddComponents(entity, _)
+            | Self::RemoveComponents(entity, _)
+            | Self::ReplaceComponent(entity, _)
+            | Self::RenameEntity(entity, _)
+            | Self::DespawnRecursive(entity)
+            | Self::SetParent(entity, _) => Some(*entity),
+        }
+    }
 }
 
 /// Stores all the entities in the scene.
+///
+/// Components are stored in archetypes,
+/// which group entities with the same set of component types,
+/// and store each component type contiguously.
 pub struct Entities {
-    /// entity_id => components
-    entities: HashMap<u128, Vec<StableTypeId>>,
-    /// component_type => entity_id => component
-    components: HashMap<StableTypeId, HashMap<u128, ComponentBox>>,
+    /// entity index => info about the entity
+    metas: Vec<EntityMeta>,
+    /// Indices of deleted entities, to be reused.
+    free_indices: Vec<u32>,
+    /// persistent id => entity
+    persistent_ids: HashMap<u128, Entity>,
+    /// All the archetypes.
+    ///
+    /// These are never removed, only emptied,
+    /// so `ArchetypeId`s stay valid.
+    archetypes: Vec<Archetype>,
+    /// sorted component types => archetype
+    archetype_ids: HashMap<Vec<StableTypeId>, ArchetypeId>,
+    /// component_type => archetypes with that component
+    component_archetypes: HashMap<StableTypeId, Vec<ArchetypeId>>,
     /// Stores info about components.
     component_info: HashMap<StableTypeId, ComponentInfo>,
+    /// The current tick, and the tick the running systems last ran at.
+    ///
+    /// Components store the ticks they were added and changed at,
+    /// which are compared against these for change detection.
+    system_ticks: SystemTicks,
 
-    /// The mpsc receiver for the entity commands.
-    cmd_queue: mpsc::Receiver<EntityCommand>,
+    /// The mpsc receiver for the entity commands,
+    /// along with the name of what sent them.
+    ///
+    /// This is in a `Mutex` as `mpsc::Receiver` isn't `Sync`,
+    /// but it's only used through `&mut self`.
+    cmd_queue: Mutex<mpsc::Receiver<(&'static str, EntityCommand)>>,
     /// The mpsc sender for the entity commands.
-    cmd_sender: mpsc::Sender<EntityCommand>,
+    cmd_sender: mpsc::Sender<(&'static str, EntityCommand)>,
 }
 
-// The only blocker for this being automatically implemented
-// is cmd_sender not being Sync.
-//
-// I think this is safe, as I only ever clone it on other threads,
-// and don't send with it.
-unsafe impl Sync for Entities {}
-
 impl Default for Entities {
     fn default() -> Self {
         let (cmd_sender, cmd_queue) = mpsc::channel();
 
         // Create Entities.
         let mut entities = Self {
-            entities: HashMap::new(),
-            components: HashMap::new(),
+            metas: Vec::new(),
+            free_indices: Vec::new(),
+            persistent_ids: HashMap::new(),
+            archetypes: Vec::new(),
+            archetype_ids: HashMap::new(),
+            component_archetypes: HashMap::new(),
             component_info: HashMap::new(),
-            cmd_queue,
+            // Before anything has run, everything counts as changed.
+            system_ticks: SystemTicks {
+                last_run: u32::MAX,
+                current: 0,
+            },
+            cmd_queue: Mutex::new(cmd_queue),
             cmd_sender,
         };
 
         // Register the default components.
         entities.register_component::<Name>();
+        entities.register_component::<PrefabInstance>();
+        entities.register_component::<SceneOrigin>();
         entities.register_component_with_default::<Transform>();
+        entities.register_component::<Parent>();
+        entities.register_component::<Children>();
+        entities.register_component::<GlobalTransform>();
 
         entities
     }
@@ -65,8 +137,23 @@ impl Default for Entities {
 impl Entities {
     /// Deletes all entities and components from a scene.
     pub fn clear(&mut self) {
-        self.entities.clear();
-        self.components.clear();
+        // Every index is freed, but the generations are kept,
+        // so old handles are still detected as deleted.
+        self.free_indices.clear();
+        for (index, meta) in self.metas.iter_mut().enumerate().rev() {
+            if meta.location.take().is_some() {
+                meta.generation += 1;
+            }
+            self.free_indices
+                .push(u32::try_from(index).expect("Too many entities"));
+        }
+        self.persistent_ids.clear();
+
+        // The archetypes are kept,
+        // as the next scene will likely use the same ones.
+        for archetype in &mut self.archetypes {
+            archetype.clear();
+        }
     }
 
     /// Updates all the assets on all the components.
@@ -77,72 +164,273 @@ impl Entities {
     pub fn update_assets(&self, assets: &Assets) -> Result<i32, String> {
         puffin::profile_function!();
 
-        Ok(self
-            .components
-            .values()
-            .map(|component_map| {
-                component_map
-                    .values()
-                    .map(|component| component.get_trait_mut().update_assets(assets))
-                    // This gets the first err if found
-                    .collect::<Result<Vec<i32>, String>>()
+        let mut updated = 0;
+
+        for archetype in &self.archetypes {
+            for column in archetype.columns() {
+                let column = ColumnBorrow::new(column, true);
+
+                for row in 0..archetype.len() {
+                    // This early returns on the first error.
+                    updated += column
+                        .get(row, self.system_ticks())
+                        .get_trait_mut()
+                        .update_assets(assets)?;
+                }
+            }
+        }
+
+        Ok(updated)
+    }
+
+    /// The current tick.
+    ///
+    /// Components added or changed now are given this tick.
+    #[must_use]
+    pub const fn change_tick(&self) -> u32 {
+        self.system_ticks.current
+    }
+
+    /// The current tick, and the tick the running system last ran at.
+    ///
+    /// This is the last run tick of the `SystemScope` on this thread if it has one,
+    /// so each system sees the changes since it last ran.
+    #[must_use]
+    pub fn system_ticks(&self) -> SystemTicks {
+        SystemTicks {
+            last_run: SystemScope::last_run_tick().unwrap_or(self.system_ticks.last_run),
+            current: self.system_ticks.current,
+        }
+    }
+
+    /// Sets the tick the running systems last ran at,
+    /// for when no `SystemScope` with a last run tick is entered.
+    ///
+    /// Components changed after this tick count as changed.
+    pub fn set_last_run_tick(&mut self, tick: u32) {
+        self.system_ticks.last_run = tick;
+    }
+
+    /// Moves onto the next tick.
+    ///
+    /// Only changes made since the previous tick count as changed after this,
+    /// unless `set_last_run_tick` is used.
+    ///
+    /// This is called after each stage by `World::run_systems`.
+    pub fn advance_tick(&mut self) {
+        self.system_ticks.last_run = self.system_ticks.current;
+        self.system_ticks.current = self.system_ticks.current.wrapping_add(1);
+    }
+
+    /// Checks if an entity's component was added since the running system last ran.
+    ///
+    /// Returns false if the entity doesn't have the component.
+    #[must_use]
+    pub fn is_added<T: Component>(&self, entity: Entity) -> bool {
+        self.entity_component(entity, T::stable_type_id())
+            .is_some_and(|component| component.is_added())
+    }
+
+    /// Checks if an entity's component was changed since the running system last ran.
+    ///
+    /// Returns false if the entity doesn't have the component.
+    #[must_use]
+    pub fn is_changed<T: Component>(&self, entity: Entity) -> bool {
+        self.entity_component(entity, T::stable_type_id())
+            .is_some_and(|component| component.is_changed())
+    }
+
+    /// Gets a component of an entity.
+    ///
+    /// The component's column is borrowed immutably until the reference is dropped.
+    ///
+    /// # Panics
+    ///
+    /// If the column is borrowed mutably.
+    #[must_use]
+    pub fn entity_component(
+        &self,
+        entity: Entity,
+        component_type: StableTypeId,
+    ) -> Option<ComponentRef<'_>> {
+        self.entity_component_with_access(entity, Access::Read(component_type))
+    }
+
+    /// Gets a component of an entity mutably.
+    ///
+    /// The component's column is borrowed mutably until the reference is dropped.
+    ///
+    /// # Panics
+    ///
+    /// If the column is already borrowed.
+    #[must_use]
+    pub fn entity_component_mut(
+        &self,
+        entity: Entity,
+        component_type: StableTypeId,
+    ) -> Option<ComponentRef<'_>> {
+        self.entity_component_with_access(entity, Access::Write(component_type))
+    }
+
+    /// Gets a component of an entity, borrowing its column with the given access.
+    fn entity_component_with_access(
+        &self,
+        entity: Entity,
+        access: Access,
+    ) -> Option<ComponentRef<'_>> {
+        let location = self.entity_location(entity)?;
+
+        self.archetypes[location.archetype]
+            .column(access.type_id())
+            .map(|column| {
+                ColumnBorrow::new(column, access.is_write()).get(location.row, self.system_ticks())
             })
-            // This gets the first err if found
-            // And then early returns if err
-            .collect::<Result<Vec<Vec<i32>>, String>>()?
-            .into_iter()
-            .flatten()
-            .sum::<i32>())
     }
 
     /// Send an `EntityCommand`.
     pub fn send_cmd(&self, cmd: EntityCommand) {
-        self.cmd_sender
-            // I clone it because `mpsc::Sender` is not Sync, only Send
-            .clone()
-            .send(cmd)
-            .unwrap_or_else(|_| {
-                unreachable!("Reciever can't have been dropped as it's in the same struct")
-            });
+        self.send_cmd_from("", cmd);
+    }
+
+    /// Send an `EntityCommand` from a system or something else with a name.
+    ///
+    /// The source decides the order commands are processed in.
+    pub fn send_cmd_from(&self, source: &'static str, cmd: EntityCommand) {
+        self.cmd_sender.send((source, cmd)).unwrap_or_else(|_| {
+            unreachable!("Reciever can't have been dropped as it's in the same struct")
+        });
+    }
+
+    /// Gets a `Commands` to send commands from the given source,
+    /// which is usually the name of the system.
+    #[must_use]
+    pub const fn commands(&self, source: &'static str) -> Commands<'_> {
+        Commands::new(self, source)
     }
 
     /// Process the commands in the command queue.
+    ///
+    /// Systems in a stage run in parallel, so commands arrive in any order.
+    /// To keep this deterministic, they are sorted by their source, then the entity they act on.
+    /// Commands with the same source and entity are processed in the order they were sent.
     pub fn process_commands(&mut self) {
-        while let Ok(cmd) = self.cmd_queue.try_recv() {
-            match cmd {
-                EntityCommand::DeleteEntity(entity_id) => {
-                    self.delete_entity(entity_id);
-                }
-                EntityCommand::NewEntity(id, name, components) => {
-                    self.new_entity(id, &name, components);
-                }
+        let mut cmds = self
+            .cmd_queue
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .try_iter()
+            .collect::<Vec<_>>();
+
+        // This is a stable sort, so keeps the send order when equal.
+        cmds.sort_by_key(|(source, cmd)| (*source, cmd.target()));
+
+        for (_, cmd) in cmds {
+            self.process_command(cmd);
+        }
+    }
+
+    /// Process a single command.
+    fn process_command(&mut self, cmd: EntityCommand) {
+        match cmd {
+            EntityCommand::DeleteEntity(entity) => {
+                self.delete_entity(entity);
+            }
+            EntityCommand::NewEntity(id, name, components) => {
+                self.new_entity(id, &name, components);
+            }
+            EntityCommand::AddComponents(entity, components) => {
+                self.add_components(entity, components);
+            }
+            EntityCommand::RemoveComponents(entity, component_types) => {
+                self.remove_components(entity, &component_types);
+            }
+            EntityCommand::ReplaceComponent(entity, component) => {
+                self.replace_component(entity, component);
+            }
+            EntityCommand::RenameEntity(entity, name) => {
+                self.rename_entity(entity, name);
+            }
+            EntityCommand::DespawnRecursive(entity) => {
+                self.delete_entity(entity);
+            }
+            EntityCommand::SetParent(entity, parent) => {
+                self.set_parent(entity, parent);
             }
         }
     }
 
-    /// Delete an entity and all its components.
+    /// Delete an entity, all its descendants, and all their components.
     ///
     /// Returns true if the entity was deleted,
     /// false if it didn't exist.
-    pub fn delete_entity(&mut self, entity_id: u128) -> bool {
-        // Remove the entity from the entities list,
-        // and get the components it had.
-        let Some(component_types) = self.entities.remove(&entity_id) else {
+    pub fn delete_entity(&mut self, entity: Entity) -> bool {
+        for child in self.children(entity) {
+            self.delete_entity(child);
+        }
+
+        self.delete_entity_keep_children(entity)
+    }
+
+    /// Delete an entity and all its components, but not its children.
+    ///
+    /// Its children are kept, and become root entities.
+    ///
+    /// Returns true if the entity was deleted,
+    /// false if it didn't exist.
+    pub fn delete_entity_keep_children(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
             return false;
-        };
+        }
 
-        // For each component the entity had,
-        // remove it from the components list.
-        for component_type in component_types {
-            self.components
-                .get_mut(&component_type)
-                .expect("Invalid component type in entity while deleting")
-                .remove(&entity_id);
+        // Remove it from the hierarchy.
+        self.unlink_from_parent(entity);
+        for child in self.children(entity) {
+            self.take_components(child, &[Parent::stable_type_id()]);
         }
 
+        // Get where its components are,
+        // this is after the hierarchy is updated as that can move it.
+        let Some(location) = self.entity_location(entity) else {
+            return false;
+        };
+
+        // Free the index, and increase the generation
+        // so existing handles become invalid.
+        let meta = &mut self.metas[entity.index() as usize];
+        meta.location = None;
+        meta.generation += 1;
+        self.persistent_ids.remove(&meta.persistent_id);
+        self.free_indices.push(entity.index());
+
+        // Remove the components from the archetype,
+        // dropping them here.
+        self.take_row(location);
+
         true
     }
 
+    /// Checks whether an entity handle still refers to a living entity.
+    #[must_use]
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entity_location(entity).is_some()
+    }
+
+    /// Gets the persistent id of an entity.
+    ///
+    /// This is the id used in scene files,
+    /// and stays the same between runs.
+    #[must_use]
+    pub fn persistent_id(&self, entity: Entity) -> Option<u128> {
+        self.is_alive(entity)
+            .then(|| self.metas[entity.index() as usize].persistent_id)
+    }
+
+    /// Gets an entity from its persistent id.
+    #[must_use]
+    pub fn entity_from_persistent_id(&self, persistent_id: u128) -> Option<Entity> {
+        self.persistent_ids.get(&persistent_id).copied()
+    }
+
     /// Gets `ComponentInfo` from the component type.
     #[must_use]
     pub fn component_info<T: Component>(&self) -> Option<&ComponentInfo> {
@@ -167,23 +455,51 @@ impl Entities {
     /// Gets the number of entities with a given component.
     #[must_use]
     pub fn entity_count<T: Component>(&self) -> usize {
-        self.components
+        self.component_archetypes
             .get(&T::stable_type_id())
-            .map_or(0, HashMap::len)
+            .map_or(0, |archetypes| {
+                archetypes
+                    .iter()
+                    .map(|&archetype| self.archetypes[archetype].len())
+                    .sum()
+            })
     }
 
-    /// Gets an entity id from its name.
+    /// Gets an entity from its name.
     #[must_use]
-    pub fn entity_id_from_name(&self, name: &str) -> Option<u128> {
+    pub fn entity_from_name(&self, name: &str) -> Option<Entity> {
         self.query_by_type_id([Name::stable_type_id()])
             .find(|(_, [name_component])| name_component.get::<Name>().name == name)
-            .map(|(id, _)| id)
+            .map(|(entity, _)| entity)
+    }
+
+    /// Gets a vec of all living entities.
+    #[must_use]
+    pub fn all_entities(&self) -> Vec<Entity> {
+        self.archetypes
+            .iter()
+            .flat_map(|archetype| archetype.entities().iter().copied())
+            .collect()
+    }
+
+    /// Gets all the archetypes.
+    ///
+    /// Some of these may be empty.
+    #[must_use]
+    pub fn archetypes(&self) -> &[Archetype] {
+        &self.archetypes
     }
 
-    /// Gets a vec of all entity ids.
+    /// Gets where an entity's components are stored.
     #[must_use]
-    pub fn entity_ids(&self) -> Vec<u128> {
-        self.entities.keys().copied().collect()
+    pub fn entity_location(&self, entity: Entity) -> Option<EntityLocation> {
+        let meta = self.metas.get(entity.index() as usize)?;
+
+        if meta.generation == entity.generation() {
+            meta.location
+        } else {
+            None
+        }
     }
 
     /// Registers a component type.
@@ -204,6 +520,22 @@ impl Entities {
             .insert(T::stable_type_id(), component_info);
     }
 
+    /// Unregisters a component type, returning its info.
+    ///
+    /// Existing components of this type are kept,
+    /// but it can't be loaded from a file or added in the editor anymore.
+    pub fn unregister_component<T: Component>(&mut self) -> Option<ComponentInfo> {
+        self.unregister_component_by_type_id(T::stable_type_id())
+    }
+
+    /// Unregisters a component type by its type id, returning its info.
+    pub fn unregister_component_by_type_id(
+        &mut self,
+        component_type: StableTypeId,
+    ) -> Option<ComponentInfo> {
+        self.component_info.remove(&component_type)
+    }
+
     /// Get a vec of component names and their factories.
     #[allow(clippy::type_complexity)]
     #[must_use]
@@ -211,247 +543,585 @@ impl Entities {
         &self,
     ) -> Vec<(&'static str, fn(&Assets) -> Result<ComponentBox, String>)> {
         self.component_info
-            .iter()
-            .filter_map(|(_, info)| Some((info.type_name, info.default?)))
+            .values()
+            .filter_map(|info| Some((info.type_name, info.default?)))
             .collect::<Vec<_>>()
     }
 
+    /// Removes the entity in the given location from its archetype,
+    /// and returns its components with their ticks.
+    fn take_row(&mut self, location: EntityLocation) -> Vec<(ComponentBox, ComponentTicks)> {
+        let (components, moved) = self.archetypes[location.archetype].swap_remove(location.row);
+
+        // The last entity in the archetype was moved into this row.
+        if let Some(moved) = moved {
+            self.metas[moved.index() as usize].location = Some(location);
+        }
+
+        components
+    }
+
+    /// Gets the archetype that stores exactly the given components,
+    /// creating it if it doesn't exist.
+    fn archetype_with(&mut self, components: &[(ComponentBox, ComponentTicks)]) -> ArchetypeId {
+        let mut types = components
+            .iter()
+            .map(|(component, _)| component.stable_type_id())
+            .collect::<Vec<_>>();
+        types.sort_unstable();
+
+        if let Some(&archetype) = self.archetype_ids.get(&types) {
+            return archetype;
+        }
+
+        let archetype = self.archetypes.len();
+
+        self.archetypes.push(Archetype::new(
+            components
+                .iter()
+                .map(|(component, _)| {
+                    (
+                        component.stable_type_id(),
+                        component.get_trait().empty_column(),
+                    )
+                })
+                .collect(),
+        ));
+
+        for component_type in &types {
+            self.component_archetypes
+                .entry(*component_type)
+                .or_default()
+                .push(archetype);
+        }
+
+        self.archetype_ids.insert(types, archetype);
+
+        archetype
+    }
+
     /// Add components to an entity.
-    pub fn add_components(&mut self, entity_id: u128, components: Vec<ComponentBox>) {
-        // Get the vec of components the entities has.
-        // If it doesn't exist, add it.
-        let entity = self.entities.entry(entity_id).or_default();
+    ///
+    /// A `Parent` sets the parent to the entity with its persistent id, like `set_parent`,
+    /// if the entity doesn't have one already.
+    ///
+    /// Returns false if the entity doesn't exist.
+    pub fn add_components(&mut self, entity: Entity, mut components: Vec<ComponentBox>) -> bool {
+        let parent = Self::take_parent(&mut components);
+        if !self.add_components_unlinked(entity, components) {
+            return false;
+        }
+
+        // Like the other components, an existing parent isn't replaced.
+        if self.parent(entity).is_none() {
+            self.link_parent(entity, parent);
+        }
+
+        true
+    }
+
+    /// Add components to an entity,
+    /// without keeping the hierarchy consistent.
+    ///
+    /// Returns false if the entity doesn't exist.
+    pub(crate) fn add_components_unlinked(
+        &mut self,
+        entity: Entity,
+        components: Vec<ComponentBox>,
+    ) -> bool {
+        let Some(location) = self.entity_location(entity) else {
+            return false;
+        };
+
+        // If every component is already added,
+        // there's no need to move it.
+        let existing_types = self.archetypes[location.archetype].types();
+        if components
+            .iter()
+            .all(|component| existing_types.contains(&component.stable_type_id()))
+        {
+            return true;
+        }
+
+        // Take the components the entity has out of its archetype.
+        let mut entity_components = self.take_row(location);
 
         // For each component to be added.
         for component in components {
             // If the component is already added, continue.
-            if entity.contains(&component.stable_type_id()) {
+            if entity_components
+                .iter()
+                .any(|(existing, _)| existing.stable_type_id() == component.stable_type_id())
+            {
                 continue;
             }
 
-            // Add to entities.
-            entity.push(component.stable_type_id());
+            entity_components.push((component, ComponentTicks::new(self.change_tick())));
+        }
+
+        // Move the entity into its new archetype.
+        self.insert_row(entity, entity_components);
+
+        true
+    }
+
+    /// Remove components from an entity by their type.
+    ///
+    /// Types the entity doesn't have are ignored,
+    /// and `Name` is never removed, as every entity needs one.
+    ///
+    /// Removing `Parent` or `Children` also updates the other side of the hierarchy.
+    ///
+    /// Returns the removed components, or `None` if the entity doesn't exist.
+    pub fn remove_components(
+        &mut self,
+        entity: Entity,
+        component_types: &[StableTypeId],
+    ) -> Option<Vec<ComponentBox>> {
+        if !self.is_alive(entity) {
+            return None;
+        }
 
-            // Get the hashmap of type_id => component.
-            self.components
-                .entry(component.stable_type_id())
-                // If it doesn't exist, add it.
-                .or_insert_with(HashMap::new)
-                // Add this component to the hashmap.
-                .insert(entity_id, component);
+        if component_types.contains(&Parent::stable_type_id()) {
+            self.unlink_from_parent(entity);
+        }
+        if component_types.contains(&Children::stable_type_id()) {
+            for child in self.children(entity) {
+                self.take_components(child, &[Parent::stable_type_id()]);
+            }
         }
+
+        self.take_components(entity, component_types)
     }
 
-    /// Creates a new entity with the given components and id.
+    /// Remove components from an entity by their type,
+    /// without keeping the hierarchy consistent.
     ///
-    /// If id is none, a new id is generated.
-    /// Returns the id of the new entity.
+    /// Returns the removed components, or `None` if the entity doesn't exist.
+    pub(crate) fn take_components(
+        &mut self,
+        entity: Entity,
+        component_types: &[StableTypeId],
+    ) -> Option<Vec<ComponentBox>> {
+        let location = self.entity_location(entity)?;
+
+        // If the entity doesn't have any of the components,
+        // there's no need to move it.
+        let archetype = &self.archetypes[location.archetype];
+        if !component_types.iter().any(|component_type| {
+            *component_type != Name::stable_type_id() && archetype.has_type(*component_type)
+        }) {
+            return Some(Vec::new());
+        }
+
+        // Split the components into the ones to keep and remove.
+        let (removed, kept): (Vec<_>, Vec<_>) =
+            self.take_row(location)
+                .into_iter()
+                .partition(|(component, _)| {
+                    component.stable_type_id() != Name::stable_type_id()
+                        && component_types.contains(&component.stable_type_id())
+                });
+
+        // Move the entity into its new archetype.
+        self.insert_row(entity, kept);
+
+        Some(
+            removed
+                .into_iter()
+                .map(|(component, _)| component)
+                .collect(),
+        )
+    }
+
+    /// Remove a component from an entity.
+    ///
+    /// Returns the removed component,
+    /// or `None` if the entity doesn't exist or doesn't have it.
+    pub fn remove_component<T: Component>(&mut self, entity: Entity) -> Option<ComponentBox> {
+        self.remove_component_by_type_id(entity, T::stable_type_id())
+    }
+
+    /// Remove a component from an entity by its type id.
+    ///
+    /// Returns the removed component,
+    /// or `None` if the entity doesn't exist or doesn't have it.
+    pub fn remove_component_by_type_id(
+        &mut self,
+        entity: Entity,
+        component_type: StableTypeId,
+    ) -> Option<ComponentBox> {
+        self.remove_components(entity, &[component_type])?.pop()
+    }
+
+    /// Replace a component of an entity with one of the same type,
+    /// adding it if the entity doesn't have it.
+    ///
+    /// A `Parent` sets the parent to the entity with its persistent id, like `set_parent`.
+    ///
+    /// Returns the old component, if there was one.
+    pub fn replace_component(
+        &mut self,
+        entity: Entity,
+        component: ComponentBox,
+    ) -> Option<ComponentBox> {
+        if !component.is_type::<Parent>() {
+            return self.replace_component_unlinked(entity, component);
+        }
+
+        let persistent_id = component.get::<Parent>().persistent_id();
+        let old = self
+            .remove_components(entity, &[Parent::stable_type_id()])?
+            .pop();
+        self.link_parent(entity, Some(persistent_id));
+        old
+    }
+
+    /// Replace a component of an entity like `replace_component`,
+    /// without keeping the hierarchy consistent.
+    pub(crate) fn replace_component_unlinked(
+        &mut self,
+        entity: Entity,
+        component: ComponentBox,
+    ) -> Option<ComponentBox> {
+        let location = self.entity_location(entity)?;
+        let change_tick = self.change_tick();
+
+        let Some(column) =
+            self.archetypes[location.archetype].column_mut(component.stable_type_id())
+        else {
+            self.add_components_unlinked(entity, vec![component]);
+            return None;
+        };
+
+        Some(column.replace(location.row, component, change_tick))
+    }
+
+    /// Change the name of an entity.
+    ///
+    /// Returns false if the entity doesn't exist.
+    pub fn rename_entity(&mut self, entity: Entity, name: String) -> bool {
+        let Some(mut component) = self.entity_component_mut(entity, Name::stable_type_id()) else {
+            return false;
+        };
+
+        component.get_mut::<Name>().name = name;
+
+        true
+    }
+
+    /// Takes the `Parent` out of components being added,
+    /// returning the persistent id of the parent.
+    fn take_parent(components: &mut Vec<ComponentBox>) -> Option<u128> {
+        let mut parent = None;
+        components.retain(|component| {
+            if !component.is_type::<Parent>() {
+                return true;
+            }
+            // Only the first is used, the same as other duplicate components.
+            parent.get_or_insert_with(|| component.get::<Parent>().persistent_id());
+            false
+        });
+        parent
+    }
+
+    /// Sets the parent of an entity to the entity with the persistent id,
+    /// so the parent's `Children` are updated too.
+    ///
+    /// It's left without a parent if there's no entity with the id.
+    fn link_parent(&mut self, child: Entity, parent: Option<u128>) {
+        if let Some(parent) = parent.and_then(|id| self.entity_from_persistent_id(id)) {
+            self.set_parent(child, Some(parent));
+        }
+    }
+
+    /// Adds an entity's components to the matching archetype,
+    /// and updates its location.
+    ///
+    /// A `GlobalTransform` is added if it has a `Transform`,
+    /// and removed if it doesn't.
+    fn insert_row(&mut self, entity: Entity, mut components: Vec<(ComponentBox, ComponentTicks)>) {
+        let transform = components
+            .iter()
+            .find(|(component, _)| component.is_type::<Transform>())
+            .map(|(component, _)| GlobalTransform::from(&*component.get::<Transform>()));
+        let global_transform = components
+            .iter()
+            .position(|(component, _)| component.is_type::<GlobalTransform>());
+        match (transform, global_transform) {
+            (Some(transform), None) => {
+                components.push((transform.into(), ComponentTicks::new(self.change_tick())))
+            }
+            (None, Some(index)) => {
+                components.swap_remove(index);
+            }
+            _ => {}
+        }
+
+        let archetype = self.archetype_with(&components);
+        let row = self.archetypes[archetype].push(entity, components);
+
+        self.metas[entity.index() as usize].location = Some(EntityLocation { archetype, row });
+    }
+
+    /// Creates a new entity with the given components and persistent id.
+    ///
+    /// If the persistent id is none, a new one is generated.
+    /// If an entity already has the persistent id, it is replaced.
+    /// Returns the handle of the new entity.
     ///
     /// Automatically adds the Name component with the given name.
+    ///
+    /// A `Parent` sets the parent to the entity with its persistent id, like `set_parent`.
     pub fn new_entity(
         &mut self,
-        id: Option<u128>,
+        persistent_id: Option<u128>,
+        name: &str,
+        mut components: Vec<ComponentBox>,
+    ) -> Entity {
+        let parent = Self::take_parent(&mut components);
+        let entity = self.new_entity_unlinked(persistent_id, name, components);
+        self.link_parent(entity, parent);
+        entity
+    }
+
+    /// Creates a new entity like `new_entity`,
+    /// keeping a `Parent` as it is without updating the parent's `Children`.
+    ///
+    /// Loading uses this, as the parent may not be loaded yet,
+    /// then calls `resolve_parents`.
+    pub(crate) fn new_entity_unlinked(
+        &mut self,
+        persistent_id: Option<u128>,
         name: &str,
         components: Vec<ComponentBox>,
-    ) -> u128 {
-        // Generate a new entity id if none is given.
-        let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().as_u128());
+    ) -> Entity {
+        // Generate a new persistent id if none is given.
+        let persistent_id = persistent_id.unwrap_or_else(|| uuid::Uuid::new_v4().as_u128());
 
-        // Add it to entities.
-        self.entities.insert(id, vec![]);
+        // If an entity already has this id, replace it.
+        // Its children are kept, as they may be in another scene.
+        if let Some(existing) = self.entity_from_persistent_id(persistent_id) {
+            self.delete_entity_keep_children(existing);
+        }
+
+        // Reuse a free index if there is one.
+        let entity = if let Some(index) = self.free_indices.pop() {
+            let meta = &mut self.metas[index as usize];
+            meta.persistent_id = persistent_id;
+            Entity::from_raw_parts(index, meta.generation)
+        } else {
+            self.metas.push(EntityMeta {
+                generation: 0,
+                location: None,
+                persistent_id,
+            });
+            Entity::from_raw_parts(
+                u32::try_from(self.metas.len() - 1).expect("Too many entities"),
+                0,
+            )
+        };
+        self.persistent_ids.insert(persistent_id, entity);
 
-        // Add the name component.
-        self.add_components(
-            id,
-            vec![Name {
+        // Add the name component first,
+        // so it isn't replaced by another `Name` in components.
+        let mut entity_components = vec![(
+            ComponentBox::new(Name {
                 name: name.to_owned(),
-            }
-            .into()],
-        );
+            }),
+            ComponentTicks::new(self.change_tick()),
+        )];
 
-        if !components.is_empty() {
-            // Add the other components.
-            self.add_components(id, components);
+        // Then the other components, skipping duplicates.
+        for component in components {
+            if entity_components
+                .iter()
+                .all(|(existing, _)| existing.stable_type_id() != component.stable_type_id())
+            {
+                entity_components.push((component, ComponentTicks::new(self.change_tick())));
+            }
         }
 
-        id
+        // Add it to entities with all its components.
+        self.insert_row(entity, entity_components);
+
+        entity
     }
 
     /// Get all the component types an entity has.
     ///
     /// Returns None if the entity doesn't exist.
     #[must_use]
-    pub fn get_entity_component_types(&self, entity_id: u128) -> Option<Vec<StableTypeId>> {
-        // Do a simple look up in the entities map.
-        self.entities.get(&entity_id).cloned()
+    pub fn get_entity_component_types(&self, entity: Entity) -> Option<Vec<StableTypeId>> {
+        // Look up the entity's archetype.
+        let location = self.entity_location(entity)?;
+
+        Some(self.archetypes[location.archetype].types().to_vec())
     }
 
     /// Get all the components of a given entity.
     ///
     /// Returns None if the entity doesn't exist.
     #[must_use]
-    pub fn get_entity_components(&self, entity_id: u128) -> Option<Vec<&ComponentBox>> {
-        // Get the component types of the entity.
-        let component_types = self.get_entity_component_types(entity_id)?;
+    pub fn get_entity_components(&self, entity: Entity) -> Option<Vec<ComponentRef<'_>>> {
+        // Look up the entity's archetype.
+        let location = self.entity_location(entity)?;
 
-        // For each component the entity has.
-        Some(
-            component_types
-                .into_iter()
-                .map(|component_type| {
-                    // Get the map of entities => components.
-                    let entities_to_components = self
-                        .components
-                        .get(&component_type)
-                        .expect("Component type not registered.");
-                    // Return the component.
-                    entities_to_components
-                        .get(&entity_id)
-                        .expect("Component not found in entity.")
-                    // Collect into a HashMap
-                })
-                .collect::<Vec<_>>(),
-        )
+        // Get every component in the entity's row.
+        Some(self.archetypes[location.archetype].row(location.row, self.system_ticks()))
     }
 
-    /// Get an iterator over components of given types, in the form (entity id, \[comp1, comp2, comp3\]).
+    /// Get an iterator over components of given types, in the form (entity, \[comp1, comp2, comp3\]).
     ///
-    /// # Panics
+    /// The components are borrowed immutably.
     #[must_use]
     pub fn query_by_type_id<const N: usize>(
         &self,
         component_types: [StableTypeId; N],
-    ) -> std::vec::IntoIter<(u128, [&ComponentBox; N])> {
-        puffin::profile_function!();
-
-        // If only one component type is given,
-        // we can skip all the hard work.
-        if N == 1 {
-            // Get the map of entities => components.
-            let entities_to_components = self
-                .components
-                .get(&component_types[0])
-                .expect("Component type not registered.");
-            // Return the iterator.
-            return entities_to_components
-                .iter()
-                .map(|(&entity_id, component)| {
-                    // Return the entity id and the component.
-                    (entity_id, [component; N])
-                })
-                .collect::<Vec<_>>()
-                .into_iter();
-        }
-
-        // Find all the entities that have each component.
-        let mut entities_with_each_component: Vec<Vec<u128>> = {
-            puffin::profile_scope!("entities_with_each_component");
-
-            component_types
-                .into_iter()
-                // Get all the entities that have each component.
-                .map(|component_type| {
-                    self.components
-                        // Get entities => components for this component type.
-                        .get(&component_type)
-                        // Do this if previous is Some.
-                        .map_or_else(Vec::new, |map| {
-                            map
-                                // Only get the entity id.
-                                .keys()
-                                // From &u128 -> u128
-                                .copied()
-                                // Into a vector.
-                                .collect::<Vec<u128>>()
-                        })
-                })
-                .collect()
-        };
-
-        // Find the intersection of the previous.
-        // This is the set of entities that have all the components.
-        let entities_with_all_components = {
-            puffin::profile_scope!("entities_with_all_components");
-
-            let mut ids = vec![];
-
-            for id in entities_with_each_component
-                .pop()
-                .expect("This shouldn't happen, as length was checked before")
-            {
-                let mut in_all = true;
-
-                for other_ids in &mut entities_with_each_component {
-                    if let Some(index) = other_ids.iter().position(|x| *x == id) {
-                        other_ids.remove(index);
-                    } else {
-                        in_all = false;
-                        break;
-                    };
-                }
+    ) -> impl ExactSizeIterator<Item = (Entity, [ComponentRef<'_>; N])> {
+        self.query_by_type_id_filtered(
+            component_types.map(Access::Read),
+            [],
+            &QueryFilter::default(),
+        )
+        .map(|(entity, components, [])| (entity, components))
+    }
 
-                if in_all {
-                    ids.push(id);
-                }
-            }
+    /// Get an iterator over components of given types, filtered by a `QueryFilter`.
+    ///
+    /// This is in the form (entity, \[comp1, comp2\], \[optional1, optional2\]).
+    ///
+    /// Every entity returned has the component types,
+    /// and `None` is returned for the optional types it doesn't have.
+    ///
+    /// The columns of the components are borrowed with the given access,
+    /// until every reference returned is dropped.
+    ///
+    /// # Panics
+    ///
+    /// If a column is already borrowed in a way that conflicts,
+    /// including by an earlier type in this query.
+    #[must_use]
+    pub fn query_by_type_id_filtered<const N: usize, const O: usize>(
+        &self,
+        component_types: [Access; N],
+        optional_types: [Access; O],
+        filter: &QueryFilter,
+    ) -> std::vec::IntoIter<(Entity, [ComponentRef<'_>; N], [Option<ComponentRef<'_>>; O])> {
+        puffin::profile_function!();
 
-            ids
+        // Only the archetypes with the first required type need to be checked.
+        // If no types are required, every archetype is checked.
+        let candidates: Vec<ArchetypeId> = match component_types
+            .iter()
+            .map(|access| access.type_id())
+            .chain(filter.required())
+            .next()
+        {
+            Some(first) => self
+                .component_archetypes
+                .get(&first)
+                .cloned()
+                .unwrap_or_default(),
+            None => (0..self.archetypes.len()).collect(),
         };
 
-        puffin::profile_scope!("components_from_ids");
+        let system_ticks = self.system_ticks();
 
-        // Create the final return value for each entity previously found.
-        entities_with_all_components
+        candidates
             .into_iter()
-            // For each entity id.
-            .map(|id| {
-                // Get the given components for the entity.
-                let components_vec = component_types
-                    .into_iter()
-                    // For each component type.
-                    .map(|component_type| {
-                        // Get the map of entities => components.
-                        let entities_to_components = &self.components[&component_type];
-                        // Return the component.
-                        &entities_to_components[&id]
-                    })
-                    // Into a vector.
-                    .collect::<Vec<_>>();
-
-                // This converts a vector to a sized array.
-                let components_array: [&ComponentBox; N] =
-                    components_vec.try_into().unwrap_or_else(|_| panic!());
+            .map(|archetype| &self.archetypes[archetype])
+            // Only archetypes that have every component type, and match the filter.
+            .filter(|archetype| {
+                component_types
+                    .iter()
+                    .all(|access| archetype.has_type(access.type_id()))
+                    && filter.matches_archetype(archetype)
+            })
+            .flat_map(|archetype| {
+                // Borrow the columns once per archetype, not per entity.
+                let columns = component_types.map(|access| {
+                    let column = archetype
+                        .column(access.type_id())
+                        .unwrap_or_else(|| unreachable!("Archetype was checked to have the type"));
+                    ColumnBorrow::new(column, access.is_write())
+                });
+                let optional_columns = optional_types.map(|access| {
+                    archetype
+                        .column(access.type_id())
+                        .map(|column| ColumnBorrow::new(column, access.is_write()))
+                });
+                let (changed_columns, added_columns) = filter.tick_columns(archetype);
 
-                // Join with the id in a tuple.
-                (id, components_array)
+                archetype
+                    .entities()
+                    .iter()
+                    .enumerate()
+                    // Only entities whose components were changed or added since the system last ran.
+                    .filter(move |(row, _)| {
+                        changed_columns
+                            .iter()
+                            .all(|column| column.ticks(*row).is_changed(system_ticks))
+                            && added_columns
+                                .iter()
+                                .all(|column| column.ticks(*row).is_added(system_ticks))
+                    })
+                    .map(move |(row, &entity)| {
+                        (
+                            entity,
+                            columns
+                                .each_ref()
+                                .map(|column| column.get(row, system_ticks)),
+                            optional_columns.each_ref().map(|column| {
+                                column.as_ref().map(|column| column.get(row, system_ticks))
+                            }),
+                        )
+                    })
             })
             // Into a vector to evaluate everything.
-            .collect::<Vec<(u128, [_; N])>>()
+            .collect::<Vec<_>>()
             // Into an iterator for ease of use in a system.
             .into_iter()
     }
 
-    /// Get a single component of a given type.
+    /// Get a single component of a given type mutably.
     ///
     /// This gets the first component of the given type,
     ///
     /// but should only be used when you're sure there is only one.
+    ///
+    /// # Panics
+    ///
+    /// If there are no components of this type,
+    /// or its column is already borrowed.
     #[must_use]
-    pub fn get<T: Component>(&self) -> &mut T {
-        self.get_by_type_id(T::stable_type_id()).get_mut::<T>()
+    pub fn get<T: Component>(&self) -> Mut<'_, T> {
+        self.get_by_type_id(T::stable_type_id()).into_mut::<T>()
     }
 
-    /// Get a single component with a given stable type id.
+    /// Get a single component with a given stable type id, borrowed mutably.
     ///
     /// This gets the first component of the given type,
     ///
     /// but should only be used when you're sure there is only one.
+    ///
+    /// # Panics
+    ///
+    /// If there are no components of this type,
+    /// or its column is already borrowed.
     #[must_use]
-    pub fn get_by_type_id(&self, component_type: StableTypeId) -> &ComponentBox {
-        self.components[&component_type]
-            .values()
-            .next()
-            .expect("Component not found.")
+    pub fn get_by_type_id(&self, component_type: StableTypeId) -> ComponentRef<'_> {
+        let column = self
+            .component_archetypes
+            .get(&component_type)
+            .into_iter()
+            .flatten()
+            .map(|&archetype| &self.archetypes[archetype])
+            .find(|archetype| !archetype.is_empty())
+            .and_then(|archetype| archetype.column(component_type))
+            .expect("Component not found.");
+
+        ColumnBorrow::new(column, true).get(0, self.system_ticks())
     }
 }
 
@@ -459,49 +1129,513 @@ impl Entities {
 ///
 /// Used as `query(&Entities, [mut Component1, mut Component2 etc ; Component3, Component4 etc])`.
 ///
-/// Returns an iterator of tuples of the form (id, Component1, Component2 etc).
+/// Returns an iterator of tuples of the form (entity, Component1, Component2 etc),
+/// where the components are `Mut` or `Ref` guards.
+///
+/// The required components come first, mutable then immutable,
+/// followed by the optional components as `Option`s, mutable then immutable.
+///
+/// After the required components, these clauses can be given, in this order:
+/// - `optional [mut A; B]` - returns the component if the entity has it.
+/// - `with [A]` - the entity must have the component.
+/// - `without [A]` - the entity must not have the component.
+/// - `changed [A]` - the component must have changed since the system last ran.
+/// - `added [A]` - the component must have been added since the system last ran.
+///
+/// Components accessed mutably are marked as changed.
+///
+/// The columns stay borrowed until the guards are dropped,
+/// so querying a component mutably while it's used elsewhere panics.
 ///
 /// # Examples
 ///
 /// ```ignore
-/// for (_id, transform, velocity)
+/// for (_entity, mut transform, velocity)
 /// in query!(&entities, [mut Transform; Velocity]) {
 ///    transform.position += velocity.velocity;
 /// }
+///
+/// for (_entity, transform, velocity)
+/// in query!(&entities, [; Transform], optional [; Velocity], without [Wall]) {
+///    if let Some(velocity) = velocity {
+///        println!("{:?} {:?}", transform.position, velocity.velocity);
+///    }
+/// }
 /// ```
 #[macro_export]
 macro_rules! query {
-    ($entities:expr, [$(mut $mut_type:ty),* $(,)?; $($type:ty),* $(,)?]) => {
+    (
+        $entities:expr,
+        [$(mut $mut_type:ty),* $(,)?; $($type:ty),* $(,)?]
+        $(, optional [$(mut $opt_mut_type:ty),* $(,)?; $($opt_type:ty),* $(,)?])?
+        $(, with [$($with_type:ty),* $(,)?])?
+        $(, without [$($without_type:ty),* $(,)?])?
+        $(, changed [$($changed_type:ty),* $(,)?])?
+        $(, added [$($added_type:ty),* $(,)?])?
+        $(,)?
+    ) => {
         {
             let type_ids = [
                 $(
-                    <$mut_type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                    $crate::Access::Write(
+                        <$mut_type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                    ),
                 )*
                 $(
-                    <$type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                    $crate::Access::Read(
+                        <$type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                    ),
                 )*
             ];
 
-            $entities.query_by_type_id(type_ids).map(|(id, components)| {
-                let mut index = 0;
-                (
-                    id,
+            let optional_type_ids = [
+                $(
                     $(
-                        {
-                            #![allow(clippy::mixed_read_write_in_expression)]
-                            index += 1;
-                            components[index - 1].get_mut::<$mut_type>()
-                        },
+                        $crate::Access::Write(
+                            <$opt_mut_type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                        ),
                     )*
                     $(
-                        {
-                            #![allow(clippy::mixed_read_write_in_expression)]
-                            index += 1;
-                            components[index - 1].get::<$type>()
-                        },
+                        $crate::Access::Read(
+                            <$opt_type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                        ),
                     )*
-                )
-            }).collect::<Vec<_>>().into_iter()
+                )?
+            ];
+
+            let filter = $crate::QueryFilter {
+                with: vec![$($(
+                    <$with_type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                )*)?],
+                without: vec![$($(
+                    <$without_type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                )*)?],
+                changed: vec![$($(
+                    <$changed_type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                )*)?],
+                added: vec![$($(
+                    <$added_type as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                )*)?],
+            };
+
+            let entities = &$entities;
+
+            entities
+                .query_by_type_id_filtered(type_ids, optional_type_ids, &filter)
+                .map(|query_item| {
+                    #[allow(unused_variables)]
+                    let (entity, components, optional_components) = query_item;
+                    // The components are in the same order as the types.
+                    #[allow(unused_mut, unused_variables)]
+                    let mut components = components.into_iter();
+                    #[allow(unused_mut, unused_variables)]
+                    let mut optional_components = optional_components.into_iter();
+                    (
+                        entity,
+                        $(
+                            components
+                                .next()
+                                .unwrap_or_else(|| unreachable!())
+                                .into_mut::<$mut_type>(),
+                        )*
+                        $(
+                            components
+                                .next()
+                                .unwrap_or_else(|| unreachable!())
+                                .into_ref::<$type>(),
+                        )*
+                        $(
+                            $(
+                                optional_components
+                                    .next()
+                                    .unwrap_or_else(|| unreachable!())
+                                    .map(|component| component.into_mut::<$opt_mut_type>()),
+                            )*
+                            $(
+                                optional_components
+                                    .next()
+                                    .unwrap_or_else(|| unreachable!())
+                                    .map(|component| component.into_ref::<$opt_type>()),
+                            )*
+                        )?
+                    )
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transform_at(x: f32) -> Transform {
+        Transform {
+            position: iridium_maths::VecN::new([x, 0., 0.]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn query_matches_archetypes() {
+        let mut entities = Entities::default();
+
+        entities.new_entity(None, "A", vec![transform_at(1.).into()]);
+        entities.new_entity(None, "B", vec![transform_at(2.).into()]);
+        entities.new_entity(None, "C", vec![]);
+
+        assert_eq!(entities.query_by_type_id([Name::stable_type_id()]).len(), 3);
+        assert_eq!(
+            entities
+                .query_by_type_id([Name::stable_type_id(), Transform::stable_type_id()])
+                .len(),
+            2
+        );
+        assert_eq!(entities.entity_count::<Transform>(), 2);
+    }
+
+    #[test]
+    fn add_components_keeps_data() {
+        let mut entities = Entities::default();
+
+        let entity = entities.new_entity(None, "A", vec![]);
+        assert!(entities.add_components(entity, vec![transform_at(5.).into()]));
+
+        let types = entities
+            .get_entity_component_types(entity)
+            .expect("Entity not found");
+        assert!(types.contains(&Name::stable_type_id()));
+        assert!(types.contains(&Transform::stable_type_id()));
+
+        let (_, transform) = query!(entities, [; Transform])
+            .next()
+            .expect("Transform not found");
+        assert_eq!(transform.position.x(), 5.);
+        assert_eq!(entities.get::<Name>().name, "A");
+    }
+
+    #[test]
+    fn delete_entity_moves_last_row() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(None, "A", vec![transform_at(1.).into()]);
+        let b = entities.new_entity(None, "B", vec![transform_at(2.).into()]);
+        let c = entities.new_entity(None, "C", vec![transform_at(3.).into()]);
+
+        assert!(entities.delete_entity(a));
+        assert!(!entities.delete_entity(a));
+
+        // C was moved into A's row, so its location must have been updated.
+        for (entity, x) in [(b, 2.), (c, 3.)] {
+            let components = entities
+                .get_entity_components(entity)
+                .expect("Entity not found");
+            let transform = components
+                .iter()
+                .find(|component| component.is_type::<Transform>())
+                .expect("Transform not found");
+            assert_eq!(transform.get::<Transform>().position.x(), x);
+        }
+
+        assert_eq!(entities.entity_count::<Transform>(), 2);
+    }
+
+    #[test]
+    fn clear_removes_everything() {
+        let mut entities = Entities::default();
+
+        entities.new_entity(None, "A", vec![transform_at(1.).into()]);
+        entities.clear();
+
+        assert!(entities.all_entities().is_empty());
+        assert_eq!(entities.entity_count::<Transform>(), 0);
+    }
+
+    #[test]
+    fn query_filters() {
+        let mut entities = Entities::default();
+
+        entities.new_entity(None, "A", vec![transform_at(1.).into()]);
+        entities.new_entity(None, "B", vec![]);
+        entities.new_entity(None, "C", vec![transform_at(3.).into()]);
+
+        assert_eq!(query!(entities, [; Name], with [Transform]).len(), 2);
+
+        let without = query!(entities, [; Name], without [Transform]).collect::<Vec<_>>();
+        assert_eq!(without.len(), 1);
+        assert_eq!(without[0].1.name, "B");
+
+        let optional = query!(entities, [; Name], optional [; Transform]).collect::<Vec<_>>();
+        assert_eq!(optional.len(), 3);
+        for (_, name, transform) in optional {
+            assert_eq!(transform.is_some(), name.name != "B");
+        }
+
+        assert_eq!(query!(entities, [;], without [Name]).len(), 0);
+    }
+
+    #[test]
+    fn changed_and_added_filters() {
+        let mut entities = Entities::default();
+
+        entities.new_entity(None, "A", vec![transform_at(1.).into()]);
+        entities.new_entity(None, "B", vec![transform_at(2.).into()]);
+
+        // Everything was added since the last tick.
+        assert_eq!(query!(entities, [; Name], added [Transform]).len(), 2);
+        assert_eq!(query!(entities, [; Name], changed [Transform]).len(), 2);
+
+        entities.advance_tick();
+        assert_eq!(query!(entities, [; Name], added [Transform]).len(), 0);
+        assert_eq!(query!(entities, [; Name], changed [Transform]).len(), 0);
+
+        // Accessing a component mutably marks it as changed.
+        for (_, mut transform, name) in query!(entities, [mut Transform; Name]) {
+            if name.name == "A" {
+                transform.position = iridium_maths::VecN::new([5., 0., 0.]);
+            }
+        }
+        assert_eq!(query!(entities, [; Name], changed [Transform]).len(), 2);
+        assert_eq!(query!(entities, [; Name], added [Transform]).len(), 0);
+
+        // Moving an entity between archetypes keeps its ticks.
+        entities.advance_tick();
+        let c = entities.new_entity(None, "C", vec![]);
+        entities.add_components(c, vec![transform_at(3.).into()]);
+        let added = query!(entities, [; Name], added [Transform]).collect::<Vec<_>>();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].1.name, "C");
+        assert_eq!(query!(entities, [; Transform], added [Name]).len(), 1);
+    }
+
+    #[test]
+    fn get_mut_marks_changed() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(None, "A", vec![transform_at(1.).into()]);
+        assert!(entities.is_added::<Transform>(a));
+        assert!(entities.is_changed::<Transform>(a));
+
+        entities.advance_tick();
+        assert!(!entities.is_added::<Transform>(a));
+        assert!(!entities.is_changed::<Transform>(a));
+
+        // Reading doesn't mark it as changed.
+        assert_eq!(
+            query!(entities, [; Transform])
+                .next()
+                .map(|(_, t)| t.position.x()),
+            Some(1.)
+        );
+        assert!(!entities.is_changed::<Transform>(a));
+
+        entities.get::<Transform>().position = iridium_maths::VecN::new([2., 0., 0.]);
+        assert!(entities.is_changed::<Transform>(a));
+        assert!(!entities.is_added::<Transform>(a));
+
+        // Components the entity doesn't have are never changed.
+        entities.advance_tick();
+        let b = entities.new_entity(None, "B", vec![]);
+        assert!(!entities.is_changed::<Transform>(b));
+    }
+
+    #[test]
+    fn changes_since_last_run() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(None, "A", vec![transform_at(1.).into()]);
+
+        // A system that last ran 3 ticks ago sees changes from the ticks since then.
+        entities.advance_tick();
+        entities.advance_tick();
+        entities.get::<Transform>().position = iridium_maths::VecN::new([2., 0., 0.]);
+        entities.advance_tick();
+
+        entities.set_last_run_tick(entities.change_tick().wrapping_sub(3));
+        assert!(entities.is_changed::<Transform>(a));
+        assert!(!entities.is_added::<Transform>(a));
+
+        entities.set_last_run_tick(entities.change_tick().wrapping_sub(1));
+        assert!(!entities.is_changed::<Transform>(a));
+
+        // The ticks can wrap around.
+        let ticks = SystemTicks {
+            last_run: u32::MAX - 1,
+            current: 1,
+        };
+        assert!(ComponentTicks::new(u32::MAX).is_changed(ticks));
+        assert!(ComponentTicks::new(0).is_added(ticks));
+        assert!(!ComponentTicks::new(u32::MAX - 1).is_changed(ticks));
+    }
+
+    #[test]
+    #[should_panic(expected = "Transform can't be borrowed mutably")]
+    fn query_while_borrowed_panics() {
+        let mut entities = Entities::default();
+        entities.new_entity(None, "A", vec![transform_at(0.).into()]);
+
+        let _transform = query!(entities, [; Transform]).collect::<Vec<_>>();
+        let _ = query!(entities, [mut Transform;]).count();
+    }
+
+    #[test]
+    fn borrows_are_released() {
+        let mut entities = Entities::default();
+        entities.new_entity(None, "A", vec![transform_at(0.).into()]);
+
+        for (_, mut transform) in query!(entities, [mut Transform;]) {
+            transform.position = iridium_maths::VecN::new([1., 0., 0.]);
+        }
+
+        assert_eq!(entities.get::<Transform>().position.x(), 1.);
+        assert_eq!(query!(entities, [; Transform]).count(), 1);
+    }
+
+    #[test]
+    fn structural_commands() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(None, "A", vec![]);
+        let b = entities.new_entity(None, "B", vec![transform_at(1.).into()]);
+
+        let commands = entities.commands("Test");
+        commands.add_component(a, transform_at(2.));
+        commands.remove_component::<Transform>(b);
+        commands.rename(b, "C");
+        commands.spawn("D", vec![transform_at(4.).into()]);
+
+        // Nothing happens until the commands are processed.
+        assert_eq!(entities.entity_count::<Transform>(), 1);
+        entities.process_commands();
+
+        let positions = query!(entities, [; Name, Transform])
+            .map(|(_, name, transform)| (name.name.clone(), transform.position.x()))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions["A"], 2.);
+        assert_eq!(positions["D"], 4.);
+        assert_eq!(entities.entity_from_name("C"), Some(b));
+
+        // Replacing keeps the entity in place, and gives back the old component.
+        let old = entities
+            .replace_component(a, transform_at(5.).into())
+            .expect("Transform not found");
+        assert_eq!(old.get::<Transform>().position.x(), 2.);
+        entities.commands("Test").despawn_recursive(b);
+        entities.process_commands();
+        assert!(!entities.is_alive(b));
+    }
+
+    #[test]
+    fn commands_are_sorted_by_source() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(None, "A", vec![]);
+
+        // Sent in the opposite order they are processed.
+        entities.commands("SystemB").rename(a, "FromB");
+        entities.commands("SystemA").rename(a, "FromA");
+        entities.commands("SystemA").rename(a, "FromA2");
+        entities.process_commands();
+
+        assert_eq!(entities.entity_from_name("FromB"), Some(a));
+    }
+
+    #[test]
+    fn remove_keeps_name() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(None, "A", vec![transform_at(1.).into()]);
+        let removed = entities
+            .remove_components(a, &[Name::stable_type_id(), Transform::stable_type_id()])
+            .expect("Entity not found");
+
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].is_type::<Transform>());
+        assert_eq!(
+            entities.get_entity_component_types(a),
+            Some(vec![Name::stable_type_id()])
+        );
+    }
+
+    #[test]
+    fn remove_single_component() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(None, "A", vec![transform_at(3.).into()]);
+
+        let removed = entities
+            .remove_component::<Transform>(a)
+            .expect("Transform not removed");
+        assert_eq!(removed.take::<Transform>().position.x(), 3.);
+        assert_eq!(entities.entity_count::<Transform>(), 0);
+
+        // It's already gone, and Name can't be removed.
+        assert!(entities.remove_component::<Transform>(a).is_none());
+        assert!(entities.remove_component::<Name>(a).is_none());
+        assert!(entities.is_alive(a));
+    }
+
+    #[test]
+    fn unregister_component() {
+        let mut entities = Entities::default();
+
+        assert!(entities.component_info::<Transform>().is_some());
+        assert!(entities.unregister_component::<Transform>().is_some());
+        assert!(entities.component_info::<Transform>().is_none());
+        assert!(entities.component_info_from_name("Transform").is_none());
+        assert!(entities.unregister_component::<Transform>().is_none());
+    }
+
+    #[test]
+    fn deleted_handle_is_dangling() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(None, "A", vec![]);
+        assert!(entities.delete_entity(a));
+
+        // The index is reused, but with a new generation.
+        let b = entities.new_entity(None, "B", vec![]);
+        assert_eq!(a.index(), b.index());
+        assert_ne!(a, b);
+
+        assert!(!entities.is_alive(a));
+        assert!(entities.is_alive(b));
+        assert!(entities.get_entity_components(a).is_none());
+        assert!(!entities.add_components(a, vec![transform_at(1.).into()]));
+        assert!(!entities.delete_entity(a));
+        assert!(entities.is_alive(b));
+    }
+
+    #[test]
+    fn clear_invalidates_handles() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(None, "A", vec![]);
+        entities.clear();
+        let b = entities.new_entity(None, "B", vec![]);
+
+        assert!(!entities.is_alive(a));
+        assert!(entities.is_alive(b));
+    }
+
+    #[test]
+    fn persistent_id_round_trip() {
+        let mut entities = Entities::default();
+
+        let a = entities.new_entity(Some(42), "A", vec![]);
+        assert_eq!(entities.persistent_id(a), Some(42));
+        assert_eq!(entities.entity_from_persistent_id(42), Some(a));
+
+        // Using the same persistent id replaces the entity.
+        let b = entities.new_entity(Some(42), "B", vec![]);
+        assert!(!entities.is_alive(a));
+        assert_eq!(entities.entity_from_persistent_id(42), Some(b));
+        assert_eq!(entities.all_entities(), vec![b]);
+
+        entities.delete_entity(b);
+        assert_eq!(entities.persistent_id(b), None);
+        assert_eq!(entities.entity_from_persistent_id(42), None);
+    }
+}
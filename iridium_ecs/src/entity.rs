@@ -0,0 +1,58 @@
+use crate::EntityLocation;
+
+/// A handle to an entity in `Entities`.
+///
+/// The index is reused after an entity is deleted,
+/// but the generation is increased each time,
+/// so a handle to a deleted entity can be detected.
+///
+/// These aren't stable between runs,
+/// use the entity's persistent id for that.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// Creates an entity handle from its parts.
+    ///
+    /// This is mostly useful for tests,
+    /// entities should usually be obtained from `Entities`.
+    #[must_use]
+    pub const fn from_raw_parts(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    /// The index of the entity.
+    #[must_use]
+    pub const fn index(self) -> u32 {
+        self.index
+    }
+
+    /// The generation of the entity.
+    ///
+    /// This is increased each time the index is reused.
+    #[must_use]
+    pub const fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+impl std::fmt::Display for Entity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+/// What `Entities` stores about each entity index.
+pub(crate) struct EntityMeta {
+    /// The current generation of this index.
+    pub generation: u32,
+    /// Where the entity's components are stored.
+    ///
+    /// This is `None` if the index isn't in use.
+    pub location: Option<EntityLocation>,
+    /// The id saved in scene files.
+    pub persistent_id: u128,
+}
@@ -0,0 +1,258 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+};
+
+use iridium_assets::Assets;
+use iridium_reflect::{
+    extend_stable_type_id, stable_type_id_from_name, HasStableTypeId, StableTypeId,
+};
+
+use crate::{
+    storage::{ComponentStorage, StoredComponent},
+    ui::InspectorUi,
+    Component, ComponentColumn, TypedColumn,
+};
+
+/// A type that can be sent between systems with `Events`.
+pub trait Event: Send + Sync + HasStableTypeId + 'static {}
+
+/// A queue of events of type `T`, stored as a resource.
+///
+/// Systems send events with `send`, and other systems read them with `read`.
+/// Each reader has its own cursor, so every reader sees every event once.
+///
+/// Events are kept for two frames, so readers that run before
+/// the sender in a frame still see them in the next frame.
+///
+/// In `System::required_components`, sending counts as mutable access to `Events<T>`,
+/// and reading counts as immutable access,
+/// so the stages are checked for conflicts like with components.
+///
+/// Add one with `World::add_event`.
+pub struct Events<T: Event> {
+    /// The events sent before the last update.
+    previous: Vec<T>,
+    /// The events sent since the last update.
+    current: Vec<T>,
+    /// The id of the first event in `previous`.
+    ///
+    /// Events are given increasing ids as they're sent.
+    previous_start: usize,
+    /// The number of events ever sent, which is the id of the next event.
+    event_count: usize,
+    /// reader => id of the next event it hasn't read
+    cursors: Mutex<HashMap<&'static str, usize>>,
+}
+
+impl<T: Event> Default for Events<T> {
+    fn default() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            previous_start: 0,
+            event_count: 0,
+            cursors: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Event> Events<T> {
+    /// Creates an empty event queue.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends an event.
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+        self.event_count += 1;
+    }
+
+    /// Reads the events the reader hasn't read yet, oldest first.
+    ///
+    /// The reader is identified by its name, usually the system name.
+    /// A new reader starts at the oldest event still stored.
+    pub fn read(&self, reader: &'static str) -> impl Iterator<Item = &T> {
+        let start = {
+            let mut cursors = self.cursors.lock().unwrap_or_else(PoisonError::into_inner);
+            let cursor = cursors.entry(reader).or_insert(0);
+            let start = (*cursor).max(self.previous_start);
+            *cursor = self.event_count;
+            start
+        };
+
+        let previous_skip = (start - self.previous_start).min(self.previous.len());
+        let current_skip = start.saturating_sub(self.previous_start + self.previous.len());
+
+        self.previous[previous_skip..]
+            .iter()
+            .chain(&self.current[current_skip..])
+    }
+
+    /// Gets every stored event, oldest first,
+    /// without moving any reader's cursor.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(&self.current)
+    }
+
+    /// The number of stored events.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    /// Checks if there are no stored events.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops the events sent before the last update.
+    ///
+    /// Called once a frame by `World::run_systems`,
+    /// so events are dropped two frames after they're sent.
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+        self.previous_start = self.event_count - self.previous.len();
+    }
+
+    /// Drops every stored event.
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+        self.previous_start = self.event_count;
+    }
+}
+
+impl<T: Event> HasStableTypeId for Events<T> {
+    fn stable_type_id() -> StableTypeId {
+        // Derived from the event type's id, so it's different for each event type,
+        // with the same hash as derived ids so it doesn't change between builds.
+        extend_stable_type_id(
+            stable_type_id_from_name("Events<"),
+            &T::stable_type_id().to_le_bytes(),
+        )
+    }
+
+    fn dyn_stable_type_id(&self) -> StableTypeId {
+        Self::stable_type_id()
+    }
+}
+
+impl<T: Event> Component for Events<T> {
+    fn type_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    fn dyn_type_name(&self) -> &'static str {
+        Self::type_name()
+    }
+
+    fn update_assets(&mut self, _assets: &Assets) -> Result<i32, String> {
+        Ok(0)
+    }
+
+    fn empty_column(&self) -> Box<dyn ComponentColumn> {
+        Box::new(TypedColumn::<Self>::new())
+    }
+}
+
+impl<T: Event> ComponentStorage for Events<T> {
+    fn from_stored(_stored: StoredComponent, _assets: &Assets) -> Option<Self> {
+        Some(Self::new())
+    }
+
+    fn to_stored(&self) -> StoredComponent {
+        StoredComponent {
+            type_name: Self::type_name().to_string(),
+            fields: HashMap::new(),
+        }
+    }
+
+    fn is_stored(&self) -> bool {
+        false
+    }
+}
+
+impl<T: Event> InspectorUi for Events<T> {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("stored");
+        ui.label(self.len().to_string());
+        ui.end_row();
+
+        ui.label("sent");
+        ui.label(self.event_count.to_string());
+        ui.end_row();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iridium_ecs_macros::HasStableTypeId;
+
+    use super::*;
+
+    #[derive(HasStableTypeId, Debug, PartialEq)]
+    struct Hit(u32);
+
+    impl Event for Hit {}
+
+    #[derive(HasStableTypeId)]
+    struct Miss;
+
+    impl Event for Miss {}
+
+    fn read(events: &Events<Hit>, reader: &'static str) -> Vec<u32> {
+        events.read(reader).map(|hit| hit.0).collect()
+    }
+
+    #[test]
+    fn readers_have_their_own_cursors() {
+        let mut events = Events::new();
+        events.send(Hit(0));
+        events.send(Hit(1));
+
+        assert_eq!(read(&events, "a"), vec![0, 1]);
+        assert_eq!(read(&events, "a"), vec![]);
+
+        events.send(Hit(2));
+        assert_eq!(read(&events, "a"), vec![2]);
+        assert_eq!(read(&events, "b"), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn events_expire_after_two_updates() {
+        let mut events = Events::new();
+        events.send(Hit(0));
+        assert_eq!(read(&events, "early"), vec![0]);
+
+        // The next frame, readers can still see it.
+        events.update();
+        events.send(Hit(1));
+        assert_eq!(read(&events, "late"), vec![0, 1]);
+        assert_eq!(read(&events, "early"), vec![1]);
+
+        // The frame after, it's gone.
+        events.update();
+        assert_eq!(read(&events, "new"), vec![1]);
+        assert_eq!(read(&events, "late"), vec![]);
+
+        events.update();
+        assert!(events.is_empty());
+        assert_eq!(read(&events, "early"), vec![]);
+    }
+
+    #[test]
+    fn stable_type_id_depends_on_event() {
+        assert_ne!(
+            Events::<Hit>::stable_type_id(),
+            Events::<Miss>::stable_type_id()
+        );
+        assert_ne!(Events::<Hit>::stable_type_id(), Hit::stable_type_id());
+
+        // The same in every build, unlike the standard hasher.
+        assert_eq!(Events::<Hit>::stable_type_id(), 0x8c6f_fbbc_c384_f183);
+    }
+}
@@ -0,0 +1,526 @@
+use iridium_reflect::HasStableTypeId;
+
+use crate::{Access, Children, Entities, Entity, GlobalTransform, Parent, QueryFilter, Transform};
+
+impl Entities {
+    /// Gets the parent of an entity.
+    ///
+    /// Returns `None` if the entity doesn't exist or has no parent.
+    #[must_use]
+    pub fn parent(&self, entity: Entity) -> Option<Entity> {
+        self.entity_component(entity, Parent::stable_type_id())
+            .map(|parent| parent.get::<Parent>().entity())
+    }
+
+    /// Gets the children of an entity, in the order they were added.
+    ///
+    /// This is empty if the entity doesn't exist or has no children.
+    #[must_use]
+    pub fn children(&self, entity: Entity) -> Vec<Entity> {
+        self.entity_component(entity, Children::stable_type_id())
+            .map(|children| children.get::<Children>().entities().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Gets the entities without a parent.
+    #[must_use]
+    pub fn root_entities(&self) -> Vec<Entity> {
+        self.query_by_type_id_filtered(
+            [],
+            [],
+            &QueryFilter::new().without(Parent::stable_type_id()),
+        )
+        .map(|(entity, [], [])| entity)
+        .collect()
+    }
+
+    /// Sets the parent of an entity, or removes it if `None`.
+    ///
+    /// This updates the `Parent` of the child,
+    /// and the `Children` of the old and new parent.
+    ///
+    /// Returns false if either entity doesn't exist,
+    /// or if the parent is the child or one of its descendants.
+    pub fn set_parent(&mut self, child: Entity, parent: Option<Entity>) -> bool {
+        if !self.is_alive(child) {
+            return false;
+        }
+
+        // Check the parent exists and wouldn't create a cycle.
+        if let Some(parent) = parent {
+            if !self.is_alive(parent) {
+                return false;
+            }
+
+            let mut ancestor = Some(parent);
+            while let Some(entity) = ancestor {
+                if entity == child {
+                    return false;
+                }
+                ancestor = self.parent(entity);
+            }
+        }
+
+        if self.parent(child) == parent {
+            return true;
+        }
+
+        self.unlink_from_parent(child);
+
+        let Some(parent) = parent else {
+            self.take_components(child, &[Parent::stable_type_id()]);
+            return true;
+        };
+
+        let persistent_id = self
+            .persistent_id(parent)
+            .unwrap_or_else(|| unreachable!("Parent was checked to be alive"));
+        self.replace_component_unlinked(child, Parent::new(parent, persistent_id).into());
+
+        let has_children = self
+            .entity_component_mut(parent, Children::stable_type_id())
+            .map(|mut children| children.get_mut::<Children>().push(child))
+            .is_some();
+
+        if !has_children {
+            let mut children = Children::default();
+            children.push(child);
+            self.add_components(parent, vec![children.into()]);
+        }
+
+        true
+    }
+
+    /// Removes an entity from its parent's `Children`,
+    /// removing the component if it was the last child.
+    ///
+    /// The entity's own `Parent` is left for the caller to remove.
+    pub(crate) fn unlink_from_parent(&mut self, child: Entity) {
+        let Some(parent) = self.parent(child) else {
+            return;
+        };
+        let is_empty = {
+            let Some(mut children) = self.entity_component_mut(parent, Children::stable_type_id())
+            else {
+                return;
+            };
+
+            let children = children.get_mut::<Children>();
+            children.remove(child);
+            children.entities().is_empty()
+        };
+
+        if is_empty {
+            self.take_components(parent, &[Children::stable_type_id()]);
+        }
+    }
+
+    /// Points every `Parent` at the entity with its persistent id,
+    /// and rebuilds the `Children` from them.
+    ///
+    /// Loaded `Parent`s only know the persistent id,
+    /// so this needs to be called after loading a scene.
+    /// Parents that don't exist are removed.
+    pub fn resolve_parents(&mut self) {
+        let parents = self
+            .query_by_type_id([Parent::stable_type_id()])
+            .map(|(entity, [parent])| (entity, parent.get::<Parent>().persistent_id()))
+            .collect::<Vec<_>>();
+
+        for (child, persistent_id) in parents {
+            // The loaded handle isn't valid,
+            // so it's removed without looking at the parent.
+            self.take_components(child, &[Parent::stable_type_id()]);
+
+            if let Some(parent) = self.entity_from_persistent_id(persistent_id) {
+                self.set_parent(child, Some(parent));
+            }
+        }
+    }
+
+    /// Computes the `GlobalTransform` of every entity,
+    /// by walking down the hierarchy from the root entities.
+    ///
+    /// Entities without a `Transform` pass their parent's on to their children.
+    ///
+    /// Only global transforms that are different are written,
+    /// so unmoved entities aren't marked as changed.
+    ///
+    /// Entities moved by this aren't interpolated by the renderer,
+    /// use `propagate_fixed_transforms` in fixed steps.
+    pub fn propagate_transforms(&self) {
+        puffin::profile_function!();
+
+        self.propagate(false);
+    }
+
+    /// Computes the `GlobalTransform` of every entity, the same as `propagate_transforms`,
+    /// but for a fixed step.
+    ///
+    /// Moved entities keep where they were before the step,
+    /// as remembered by `start_fixed_step`, so the renderer can interpolate them.
+    pub fn propagate_fixed_transforms(&self) {
+        puffin::profile_function!();
+
+        self.propagate(true);
+    }
+
+    /// Remembers where every entity is before a fixed step,
+    /// for interpolating to where the step moves it.
+    ///
+    /// This doesn't mark the global transforms as changed.
+    pub fn start_fixed_step(&self) {
+        puffin::profile_function!();
+
+        for (_, [mut global_transform], []) in self.query_by_type_id_filtered(
+            [Access::Write(GlobalTransform::stable_type_id())],
+            [],
+            &QueryFilter::default(),
+        ) {
+            global_transform
+                .get_mut_untracked::<GlobalTransform>()
+                .start_fixed_step();
+        }
+    }
+
+    /// Computes the `GlobalTransform` of every entity from the root entities down.
+    fn propagate(&self, in_fixed_step: bool) {
+        let roots = self.query_by_type_id_filtered(
+            [],
+            [
+                Access::Read(Transform::stable_type_id()),
+                Access::Read(Children::stable_type_id()),
+            ],
+            &QueryFilter::new().without(Parent::stable_type_id()),
+        );
+
+        for (entity, [], [transform, children]) in roots {
+            let global_transform = transform.map_or_else(GlobalTransform::default, |transform| {
+                GlobalTransform::from(transform.get::<Transform>())
+            });
+            self.set_global_transform(entity, global_transform, in_fixed_step);
+
+            if let Some(children) = children {
+                self.propagate_to_children(
+                    &global_transform,
+                    children.get::<Children>(),
+                    in_fixed_step,
+                );
+            }
+        }
+    }
+
+    /// Computes the `GlobalTransform` of the children of an entity, and their descendants.
+    fn propagate_to_children(
+        &self,
+        parent: &GlobalTransform,
+        children: &Children,
+        in_fixed_step: bool,
+    ) {
+        for &child in children.entities() {
+            let global_transform = self
+                .entity_component(child, Transform::stable_type_id())
+                .map_or(*parent, |transform| {
+                    parent.child_transform(transform.get::<Transform>())
+                });
+            self.set_global_transform(child, global_transform, in_fixed_step);
+
+            if let Some(grandchildren) = self.entity_component(child, Children::stable_type_id()) {
+                self.propagate_to_children(
+                    &global_transform,
+                    grandchildren.get::<Children>(),
+                    in_fixed_step,
+                );
+            }
+        }
+    }
+
+    /// Sets the `GlobalTransform` of an entity, if it has one and it's different.
+    fn set_global_transform(
+        &self,
+        entity: Entity,
+        global_transform: GlobalTransform,
+        in_fixed_step: bool,
+    ) {
+        if let Some(mut component) =
+            self.entity_component_mut(entity, GlobalTransform::stable_type_id())
+        {
+            if component.get::<GlobalTransform>().placement() != global_transform.placement() {
+                component
+                    .get_mut::<GlobalTransform>()
+                    .move_to(global_transform, in_fixed_step);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iridium_maths::VecN;
+
+    use super::*;
+    use crate::{storage::ComponentStorage, ComponentBox, EntityCommand};
+
+    fn transform(position: [f32; 3], scale: [f32; 3], rotation: f32) -> ComponentBox {
+        Transform {
+            position: VecN::new(position),
+            scale: VecN::new(scale),
+            rotation,
+        }
+        .into()
+    }
+
+    fn global_transform(entities: &Entities, entity: Entity) -> GlobalTransform {
+        *entities
+            .entity_component(entity, GlobalTransform::stable_type_id())
+            .expect("Entity has no global transform")
+            .get::<GlobalTransform>()
+    }
+
+    #[test]
+    fn set_parent_keeps_children_consistent() {
+        let mut entities = Entities::default();
+        let a = entities.new_entity(None, "a", vec![]);
+        let b = entities.new_entity(None, "b", vec![]);
+        let child = entities.new_entity(None, "child", vec![]);
+
+        assert!(entities.set_parent(child, Some(a)));
+        assert_eq!(entities.parent(child), Some(a));
+        assert_eq!(entities.children(a), vec![child]);
+
+        assert!(entities.set_parent(child, Some(b)));
+        assert_eq!(entities.children(a), vec![]);
+        assert_eq!(entities.children(b), vec![child]);
+        assert!(!entities
+            .get_entity_component_types(a)
+            .unwrap()
+            .contains(&Children::stable_type_id()));
+
+        assert!(entities.set_parent(child, None));
+        assert_eq!(entities.parent(child), None);
+        assert_eq!(entities.children(b), vec![]);
+    }
+
+    #[test]
+    fn set_parent_prevents_cycles() {
+        let mut entities = Entities::default();
+        let a = entities.new_entity(None, "a", vec![]);
+        let b = entities.new_entity(None, "b", vec![]);
+        let c = entities.new_entity(None, "c", vec![]);
+
+        assert!(entities.set_parent(b, Some(a)));
+        assert!(entities.set_parent(c, Some(b)));
+
+        assert!(!entities.set_parent(a, Some(c)));
+        assert!(!entities.set_parent(a, Some(a)));
+        assert_eq!(entities.parent(a), None);
+    }
+
+    #[test]
+    fn removing_parent_updates_children() {
+        let mut entities = Entities::default();
+        let parent = entities.new_entity(None, "parent", vec![]);
+        let child = entities.new_entity(None, "child", vec![]);
+        entities.set_parent(child, Some(parent));
+
+        assert!(entities.remove_component::<Parent>(child).is_some());
+        assert_eq!(entities.children(parent), vec![]);
+
+        entities.set_parent(child, Some(parent));
+        assert!(entities.remove_component::<Children>(parent).is_some());
+        assert_eq!(entities.parent(child), None);
+    }
+
+    #[test]
+    fn delete_entity_deletes_subtree() {
+        let mut entities = Entities::default();
+        let root = entities.new_entity(None, "root", vec![]);
+        let parent = entities.new_entity(None, "parent", vec![]);
+        let child = entities.new_entity(None, "child", vec![]);
+        let sibling = entities.new_entity(None, "sibling", vec![]);
+        entities.set_parent(parent, Some(root));
+        entities.set_parent(child, Some(parent));
+        entities.set_parent(sibling, Some(root));
+
+        assert!(entities.delete_entity(parent));
+        assert!(!entities.is_alive(parent));
+        assert!(!entities.is_alive(child));
+        assert!(entities.is_alive(sibling));
+        assert_eq!(entities.children(root), vec![sibling]);
+    }
+
+    #[test]
+    fn delete_command_deletes_subtree() {
+        let mut entities = Entities::default();
+        let character = entities.new_entity(None, "character", vec![]);
+        let sword = entities.new_entity(None, "sword", vec![]);
+        entities.set_parent(sword, Some(character));
+
+        entities.send_cmd(EntityCommand::DeleteEntity(character));
+        entities.process_commands();
+        assert!(!entities.is_alive(character));
+        assert!(!entities.is_alive(sword));
+    }
+
+    #[test]
+    fn delete_entity_keep_children_orphans_children() {
+        let mut entities = Entities::default();
+        let parent = entities.new_entity(None, "parent", vec![]);
+        let child = entities.new_entity(None, "child", vec![]);
+        entities.set_parent(child, Some(parent));
+
+        assert!(entities.delete_entity_keep_children(parent));
+        assert!(entities.is_alive(child));
+        assert_eq!(entities.parent(child), None);
+        assert_eq!(entities.root_entities(), vec![child]);
+    }
+
+    #[test]
+    fn global_transform_follows_transform() {
+        let mut entities = Entities::default();
+        let entity = entities.new_entity(None, "a", vec![]);
+        assert!(!entities
+            .get_entity_component_types(entity)
+            .unwrap()
+            .contains(&GlobalTransform::stable_type_id()));
+
+        entities.add_components(entity, vec![transform([1., 2., 0.], [1., 1., 1.], 0.)]);
+        assert_eq!(
+            global_transform(&entities, entity).position,
+            VecN::new([1., 2., 0.])
+        );
+
+        entities.remove_component::<Transform>(entity);
+        assert!(!entities
+            .get_entity_component_types(entity)
+            .unwrap()
+            .contains(&GlobalTransform::stable_type_id()));
+    }
+
+    #[test]
+    fn inserted_parents_update_children() {
+        let mut entities = Entities::default();
+        let a = entities.new_entity(Some(1), "a", vec![]);
+        let b = entities.new_entity(Some(2), "b", vec![]);
+        // Parents from prefabs or scenes only know the persistent id.
+        let parent =
+            |persistent_id| Parent::new(Entity::from_raw_parts(u32::MAX, 0), persistent_id);
+
+        let child = entities.new_entity(None, "child", vec![parent(1).into()]);
+        assert_eq!(entities.parent(child), Some(a));
+        assert_eq!(entities.children(a), vec![child]);
+        assert!(!entities.root_entities().contains(&child));
+
+        entities.replace_component(child, parent(2).into());
+        assert_eq!(entities.parent(child), Some(b));
+        assert!(entities.children(a).is_empty());
+
+        let other = entities.new_entity(None, "other", vec![]);
+        entities.add_components(other, vec![parent(2).into()]);
+        assert_eq!(entities.children(b), vec![child, other]);
+
+        // Parents that don't exist are left out.
+        let orphan = entities.new_entity(None, "orphan", vec![parent(3).into()]);
+        assert_eq!(entities.parent(orphan), None);
+        assert!(entities.root_entities().contains(&orphan));
+    }
+
+    #[test]
+    fn propagate_transforms() {
+        let mut entities = Entities::default();
+        let parent = entities.new_entity(
+            None,
+            "parent",
+            vec![transform(
+                [1., 0., 0.],
+                [2., 2., 1.],
+                std::f32::consts::FRAC_PI_2,
+            )],
+        );
+        // Without a transform, the parent's is passed on.
+        let group = entities.new_entity(None, "group", vec![]);
+        let child = entities.new_entity(
+            None,
+            "child",
+            vec![transform([1., 0., 1.], [0.5, 1., 1.], 0.5)],
+        );
+        entities.set_parent(group, Some(parent));
+        entities.set_parent(child, Some(group));
+
+        entities.propagate_transforms();
+
+        // Scaled by 2, then rotated a quarter turn clockwise, then moved.
+        let global = global_transform(&entities, child);
+        assert_eq!(global.position, VecN::new([1., -2., 1.]));
+        assert_eq!(global.scale, VecN::new([1., 2., 1.]));
+        assert!((global.rotation - (std::f32::consts::FRAC_PI_2 + 0.5)).abs() < 1e-6);
+
+        // Moving the parent moves the child.
+        entities
+            .entity_component_mut(parent, Transform::stable_type_id())
+            .unwrap()
+            .get_mut::<Transform>()
+            .position = VecN::new([0., 0., 0.]);
+        entities.propagate_transforms();
+        assert_eq!(
+            global_transform(&entities, child).position,
+            VecN::new([0., -2., 1.])
+        );
+    }
+
+    #[test]
+    fn fixed_steps_keep_the_previous_transform() {
+        let mut entities = Entities::default();
+        let entity = entities.new_entity(
+            None,
+            "entity",
+            vec![transform([0., 0., 0.], [1., 1., 1.], 0.)],
+        );
+        let move_to = |entities: &Entities, x: f32| {
+            *entities
+                .entity_component_mut(entity, Transform::stable_type_id())
+                .unwrap()
+                .get_mut::<Transform>()
+                .position
+                .x_mut() = x;
+        };
+
+        entities.start_fixed_step();
+        move_to(&entities, 2.);
+        entities.propagate_fixed_transforms();
+        let global = global_transform(&entities, entity);
+        assert_eq!(global.position, VecN::new([2., 0., 0.]));
+        assert_eq!(global.interpolated(0.25).position, VecN::new([0.5, 0., 0.]));
+
+        // Moves outside fixed steps aren't interpolated.
+        move_to(&entities, 4.);
+        entities.propagate_transforms();
+        let global = global_transform(&entities, entity);
+        assert_eq!(global.interpolated(0.25).position, VecN::new([4., 0., 0.]));
+    }
+
+    #[test]
+    fn parent_round_trips_through_storage() {
+        let mut entities = Entities::default();
+        let parent = entities.new_entity(Some(1), "parent", vec![]);
+        let child = entities.new_entity(Some(2), "child", vec![]);
+        entities.set_parent(child, Some(parent));
+
+        let stored = entities
+            .entity_component(child, Parent::stable_type_id())
+            .unwrap()
+            .get::<Parent>()
+            .to_stored();
+        let loaded = Parent::from_stored(stored, &iridium_assets::Assets::new()).unwrap();
+        assert_eq!(loaded.persistent_id(), 1);
+
+        // Reload the scene like loading does, with the parent created after the child.
+        entities.clear();
+        let child = entities.new_entity_unlinked(Some(2), "child", vec![loaded.into()]);
+        let parent = entities.new_entity(Some(1), "parent", vec![]);
+        entities.resolve_parents();
+
+        assert_eq!(entities.parent(child), Some(parent));
+        assert_eq!(entities.children(parent), vec![child]);
+    }
+}
@@ -6,10 +6,26 @@
 //!
 //! but it massively simplified the code the integrate UI into components.
 
+mod archetypes;
+pub use archetypes::*;
+mod borrow;
+pub use borrow::*;
+mod commands;
+pub use commands::*;
 mod components;
 pub use components::*;
 mod entities;
 pub use entities::*;
+mod entity;
+pub use entity::*;
+mod events;
+pub use events::*;
+mod hierarchy;
+mod query_filter;
+pub use query_filter::*;
+mod resources;
+pub use resources::*;
+mod scenes;
 mod world;
 pub use world::*;
 
@@ -0,0 +1,102 @@
+use iridium_reflect::StableTypeId;
+
+use crate::{Archetype, ComponentColumn};
+
+/// Filters which entities a query returns,
+/// without returning the components themselves.
+///
+/// Used by `Entities::query_by_type_id_filtered` and `query!`.
+#[derive(Clone, Debug, Default)]
+pub struct QueryFilter {
+    /// Entities must have all of these components.
+    pub with: Vec<StableTypeId>,
+    /// Entities must have none of these components.
+    pub without: Vec<StableTypeId>,
+    /// Entities must have all of these components,
+    /// and they must have changed since the system last ran.
+    pub changed: Vec<StableTypeId>,
+    /// Entities must have all of these components,
+    /// and they must have been added since the system last ran.
+    pub added: Vec<StableTypeId>,
+}
+
+impl QueryFilter {
+    /// Creates an empty filter, that lets every entity through.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Entities must have this component.
+    #[must_use]
+    pub fn with(mut self, component_type: StableTypeId) -> Self {
+        self.with.push(component_type);
+        self
+    }
+
+    /// Entities must not have this component.
+    #[must_use]
+    pub fn without(mut self, component_type: StableTypeId) -> Self {
+        self.without.push(component_type);
+        self
+    }
+
+    /// Entities must have this component,
+    /// and it must have changed since the system last ran.
+    #[must_use]
+    pub fn changed(mut self, component_type: StableTypeId) -> Self {
+        self.changed.push(component_type);
+        self
+    }
+
+    /// Entities must have this component,
+    /// and it must have been added since the system last ran.
+    #[must_use]
+    pub fn added(mut self, component_type: StableTypeId) -> Self {
+        self.added.push(component_type);
+        self
+    }
+
+    /// The component types entities must have,
+    /// not including the ones that are returned.
+    pub fn required(&self) -> impl Iterator<Item = StableTypeId> + '_ {
+        self.with
+            .iter()
+            .chain(&self.changed)
+            .chain(&self.added)
+            .copied()
+    }
+
+    /// Checks if every entity in an archetype could pass the filter.
+    ///
+    /// The changed and added ticks still need to be checked per entity.
+    #[must_use]
+    pub fn matches_archetype(&self, archetype: &Archetype) -> bool {
+        self.required()
+            .all(|component_type| archetype.has_type(component_type))
+            && !self
+                .without
+                .iter()
+                .any(|component_type| archetype.has_type(*component_type))
+    }
+
+    /// Gets the columns in an archetype that need their ticks checked,
+    /// in the form (changed columns, added columns).
+    ///
+    /// The archetype must match the filter.
+    pub(crate) fn tick_columns<'a>(
+        &self,
+        archetype: &'a Archetype,
+    ) -> (Vec<&'a dyn ComponentColumn>, Vec<&'a dyn ComponentColumn>) {
+        let column = |component_type: &StableTypeId| {
+            archetype
+                .column(*component_type)
+                .unwrap_or_else(|| unreachable!("Archetype was checked to match the filter"))
+        };
+
+        (
+            self.changed.iter().map(column).collect(),
+            self.added.iter().map(column).collect(),
+        )
+    }
+}
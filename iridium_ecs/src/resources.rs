@@ -0,0 +1,263 @@
+#![allow(clippy::mut_from_ref)]
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use iridium_reflect::StableTypeId;
+
+use crate::{Component, ComponentBox, ComponentTicks, Event, Events, Mut, Ref, SystemTicks};
+
+/// Stores the resources in the world.
+///
+/// Resources are global singletons, like the input state or the log,
+/// and there is at most one of each type.
+///
+/// They're components, so they can be saved and shown in the inspector like them,
+/// but they aren't attached to an entity.
+///
+/// Like `ComponentBox`, this uses internal mutability,
+/// and the borrows of each resource are checked at runtime.
+///
+/// Like components, getting a resource mutably marks it as changed.
+#[derive(Default)]
+pub struct Resources {
+    /// resource type => resource
+    resources: HashMap<StableTypeId, ComponentBox>,
+    /// resource type => when it was added and last changed
+    ticks: HashMap<StableTypeId, ComponentTicks>,
+    /// The change tick of `Entities`, which `World::run_systems` keeps up to date.
+    change_tick: AtomicU32,
+    /// event type => function that updates its `Events`
+    event_updaters: HashMap<StableTypeId, fn(&Self)>,
+}
+
+impl Resources {
+    /// Creates an empty set of resources.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a resource, replacing the existing one of the same type.
+    ///
+    /// Returns the replaced resource.
+    pub fn insert(&mut self, resource: ComponentBox) -> Option<ComponentBox> {
+        self.ticks.insert(
+            resource.stable_type_id(),
+            ComponentTicks::new(self.change_tick()),
+        );
+        self.resources.insert(resource.stable_type_id(), resource)
+    }
+
+    /// Removes a resource, returning it if it existed.
+    pub fn remove<T: Component>(&mut self) -> Option<T> {
+        self.remove_by_type_id(T::stable_type_id())
+            .map(ComponentBox::take)
+    }
+
+    /// Removes a resource by its type id, returning it if it existed.
+    pub fn remove_by_type_id(&mut self, resource_type: StableTypeId) -> Option<ComponentBox> {
+        self.ticks.remove(&resource_type);
+        self.resources.remove(&resource_type)
+    }
+
+    /// Checks if there is a resource of type `T`.
+    #[must_use]
+    pub fn contains<T: Component>(&self) -> bool {
+        self.resources.contains_key(&T::stable_type_id())
+    }
+
+    /// Gets the resource of type `T`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no resource of type `T`, or it's borrowed mutably.
+    #[must_use]
+    pub fn get<T: Component>(&self) -> Ref<'_, T> {
+        self.try_get()
+            .unwrap_or_else(|| panic!("Resource {} not found", T::type_name()))
+    }
+
+    /// Gets the resource of type `T` mutably.
+    ///
+    /// # Panics
+    ///
+    /// If there is no resource of type `T`, or it's already borrowed.
+    #[must_use]
+    pub fn get_mut<T: Component>(&self) -> Mut<'_, T> {
+        self.try_get_mut()
+            .unwrap_or_else(|| panic!("Resource {} not found", T::type_name()))
+    }
+
+    /// Gets the resource of type `T`, or `None` if there isn't one.
+    #[must_use]
+    pub fn try_get<T: Component>(&self) -> Option<Ref<'_, T>> {
+        self.get_by_type_id(T::stable_type_id())
+            .map(ComponentBox::get)
+    }
+
+    /// Gets the resource of type `T` mutably, or `None` if there isn't one.
+    #[must_use]
+    pub fn try_get_mut<T: Component>(&self) -> Option<Mut<'_, T>> {
+        let resource = self.get_by_type_id(T::stable_type_id())?.get_mut();
+        self.set_changed_by_type_id(T::stable_type_id());
+        Some(resource)
+    }
+
+    /// Gets a resource by its type id.
+    ///
+    /// Changes made through this aren't tracked,
+    /// use `set_changed_by_type_id` to mark the resource as changed.
+    #[must_use]
+    pub fn get_by_type_id(&self, resource_type: StableTypeId) -> Option<&ComponentBox> {
+        self.resources.get(&resource_type)
+    }
+
+    /// The current change tick.
+    #[must_use]
+    pub fn change_tick(&self) -> u32 {
+        self.change_tick.load(Ordering::Relaxed)
+    }
+
+    /// Sets the current change tick,
+    /// which resources changed from now on are marked with.
+    ///
+    /// `World::run_systems` sets this to the change tick of `Entities`.
+    pub fn set_change_tick(&self, tick: u32) {
+        self.change_tick.store(tick, Ordering::Relaxed);
+    }
+
+    /// Marks a resource as changed, if it exists.
+    pub fn set_changed_by_type_id(&self, resource_type: StableTypeId) {
+        if let Some(ticks) = self.ticks.get(&resource_type) {
+            ticks.set_changed(self.change_tick());
+        }
+    }
+
+    /// Checks if the resource of type `T` was changed since the system last ran.
+    ///
+    /// Adding a resource counts as changing it.
+    #[must_use]
+    pub fn is_changed<T: Component>(&self, system_ticks: SystemTicks) -> bool {
+        self.is_changed_by_type_id(T::stable_type_id(), system_ticks)
+    }
+
+    /// Checks if a resource was changed since the system last ran.
+    ///
+    /// Returns false if the resource doesn't exist.
+    #[must_use]
+    pub fn is_changed_by_type_id(
+        &self,
+        resource_type: StableTypeId,
+        system_ticks: SystemTicks,
+    ) -> bool {
+        self.ticks
+            .get(&resource_type)
+            .is_some_and(|ticks| ticks.is_changed(system_ticks))
+    }
+
+    /// Adds an `Events<T>` resource, if there isn't one,
+    /// and updates it in `update_events` so its events expire.
+    pub fn add_events<T: Event>(&mut self) {
+        if !self.contains::<Events<T>>() {
+            self.insert(Events::<T>::new().into());
+        }
+
+        self.event_updaters
+            .insert(T::stable_type_id(), |resources: &Self| {
+                if let Some(mut events) = resources.try_get_mut::<Events<T>>() {
+                    events.update();
+                }
+            });
+    }
+
+    /// Updates every `Events` added with `add_events`,
+    /// dropping the events sent two updates ago.
+    ///
+    /// Called once a frame by `World::run_systems`.
+    pub fn update_events(&self) {
+        for updater in self.event_updaters.values() {
+            updater(self);
+        }
+    }
+
+    /// Gets every resource, sorted by type name.
+    #[must_use]
+    pub fn all(&self) -> Vec<&ComponentBox> {
+        let mut resources = self.resources.values().collect::<Vec<_>>();
+        resources.sort_by_key(|resource| resource.type_name());
+        resources
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iridium_ecs_macros::HasStableTypeId;
+
+    use super::*;
+    use crate::Name;
+
+    #[derive(HasStableTypeId)]
+    struct Ping;
+
+    impl Event for Ping {}
+
+    #[test]
+    fn insert_replaces() {
+        let mut resources = Resources::new();
+        assert!(resources.try_get::<Name>().is_none());
+
+        assert!(resources
+            .insert(ComponentBox::new(Name {
+                name: "a".to_owned(),
+            }))
+            .is_none());
+        let old = resources.insert(ComponentBox::new(Name {
+            name: "b".to_owned(),
+        }));
+
+        assert_eq!(old.unwrap().get::<Name>().name, "a");
+        assert_eq!(resources.get::<Name>().name, "b");
+
+        resources.get_mut::<Name>().name = "c".to_owned();
+        assert_eq!(resources.remove::<Name>().unwrap().name, "c");
+        assert!(!resources.contains::<Name>());
+    }
+
+    #[test]
+    fn update_events() {
+        let mut resources = Resources::new();
+        resources.add_events::<Ping>();
+
+        resources.get_mut::<Events<Ping>>().send(Ping);
+        resources.update_events();
+        assert_eq!(resources.get::<Events<Ping>>().len(), 1);
+
+        resources.update_events();
+        assert!(resources.get::<Events<Ping>>().is_empty());
+    }
+
+    #[test]
+    fn tracks_changes() {
+        let mut resources = Resources::new();
+        resources.insert(ComponentBox::new(Name {
+            name: "a".to_owned(),
+        }));
+
+        resources.set_change_tick(5);
+        let ticks = SystemTicks {
+            last_run: 4,
+            current: 5,
+        };
+        assert!(!resources.is_changed::<Name>(ticks));
+
+        // Only getting it mutably counts as a change.
+        let _ = resources.get::<Name>();
+        assert!(!resources.is_changed::<Name>(ticks));
+
+        resources.get_mut::<Name>().name = "b".to_owned();
+        assert!(resources.is_changed::<Name>(ticks));
+    }
+}
@@ -0,0 +1,39 @@
+use iridium_reflect::HasStableTypeId;
+
+use crate::{Entities, Entity, SceneOrigin};
+
+impl Entities {
+    /// Gets the entities loaded from a scene.
+    #[must_use]
+    pub fn scene_entities(&self, scene: &str) -> Vec<Entity> {
+        self.query_by_type_id([SceneOrigin::stable_type_id()])
+            .filter(|(_, [origin])| origin.get::<SceneOrigin>().scene == scene)
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    /// Gets the scenes that entities were loaded from, sorted.
+    #[must_use]
+    pub fn scenes(&self) -> Vec<String> {
+        let mut scenes = self
+            .query_by_type_id([SceneOrigin::stable_type_id()])
+            .map(|(_, [origin])| origin.get::<SceneOrigin>().scene.clone())
+            .collect::<Vec<_>>();
+        scenes.sort();
+        scenes.dedup();
+        scenes
+    }
+
+    /// Deletes the entities loaded from a scene.
+    ///
+    /// Only the scene's own entities are deleted,
+    /// so their children from other scenes are kept, and become root entities.
+    ///
+    /// Returns how many were deleted.
+    pub fn unload_scene(&mut self, scene: &str) -> usize {
+        self.scene_entities(scene)
+            .into_iter()
+            .filter(|&entity| self.delete_entity_keep_children(entity))
+            .count()
+    }
+}
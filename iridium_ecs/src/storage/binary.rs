@@ -0,0 +1,607 @@
+use std::{collections::HashMap, fmt};
+
+use iridium_reflect::{stable_type_id_from_name, StableTypeId};
+
+use super::{ReadError, SourcedComponent, StoredComponent, StoredValue, StoredWorld};
+use crate::Entities;
+
+/// The version of the binary scene format.
+///
+/// This is separate from `FORMAT_VERSION`, as the two formats change separately.
+///
+/// Version 2 stores field names once in a table, and numbers as fixed width integers and floats.
+/// Version 1 scenes can still be read.
+pub const BINARY_FORMAT_VERSION: u32 = 2;
+
+/// The bytes every binary scene starts with.
+const MAGIC: &[u8; 4] = b"IRSC";
+
+/// The tags written before each `StoredValue`.
+mod tag {
+    pub const NULL: u8 = 0;
+    pub const FALSE: u8 = 1;
+    pub const TRUE: u8 = 2;
+    /// A number kept as text, for numbers that wouldn't read back the same.
+    pub const NUMBER: u8 = 3;
+    pub const STRING: u8 = 4;
+    pub const ARRAY: u8 = 5;
+    pub const OBJECT: u8 = 6;
+    pub const INT: u8 = 7;
+    pub const UINT: u8 = 8;
+    pub const FLOAT: u8 = 9;
+    /// A float written like `{:?}`, as in `1.0`, which reads back the same way.
+    pub const FLOAT_DEBUG: u8 = 10;
+}
+
+/// A binary scene that couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BinaryError {
+    /// The offset in bytes of the problem.
+    pub offset: usize,
+    /// What the problem is.
+    pub message: String,
+}
+
+impl fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+/// The type name used for a type id that isn't registered.
+///
+/// Binary scenes only store type ids,
+/// so these can't be turned back into names.
+#[must_use]
+pub fn unknown_type_name(type_id: StableTypeId) -> String {
+    format!("{type_id:#018x}")
+}
+
+/// Writes the parts of a binary scene.
+///
+/// Integers and floats are little endian,
+/// and lengths, versions and field name indices are LEB128 encoded.
+#[derive(Default)]
+struct BinaryWriter {
+    buffer: Vec<u8>,
+    /// The field names written, in the order they're first used.
+    names: Vec<String>,
+    /// The index of each field name in `names`.
+    name_indices: HashMap<String, usize>,
+}
+
+impl BinaryWriter {
+    fn varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.buffer.push(byte);
+                return;
+            }
+            self.buffer.push(byte | 0x80);
+        }
+    }
+
+    fn len(&mut self, len: usize) {
+        self.varint(len as u64);
+    }
+
+    fn string(&mut self, string: &str) {
+        self.len(string.len());
+        self.buffer.extend_from_slice(string.as_bytes());
+    }
+
+    /// Writes a field name as its index in the name table.
+    fn name(&mut self, name: &str) {
+        let index = match self.name_indices.get(name) {
+            Some(index) => *index,
+            None => {
+                let index = self.names.len();
+                self.names.push(name.to_string());
+                self.name_indices.insert(name.to_string(), index);
+                index
+            }
+        };
+        self.len(index);
+    }
+
+    /// Writes a number as a fixed width integer or float,
+    /// or as text if it wouldn't read back as the same text, like hex numbers.
+    fn number(&mut self, number: &str) {
+        if let Some(int) = number
+            .parse::<i64>()
+            .ok()
+            .filter(|int| int.to_string() == number)
+        {
+            self.buffer.push(tag::INT);
+            self.buffer.extend_from_slice(&int.to_le_bytes());
+        } else if let Some(uint) = number
+            .parse::<u64>()
+            .ok()
+            .filter(|uint| uint.to_string() == number)
+        {
+            self.buffer.push(tag::UINT);
+            self.buffer.extend_from_slice(&uint.to_le_bytes());
+        } else if let Some(float) = number
+            .parse::<f64>()
+            .ok()
+            .filter(|float| float.to_string() == number)
+        {
+            self.buffer.push(tag::FLOAT);
+            self.buffer.extend_from_slice(&float.to_le_bytes());
+        } else if let Some(float) = number
+            .parse::<f64>()
+            .ok()
+            .filter(|float| format!("{float:?}") == number)
+        {
+            self.buffer.push(tag::FLOAT_DEBUG);
+            self.buffer.extend_from_slice(&float.to_le_bytes());
+        } else {
+            self.buffer.push(tag::NUMBER);
+            self.string(number);
+        }
+    }
+
+    fn value(&mut self, value: &StoredValue) {
+        match value {
+            StoredValue::Null => self.buffer.push(tag::NULL),
+            StoredValue::Bool(false) => self.buffer.push(tag::FALSE),
+            StoredValue::Bool(true) => self.buffer.push(tag::TRUE),
+            StoredValue::Number(number) => self.number(number),
+            StoredValue::String(string) => {
+                self.buffer.push(tag::STRING);
+                self.string(string);
+            }
+            StoredValue::Array(values) => {
+                self.buffer.push(tag::ARRAY);
+                self.len(values.len());
+                for value in values {
+                    self.value(value);
+                }
+            }
+            StoredValue::Object(members) => {
+                self.buffer.push(tag::OBJECT);
+                self.fields(members);
+            }
+        }
+    }
+
+    /// Writes the members of an object, sorted by key so saves are stable.
+    fn fields(&mut self, fields: &HashMap<String, StoredValue>) {
+        let mut fields = fields.iter().collect::<Vec<_>>();
+        fields.sort_by_key(|(key, _)| *key);
+
+        self.len(fields.len());
+        for (key, value) in fields {
+            self.name(key);
+            self.value(value);
+        }
+    }
+
+    /// Writes a component keyed by its type id.
+    fn component(&mut self, stored: &StoredComponent) {
+        self.buffer
+            .extend_from_slice(&stable_type_id_from_name(&stored.type_name).to_le_bytes());
+        self.fields(&stored.fields);
+    }
+}
+
+/// Reads the parts of a binary scene.
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+    /// The field names of the name table,
+    /// `None` for version 1 scenes, which wrote the names in place.
+    names: Option<Vec<String>>,
+}
+
+impl<'a> BinaryReader<'a> {
+    const fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes,
+            offset: 0,
+            names: None,
+        }
+    }
+
+    fn error(&self, message: &str) -> ReadError {
+        ReadError::BinaryError(BinaryError {
+            offset: self.offset,
+            message: message.to_string(),
+        })
+    }
+
+    fn take(&mut self, len: usize) -> Result<&[u8], ReadError> {
+        let end = self
+            .offset
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| self.error("Unexpected end of file"))?;
+
+        let bytes = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, ReadError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ReadError> {
+        Ok(self.take(N)?.try_into().expect("Took N bytes"))
+    }
+
+    fn u64(&mut self) -> Result<u64, ReadError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn u128(&mut self) -> Result<u128, ReadError> {
+        Ok(u128::from_le_bytes(self.array()?))
+    }
+
+    fn varint(&mut self) -> Result<u64, ReadError> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= u64::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(self.error("Integer is too long"))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReadError> {
+        u32::try_from(self.varint()?).map_err(|_| self.error("Expected a 32 bit integer"))
+    }
+
+    /// A length, which can't be longer than the rest of the file,
+    /// so corrupt lengths don't allocate huge buffers.
+    fn len(&mut self) -> Result<usize, ReadError> {
+        usize::try_from(self.varint()?)
+            .ok()
+            .filter(|len| *len <= self.bytes.len() - self.offset)
+            .ok_or_else(|| self.error("Length is longer than the file"))
+    }
+
+    fn string(&mut self) -> Result<String, ReadError> {
+        let len = self.len()?;
+        let start = self.offset;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|_| {
+            self.offset = start;
+            self.error("String isn't valid UTF-8")
+        })
+    }
+
+    /// A field name, which is an index in the name table.
+    fn name(&mut self) -> Result<String, ReadError> {
+        if self.names.is_none() {
+            return self.string();
+        }
+
+        let start = self.offset;
+        let index = self.varint()?;
+        let name = usize::try_from(index)
+            .ok()
+            .and_then(|index| self.names.as_ref()?.get(index).cloned());
+        name.ok_or_else(|| {
+            self.offset = start;
+            self.error("Unknown field name")
+        })
+    }
+
+    fn value(&mut self) -> Result<StoredValue, ReadError> {
+        Ok(match self.u8()? {
+            tag::NULL => StoredValue::Null,
+            tag::FALSE => StoredValue::Bool(false),
+            tag::TRUE => StoredValue::Bool(true),
+            tag::NUMBER => StoredValue::Number(self.string()?),
+            tag::INT => StoredValue::Number(i64::from_le_bytes(self.array()?).to_string()),
+            tag::UINT => StoredValue::Number(u64::from_le_bytes(self.array()?).to_string()),
+            tag::FLOAT => StoredValue::Number(f64::from_le_bytes(self.array()?).to_string()),
+            tag::FLOAT_DEBUG => {
+                StoredValue::Number(format!("{:?}", f64::from_le_bytes(self.array()?)))
+            }
+            tag::STRING => StoredValue::String(self.string()?),
+            tag::ARRAY => {
+                let len = self.len()?;
+                StoredValue::Array((0..len).map(|_| self.value()).collect::<Result<_, _>>()?)
+            }
+            tag::OBJECT => StoredValue::Object(self.fields()?),
+            _ => {
+                self.offset -= 1;
+                return Err(self.error("Unknown value tag"));
+            }
+        })
+    }
+
+    fn fields(&mut self) -> Result<HashMap<String, StoredValue>, ReadError> {
+        let len = self.len()?;
+        (0..len)
+            .map(|_| Ok((self.name()?, self.value()?)))
+            .collect()
+    }
+
+    /// A component keyed by its type id.
+    ///
+    /// The type name is looked up in the registered components,
+    /// types that aren't registered are named with `unknown_type_name`.
+    fn component(&mut self, entities: &Entities) -> Result<SourcedComponent, ReadError> {
+        let type_id = self.u64()?;
+        let type_name = entities.component_info_from_type_id(type_id).map_or_else(
+            || unknown_type_name(type_id),
+            |info| info.type_name.to_string(),
+        );
+
+        Ok(SourcedComponent::new(StoredComponent {
+            type_name,
+            fields: self.fields()?,
+        }))
+    }
+}
+
+/// Writes a world in the binary scene format.
+///
+/// Components and resources are keyed by their `StableTypeId`,
+/// and fields by their index in a table of field names at the start of the file.
+#[must_use]
+pub fn write_binary_world(world: &StoredWorld) -> Vec<u8> {
+    // The field names are only known once everything's written,
+    // so the name table is added in front after.
+    let mut writer = BinaryWriter::default();
+
+    let mut schema_versions = world.schema_versions.iter().collect::<Vec<_>>();
+    schema_versions.sort();
+    writer.len(schema_versions.len());
+    for (type_name, version) in schema_versions {
+        writer
+            .buffer
+            .extend_from_slice(&stable_type_id_from_name(type_name).to_le_bytes());
+        writer.varint((*version).into());
+    }
+
+    writer.len(world.entities.len());
+    for (id, _, components) in &world.entities {
+        writer.buffer.extend_from_slice(&id.to_le_bytes());
+        writer.len(components.len());
+        for sourced in components {
+            writer.component(&sourced.stored);
+        }
+    }
+
+    writer.len(world.resources.len());
+    for sourced in &world.resources {
+        writer.component(&sourced.stored);
+    }
+
+    let mut header = BinaryWriter::default();
+    header.buffer.extend_from_slice(MAGIC);
+    header.varint(BINARY_FORMAT_VERSION.into());
+    header.len(writer.names.len());
+    for name in &writer.names {
+        header.string(name);
+    }
+
+    header.buffer.extend(writer.buffer);
+    header.buffer
+}
+
+/// Reads a world in the binary scene format.
+///
+/// Type ids are turned back to names with the components registered in `entities`.
+///
+/// # Errors
+///
+/// Will return an error if the bytes aren't a binary scene,
+/// or it was saved with a newer format version than this reads.
+pub fn read_binary_world(bytes: &[u8], entities: &Entities) -> Result<StoredWorld, ReadError> {
+    let mut reader = BinaryReader::new(bytes);
+
+    if reader.take(MAGIC.len())? != MAGIC {
+        reader.offset = 0;
+        return Err(reader.error("Not a binary scene"));
+    }
+    let format_version = reader.u32()?;
+    if format_version > BINARY_FORMAT_VERSION {
+        return Err(ReadError::UnsupportedFormatVersion(format_version));
+    }
+
+    if format_version >= 2 {
+        let names = (0..reader.len()?)
+            .map(|_| reader.string())
+            .collect::<Result<_, _>>()?;
+        reader.names = Some(names);
+    }
+
+    let mut schema_versions = HashMap::new();
+    for _ in 0..reader.len()? {
+        let type_id = reader.u64()?;
+        let type_name = entities.component_info_from_type_id(type_id).map_or_else(
+            || unknown_type_name(type_id),
+            |info| info.type_name.to_string(),
+        );
+        schema_versions.insert(type_name, reader.u32()?);
+    }
+
+    let mut stored_entities = Vec::new();
+    for _ in 0..reader.len()? {
+        let id = reader.u128()?;
+        let components = (0..reader.len()?)
+            .map(|_| reader.component(entities))
+            .collect::<Result<_, _>>()?;
+        stored_entities.push((id, 0, components));
+    }
+
+    let resources = (0..reader.len()?)
+        .map(|_| reader.component(entities))
+        .collect::<Result<_, _>>()?;
+
+    if reader.offset != bytes.len() {
+        return Err(reader.error("Expected the end of the file"));
+    }
+
+    Ok(StoredWorld {
+        entities: stored_entities,
+        resources,
+        schema_versions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use iridium_assets::Assets;
+
+    use super::*;
+    use crate::{systems::Systems, Name, Transform, World};
+    use iridium_reflect::HasStableTypeId;
+
+    #[test]
+    fn world_round_trips() {
+        let mut world = World::new(Entities::default(), Systems::new());
+        let mut transform = Transform::default();
+        *transform.scale.x_mut() = 3.5;
+        world
+            .entities
+            .new_entity(Some(u128::MAX), "Big", vec![transform.into()]);
+        world
+            .entities
+            .new_entity(Some(1), "Small", vec![Transform::default().into()]);
+
+        let file = std::env::temp_dir().join("iridium_binary_world_round_trips.iscene");
+        let file = file.to_str().unwrap();
+        world.save(file);
+
+        let bytes = std::fs::read(file).unwrap();
+        assert!(bytes.starts_with(MAGIC));
+        assert!(!bytes.windows(9).any(|window| window == b"Transform"));
+        // Field names are only written once, in the name table.
+        assert_eq!(
+            bytes
+                .windows(8)
+                .filter(|window| window == b"position")
+                .count(),
+            1
+        );
+
+        let mut loaded = World::new(Entities::default(), Systems::new());
+        loaded.load(file, &Assets::new()).unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        let entity = loaded
+            .entities
+            .entity_from_persistent_id(u128::MAX)
+            .unwrap();
+        let name = loaded
+            .entities
+            .entity_component(entity, Name::stable_type_id())
+            .unwrap();
+        assert_eq!(name.get::<Name>().name, "Big");
+        let transform = loaded
+            .entities
+            .entity_component(entity, Transform::stable_type_id())
+            .unwrap();
+        assert!((transform.get::<Transform>().scale.x() - 3.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn values_round_trip() {
+        let value = StoredValue::Object(HashMap::from([
+            ("none".to_string(), StoredValue::Null),
+            (
+                "list".to_string(),
+                StoredValue::Array(vec![
+                    StoredValue::Bool(true),
+                    StoredValue::Number("-0x10".to_string()),
+                    StoredValue::Number("-7".to_string()),
+                    StoredValue::Number(u64::MAX.to_string()),
+                    StoredValue::Number("0.1".to_string()),
+                    StoredValue::Number("1.0".to_string()),
+                    StoredValue::String("é\n".to_string()),
+                ]),
+            ),
+        ]));
+
+        let mut writer = BinaryWriter::default();
+        writer.value(&value);
+        writer.varint(u64::MAX);
+
+        let mut reader = BinaryReader::new(&writer.buffer);
+        reader.names = Some(writer.names.clone());
+        assert_eq!(reader.value().unwrap(), value);
+        assert_eq!(reader.varint().unwrap(), u64::MAX);
+        assert_eq!(reader.offset, writer.buffer.len());
+    }
+
+    #[test]
+    fn numbers_are_fixed_width() {
+        let mut writer = BinaryWriter::default();
+        writer.value(&StoredValue::Number("3.5".to_string()));
+        writer.value(&StoredValue::Number("-1".to_string()));
+        writer.value(&StoredValue::Number("1.0".to_string()));
+
+        let mut expected = vec![tag::FLOAT];
+        expected.extend(3.5_f64.to_le_bytes());
+        expected.push(tag::INT);
+        expected.extend((-1_i64).to_le_bytes());
+        expected.push(tag::FLOAT_DEBUG);
+        expected.extend(1.0_f64.to_le_bytes());
+        assert_eq!(writer.buffer, expected);
+    }
+
+    #[test]
+    fn reads_version_1() {
+        // Version 1 wrote field names in place.
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([1, 0, 0, 1]);
+        bytes.extend(Name::stable_type_id().to_le_bytes());
+        bytes.extend([1, 4]);
+        bytes.extend(b"name");
+        bytes.extend([tag::NUMBER, 3]);
+        bytes.extend(b"1.0");
+
+        let world = read_binary_world(&bytes, &Entities::default()).unwrap();
+        assert_eq!(
+            world.resources[0].stored.fields["name"],
+            StoredValue::Number("1.0".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_files_have_offsets() {
+        let entities = Entities::default();
+        let error = |bytes: &[u8]| match read_binary_world(bytes, &entities) {
+            Err(ReadError::BinaryError(error)) => error,
+            _ => panic!("Expected a binary error"),
+        };
+
+        assert_eq!(error(b"{ entities: {} }").message, "Not a binary scene");
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend([2, 0, 0, 1, 0xFF]);
+        let truncated = error(&bytes);
+        assert_eq!(
+            (truncated.offset, truncated.message.as_str()),
+            (8, "Unexpected end of file")
+        );
+
+        bytes = MAGIC.to_vec();
+        bytes.extend([2, 0, 0, 0, 1]);
+        bytes.extend(Name::stable_type_id().to_le_bytes());
+        bytes.extend([1, 0, tag::NULL]);
+        let unknown_name = error(&bytes);
+        assert_eq!(
+            (unknown_name.offset, unknown_name.message.as_str()),
+            (18, "Unknown field name")
+        );
+
+        bytes = MAGIC.to_vec();
+        bytes.extend([2, 0, 0, 0, 0, 0]);
+        assert_eq!(error(&bytes).message, "Expected the end of the file");
+
+        assert!(matches!(
+            read_binary_world(&[b'I', b'R', b'S', b'C', 3], &entities),
+            Err(ReadError::UnsupportedFormatVersion(3))
+        ));
+    }
+}
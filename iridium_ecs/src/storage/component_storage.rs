@@ -1,39 +1,13 @@
 use iridium_assets::Assets;
 use std::collections::HashMap;
 
+use super::{FieldStorage, StoredValue};
 use crate::{Component, ComponentBox};
 
-/// A field in a `StoredComponent`.
-pub struct StoredComponentField {
-    /// The data of the field.
-    pub string: String,
-    /// Whether the field is a string.
-    ///
-    /// If true, it's stored in json as `"x"` instead of `x`.
-    pub is_string: bool,
-}
-
-impl StoredComponentField {
-    /// Creates a new `StoredComponentField`.
-    #[must_use]
-    pub const fn new(string: String, is_string: bool) -> Self {
-        Self { string, is_string }
-    }
-
-    /// Creates a `StoredComponentField` from a json5 value.
-    #[must_use]
-    pub fn from_json5(value: &str) -> Self {
-        if value.starts_with('"') && value.ends_with('"') {
-            Self::new(value[1..value.len() - 1].to_string(), true)
-        } else {
-            Self::new(value.to_string(), false)
-        }
-    }
-}
-
 /// A component as it is stored.
 ///
 /// This is created before storage, and returned from storage.
+#[derive(Debug, Clone, PartialEq)]
 pub struct StoredComponent {
     /// The type name of the component.
     pub type_name: String,
@@ -41,15 +15,56 @@ pub struct StoredComponent {
     ///
     /// This may not be a 1:1 mapping to the fields of the component,
     /// as some fields are only important at runtime.
-    pub fields: HashMap<String, StoredComponentField>,
+    pub fields: HashMap<String, StoredValue>,
 }
 
 impl StoredComponent {
-    /// Gets a value from the fields.
+    /// Gets a field, read as the given type.
     ///
     /// This is moved from the fields.
-    pub fn get(&mut self, key: &str) -> Option<String> {
-        Some(self.fields.remove(key)?.string)
+    /// Returns `None` if the field is missing or isn't valid for the type.
+    pub fn get<T: FieldStorage>(&mut self, key: &str) -> Option<T> {
+        T::from_stored_value(self.fields.remove(key)?)
+    }
+
+    /// Sets a field to the value.
+    pub fn insert<T: FieldStorage>(&mut self, key: &str, value: &T) {
+        self.fields.insert(key.to_string(), value.to_stored_value());
+    }
+}
+
+/// Why a stored component couldn't be read.
+///
+/// Components that don't say leave everything empty.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldError {
+    /// The field that couldn't be read.
+    pub field: Option<String>,
+    /// The type the field should have, as written in the component.
+    pub expected: Option<&'static str>,
+    /// Whether the field was missing, rather than invalid.
+    pub missing: bool,
+}
+
+impl FieldError {
+    /// The field is missing.
+    #[must_use]
+    pub fn missing(field: &str, expected: &'static str) -> Self {
+        Self {
+            field: Some(field.to_string()),
+            expected: Some(expected),
+            missing: true,
+        }
+    }
+
+    /// The field's value isn't valid for its type.
+    #[must_use]
+    pub fn invalid(field: &str, expected: &'static str) -> Self {
+        Self {
+            field: Some(field.to_string()),
+            expected: Some(expected),
+            missing: false,
+        }
     }
 }
 
@@ -63,17 +78,57 @@ pub trait ComponentStorage {
     where
         Self: Sized;
 
+    /// Try to create a component from a stored component,
+    /// saying which field was invalid if it can't.
+    ///
+    /// The derive implements this, manual impls only say that it failed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the field that was missing or invalid.
+    fn try_from_stored(stored: StoredComponent, assets: &Assets) -> Result<Self, FieldError>
+    where
+        Self: Sized,
+    {
+        Self::from_stored(stored, assets).ok_or_else(FieldError::default)
+    }
+
     /// Try to create a component from a stored component.
     ///
     /// Returns a `Component` instead of `Self`.
-    #[must_use]
-    fn from_stored_component(stored: StoredComponent, assets: &Assets) -> Option<ComponentBox>
+    ///
+    /// # Errors
+    ///
+    /// Returns the field that was missing or invalid.
+    fn from_stored_component(
+        stored: StoredComponent,
+        assets: &Assets,
+    ) -> Result<ComponentBox, FieldError>
     where
         Self: Sized + Component,
     {
-        Self::from_stored(stored, assets).map(Into::into)
+        Self::try_from_stored(stored, assets).map(Into::into)
     }
 
     /// Create a stored component from a component.
     fn to_stored(&self) -> StoredComponent;
+
+    /// The version of the stored fields.
+    ///
+    /// Increase this when fields are renamed or retyped,
+    /// and register a `Migration` upgrading from the previous version.
+    fn schema_version() -> u32
+    where
+        Self: Sized,
+    {
+        0
+    }
+
+    /// Whether the component is written when saving.
+    ///
+    /// Components that are rebuilt from others when loading,
+    /// like `Children`, aren't.
+    fn is_stored(&self) -> bool {
+        true
+    }
 }
@@ -0,0 +1,801 @@
+use std::{iter::Peekable, str::Chars};
+
+/// A position in a json5 file.
+///
+/// Lines and columns start at 1.
+/// Values that weren't parsed, like the ones the writer creates, are at 0, 0.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    /// The line, starting at 1.
+    pub line: usize,
+    /// The column in characters, starting at 1.
+    pub column: usize,
+}
+
+/// An error in a json5 file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Json5Error {
+    /// Where the error is.
+    pub position: Position,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl Json5Error {
+    /// Creates an error at the given position.
+    #[must_use]
+    pub fn new(position: Position, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Json5Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at line {}, column {}",
+            self.message, self.position.line, self.position.column
+        )
+    }
+}
+
+/// The kind of a json5 value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json5Kind {
+    /// `null`.
+    Null,
+    /// `true` or `false`.
+    Bool(bool),
+    /// A number, kept as it was written so no precision is lost.
+    Number(String),
+    /// A string, with the escapes resolved.
+    String(String),
+    /// A bare identifier, like `ArrowUp`.
+    ///
+    /// This isn't valid json5, but scenes use it for enums.
+    Identifier(String),
+    /// `[a, b, c]`.
+    Array(Vec<Json5Value>),
+    /// `{ key: value }`, in the order it was written.
+    Object(Vec<(String, Json5Value)>),
+}
+
+/// A json5 value, and where it is in the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Json5Value {
+    /// The value.
+    pub kind: Json5Kind,
+    /// Where the value starts.
+    pub position: Position,
+}
+
+impl Json5Value {
+    /// Creates a value that wasn't parsed from a file.
+    #[must_use]
+    pub fn new(kind: Json5Kind) -> Self {
+        Self {
+            kind,
+            position: Position::default(),
+        }
+    }
+
+    /// The members if this is an object.
+    #[must_use]
+    pub fn as_object(&self) -> Option<&[(String, Self)]> {
+        match &self.kind {
+            Json5Kind::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    /// Gets a member of an object.
+    ///
+    /// Returns `None` if this isn't an object, or doesn't have the member.
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&Self> {
+        self.as_object()?
+            .iter()
+            .find(|(member_key, _)| member_key == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Creates an error at this value.
+    #[must_use]
+    pub fn error(&self, message: impl Into<String>) -> Json5Error {
+        Json5Error::new(self.position, message)
+    }
+
+    /// Writes the value as json5.
+    ///
+    /// Objects are written over several lines, with trailing commas,
+    /// and `indent` is the indentation level of the line the value starts on.
+    /// Arrays without objects or arrays in them are kept on one line.
+    pub fn write(&self, buffer: &mut String, indent: usize) {
+        match &self.kind {
+            Json5Kind::Null => buffer.push_str("null"),
+            Json5Kind::Bool(value) => buffer.push_str(if *value { "true" } else { "false" }),
+            Json5Kind::Number(value) | Json5Kind::Identifier(value) => buffer.push_str(value),
+            Json5Kind::String(value) => write_json5_string(buffer, value),
+            Json5Kind::Array(values) => {
+                let inline = values
+                    .iter()
+                    .all(|value| !matches!(value.kind, Json5Kind::Array(_) | Json5Kind::Object(_)));
+
+                buffer.push('[');
+                if inline {
+                    for (i, value) in values.iter().enumerate() {
+                        if i != 0 {
+                            buffer.push_str(", ");
+                        }
+                        value.write(buffer, indent);
+                    }
+                } else {
+                    buffer.push('\n');
+                    for value in values {
+                        push_indent(buffer, indent + 1);
+                        value.write(buffer, indent + 1);
+                        buffer.push_str(",\n");
+                    }
+                    push_indent(buffer, indent);
+                }
+                buffer.push(']');
+            }
+            Json5Kind::Object(members) => {
+                if members.is_empty() {
+                    buffer.push_str("{}");
+                    return;
+                }
+
+                buffer.push_str("{\n");
+                for (key, value) in members {
+                    push_indent(buffer, indent + 1);
+                    write_json5_key(buffer, key);
+                    buffer.push_str(": ");
+                    value.write(buffer, indent + 1);
+                    buffer.push_str(",\n");
+                }
+                push_indent(buffer, indent);
+                buffer.push('}');
+            }
+        }
+    }
+
+    /// The value as json5.
+    #[must_use]
+    pub fn to_json5(&self) -> String {
+        let mut buffer = String::new();
+        self.write(&mut buffer, 0);
+        buffer
+    }
+}
+
+fn push_indent(buffer: &mut String, indent: usize) {
+    for _ in 0..indent {
+        buffer.push_str("    ");
+    }
+}
+
+/// Whether the string can be written without quotes, as a key or identifier.
+fn is_identifier(string: &str) -> bool {
+    let mut chars = string.chars();
+    chars.next().is_some_and(is_identifier_start) && chars.all(is_identifier_char)
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+/// Writes an object key, quoted if it isn't an identifier.
+pub fn write_json5_key(buffer: &mut String, key: &str) {
+    if is_identifier(key) {
+        buffer.push_str(key);
+    } else {
+        write_json5_string(buffer, key);
+    }
+}
+
+/// Writes a string in double quotes, escaping it so it reads back the same.
+pub fn write_json5_string(buffer: &mut String, string: &str) {
+    buffer.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => buffer.push_str("\\\""),
+            '\\' => buffer.push_str("\\\\"),
+            '\n' => buffer.push_str("\\n"),
+            '\r' => buffer.push_str("\\r"),
+            '\t' => buffer.push_str("\\t"),
+            '\u{8}' => buffer.push_str("\\b"),
+            '\u{c}' => buffer.push_str("\\f"),
+            // These end lines in json5, so they can't be in a string as they are.
+            '\u{2028}' | '\u{2029}' => buffer.push_str(&format!("\\u{:04x}", c as u32)),
+            c if c.is_control() => buffer.push_str(&format!("\\u{:04x}", c as u32)),
+            c => buffer.push(c),
+        }
+    }
+    buffer.push('"');
+}
+
+/// A token in a json5 file.
+#[derive(Debug, PartialEq)]
+enum Token {
+    LeftBrace,
+    RightBrace,
+    LeftBracket,
+    RightBracket,
+    Colon,
+    Comma,
+    String(String),
+    Identifier(String),
+    Number(String),
+}
+
+impl Token {
+    /// How the token is described in errors.
+    fn describe(&self) -> String {
+        match self {
+            Self::LeftBrace => "`{`".to_string(),
+            Self::RightBrace => "`}`".to_string(),
+            Self::LeftBracket => "`[`".to_string(),
+            Self::RightBracket => "`]`".to_string(),
+            Self::Colon => "`:`".to_string(),
+            Self::Comma => "`,`".to_string(),
+            Self::String(_) => "a string".to_string(),
+            Self::Identifier(identifier) => format!("`{identifier}`"),
+            Self::Number(number) => format!("`{number}`"),
+        }
+    }
+}
+
+/// Splits a json5 file into tokens, skipping whitespace and comments.
+struct Tokenizer<'a> {
+    chars: Peekable<Chars<'a>>,
+    position: Position,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            chars: src.chars().peekable(),
+            position: Position { line: 1, column: 1 },
+        }
+    }
+
+    /// Takes the next character, keeping track of the position.
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 1;
+        } else {
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_trivia(&mut self) -> Result<(), Json5Error> {
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || c == '\u{feff}' {
+                self.next_char();
+            } else if c == '/' {
+                let start = self.position;
+                self.next_char();
+                match self.next_char() {
+                    Some('/') => {
+                        while self.chars.peek().is_some_and(|&c| c != '\n') {
+                            self.next_char();
+                        }
+                    }
+                    Some('*') => loop {
+                        match self.next_char() {
+                            Some('*') if self.chars.peek() == Some(&'/') => {
+                                self.next_char();
+                                break;
+                            }
+                            Some(_) => {}
+                            None => return Err(Json5Error::new(start, "Unclosed comment")),
+                        }
+                    },
+                    _ => return Err(Json5Error::new(start, "Unexpected `/`")),
+                }
+            } else {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the rest of a string, after the opening quote.
+    fn string(&mut self, quote: char, start: Position) -> Result<String, Json5Error> {
+        let mut string = String::new();
+
+        loop {
+            let position = self.position;
+            match self.next_char() {
+                None | Some('\n' | '\r') => {
+                    return Err(Json5Error::new(start, "Unclosed string"));
+                }
+                Some(c) if c == quote => return Ok(string),
+                Some('\\') => match self.next_char() {
+                    Some('n') => string.push('\n'),
+                    Some('r') => string.push('\r'),
+                    Some('t') => string.push('\t'),
+                    Some('b') => string.push('\u{8}'),
+                    Some('f') => string.push('\u{c}'),
+                    Some('v') => string.push('\u{b}'),
+                    Some('0') => string.push('\0'),
+                    Some('u') => string.push(self.unicode_escape(position)?),
+                    Some('x') => {
+                        let code = self.hex_digits(2, position)?;
+                        string.push(char::from_u32(code).unwrap_or_else(|| unreachable!()));
+                    }
+                    // A backslash before a newline continues the string on the next line.
+                    Some('\n') => {}
+                    Some('\r') => {
+                        if self.chars.peek() == Some(&'\n') {
+                            self.next_char();
+                        }
+                    }
+                    Some(c) if c.is_ascii_digit() => {
+                        return Err(Json5Error::new(position, "Invalid escape"));
+                    }
+                    Some(c) => string.push(c),
+                    None => return Err(Json5Error::new(start, "Unclosed string")),
+                },
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    /// Reads the given number of hex digits.
+    fn hex_digits(&mut self, count: usize, position: Position) -> Result<u32, Json5Error> {
+        let mut code = 0;
+        for _ in 0..count {
+            let digit = self
+                .next_char()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| Json5Error::new(position, "Invalid escape"))?;
+            code = code * 16 + digit;
+        }
+        Ok(code)
+    }
+
+    /// Reads a `\uXXXX` escape, after the `u`,
+    /// including the second half of a surrogate pair.
+    fn unicode_escape(&mut self, position: Position) -> Result<char, Json5Error> {
+        let high = self.hex_digits(4, position)?;
+
+        let code = if (0xd800..0xdc00).contains(&high) {
+            if self.next_char() != Some('\\') || self.next_char() != Some('u') {
+                return Err(Json5Error::new(position, "Unpaired surrogate"));
+            }
+            let low = self.hex_digits(4, position)?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(Json5Error::new(position, "Unpaired surrogate"));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+
+        char::from_u32(code).ok_or_else(|| Json5Error::new(position, "Unpaired surrogate"))
+    }
+
+    /// Reads a number, or `Infinity` or `NaN` with a sign.
+    fn number(&mut self, start: Position) -> Result<String, Json5Error> {
+        let mut number = String::new();
+        if let Some(&sign @ ('+' | '-')) = self.chars.peek() {
+            number.push(sign);
+            self.next_char();
+        }
+
+        while let Some(&c) = self.chars.peek() {
+            let is_exponent_sign = (c == '+' || c == '-')
+                && number.ends_with(['e', 'E'])
+                && !number.to_ascii_lowercase().contains("0x");
+            if !(is_identifier_char(c) || c == '.' || is_exponent_sign) {
+                break;
+            }
+            number.push(c);
+            self.next_char();
+        }
+
+        let digits = number.trim_start_matches(['+', '-']);
+        let valid = if let Some(hex) = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            u128::from_str_radix(hex, 16).is_ok()
+        } else {
+            digits == "Infinity"
+                || digits == "NaN"
+                || (digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+                    && digits.parse::<f64>().is_ok())
+        };
+
+        if valid {
+            Ok(number)
+        } else {
+            Err(Json5Error::new(start, format!("Invalid number `{number}`")))
+        }
+    }
+
+    /// Reads the next token, or `None` at the end of the file.
+    fn next_token(&mut self) -> Result<Option<(Token, Position)>, Json5Error> {
+        self.skip_trivia()?;
+
+        let start = self.position;
+        let Some(&c) = self.chars.peek() else {
+            return Ok(None);
+        };
+
+        let token = match c {
+            '{' | '}' | '[' | ']' | ':' | ',' => {
+                self.next_char();
+                match c {
+                    '{' => Token::LeftBrace,
+                    '}' => Token::RightBrace,
+                    '[' => Token::LeftBracket,
+                    ']' => Token::RightBracket,
+                    ':' => Token::Colon,
+                    _ => Token::Comma,
+                }
+            }
+            '"' | '\'' => {
+                self.next_char();
+                Token::String(self.string(c, start)?)
+            }
+            c if c.is_ascii_digit() || c == '.' || c == '+' || c == '-' => {
+                Token::Number(self.number(start)?)
+            }
+            c if is_identifier_start(c) => {
+                let mut identifier = String::new();
+                while let Some(&c) = self.chars.peek().filter(|&&c| is_identifier_char(c)) {
+                    identifier.push(c);
+                    self.next_char();
+                }
+                Token::Identifier(identifier)
+            }
+            c => {
+                return Err(Json5Error::new(
+                    start,
+                    format!("Unexpected character `{c}`"),
+                ))
+            }
+        };
+
+        Ok(Some((token, start)))
+    }
+}
+
+/// Builds values from the tokens.
+struct Parser<'a> {
+    tokenizer: Tokenizer<'a>,
+    peeked: Option<(Token, Position)>,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Result<Option<&(Token, Position)>, Json5Error> {
+        if self.peeked.is_none() {
+            self.peeked = self.tokenizer.next_token()?;
+        }
+        Ok(self.peeked.as_ref())
+    }
+
+    fn next(&mut self) -> Result<(Token, Position), Json5Error> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self
+                .tokenizer
+                .next_token()?
+                .ok_or_else(|| Json5Error::new(self.tokenizer.position, "Unexpected end of file")),
+        }
+    }
+
+    /// Takes the next token if it's the given one.
+    fn eat(&mut self, token: &Token) -> Result<bool, Json5Error> {
+        let matches = self.peek()?.is_some_and(|(next, _)| next == token);
+        if matches {
+            self.next()?;
+        }
+        Ok(matches)
+    }
+
+    fn value(&mut self) -> Result<Json5Value, Json5Error> {
+        let (token, position) = self.next()?;
+
+        let kind = match token {
+            Token::LeftBrace => self.object()?,
+            Token::LeftBracket => self.array()?,
+            Token::String(string) => Json5Kind::String(string),
+            Token::Number(number) => Json5Kind::Number(number),
+            Token::Identifier(identifier) => match identifier.as_str() {
+                "null" => Json5Kind::Null,
+                "true" => Json5Kind::Bool(true),
+                "false" => Json5Kind::Bool(false),
+                "Infinity" | "NaN" => Json5Kind::Number(identifier),
+                _ => Json5Kind::Identifier(identifier),
+            },
+            token => {
+                return Err(Json5Error::new(
+                    position,
+                    format!("Expected a value, found {}", token.describe()),
+                ))
+            }
+        };
+
+        Ok(Json5Value { kind, position })
+    }
+
+    /// Parses an object, after the `{`.
+    fn object(&mut self) -> Result<Json5Kind, Json5Error> {
+        let mut members: Vec<(String, Json5Value)> = Vec::new();
+
+        while !self.eat(&Token::RightBrace)? {
+            let (token, position) = self.next()?;
+            let key = match token {
+                Token::String(key) | Token::Identifier(key) => key,
+                token => {
+                    return Err(Json5Error::new(
+                        position,
+                        format!("Expected a key, found {}", token.describe()),
+                    ))
+                }
+            };
+            if members.iter().any(|(member_key, _)| *member_key == key) {
+                return Err(Json5Error::new(position, format!("Duplicate key `{key}`")));
+            }
+
+            let (token, position) = self.next()?;
+            if token != Token::Colon {
+                return Err(Json5Error::new(
+                    position,
+                    format!("Expected `:`, found {}", token.describe()),
+                ));
+            }
+
+            // Older scenes wrote empty fields as nothing, like `name: ,`,
+            // so those are read as empty strings.
+            let value = match self.peek()? {
+                Some((Token::Comma | Token::RightBrace, position)) => Json5Value {
+                    kind: Json5Kind::String(String::new()),
+                    position: *position,
+                },
+                _ => self.value()?,
+            };
+            members.push((key, value));
+
+            if !self.eat(&Token::Comma)? {
+                let (token, position) = self.next()?;
+                if token != Token::RightBrace {
+                    return Err(Json5Error::new(
+                        position,
+                        format!("Expected `,` or `}}`, found {}", token.describe()),
+                    ));
+                }
+                break;
+            }
+        }
+
+        Ok(Json5Kind::Object(members))
+    }
+
+    /// Parses an array, after the `[`.
+    fn array(&mut self) -> Result<Json5Kind, Json5Error> {
+        let mut values = Vec::new();
+
+        while !self.eat(&Token::RightBracket)? {
+            values.push(self.value()?);
+
+            if !self.eat(&Token::Comma)? {
+                let (token, position) = self.next()?;
+                if token != Token::RightBracket {
+                    return Err(Json5Error::new(
+                        position,
+                        format!("Expected `,` or `]`, found {}", token.describe()),
+                    ));
+                }
+                break;
+            }
+        }
+
+        Ok(Json5Kind::Array(values))
+    }
+}
+
+/// Parses a json5 file.
+///
+/// # Errors
+///
+/// If the file isn't valid json5, with where the problem is.
+pub fn parse_json5(src: &str) -> Result<Json5Value, Json5Error> {
+    let mut parser = Parser {
+        tokenizer: Tokenizer::new(src),
+        peeked: None,
+    };
+
+    let value = parser.value()?;
+
+    if let Some((token, position)) = parser.peek()? {
+        return Err(Json5Error::new(
+            *position,
+            format!("Expected the end of the file, found {}", token.describe()),
+        ));
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json5() {
+        let value = parse_json5(
+            "// A comment.
+            {
+                /* Another one. */
+                unquoted: 'single',
+                \"quoted\": [1, -2.5, 0x1F, .5, +Infinity, NaN, 1e-3,],
+                nested: { a: null, b: true, c: ArrowUp },
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            value.get("unquoted").unwrap().kind,
+            Json5Kind::String("single".to_string())
+        );
+
+        let Json5Kind::Array(numbers) = &value.get("quoted").unwrap().kind else {
+            panic!("Expected an array");
+        };
+        let numbers = numbers
+            .iter()
+            .map(|value| match &value.kind {
+                Json5Kind::Number(number) => number.as_str(),
+                _ => panic!("Expected a number"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            numbers,
+            ["1", "-2.5", "0x1F", ".5", "+Infinity", "NaN", "1e-3"]
+        );
+
+        let nested = value.get("nested").unwrap();
+        assert_eq!(nested.get("a").unwrap().kind, Json5Kind::Null);
+        assert_eq!(nested.get("b").unwrap().kind, Json5Kind::Bool(true));
+        assert_eq!(
+            nested.get("c").unwrap().kind,
+            Json5Kind::Identifier("ArrowUp".to_string())
+        );
+        assert_eq!(
+            nested.position,
+            Position {
+                line: 6,
+                column: 25
+            }
+        );
+    }
+
+    #[test]
+    fn reports_positions() {
+        let error = parse_json5("{\n    a: 1,\n    b 2,\n}").unwrap_err();
+        assert_eq!(error.position, Position { line: 3, column: 7 });
+        assert_eq!(
+            error.to_string(),
+            "Expected `:`, found `2` at line 3, column 7"
+        );
+
+        let error = parse_json5("{\n    a: \"unclosed,\n}").unwrap_err();
+        assert_eq!(error.position, Position { line: 2, column: 8 });
+
+        let error = parse_json5("{ a: 1 } }").unwrap_err();
+        assert_eq!(
+            error.position,
+            Position {
+                line: 1,
+                column: 10
+            }
+        );
+
+        let error = parse_json5("{ a: 1, a: 2 }").unwrap_err();
+        assert_eq!(error.message, "Duplicate key `a`");
+    }
+
+    #[test]
+    fn strings_round_trip() {
+        let string = "quote \" backslash \\ newline \n tab \t unicode é 😀 control \u{1}";
+
+        let mut buffer = String::new();
+        write_json5_string(&mut buffer, string);
+        assert_eq!(
+            parse_json5(&buffer).unwrap().kind,
+            Json5Kind::String(string.to_string())
+        );
+
+        assert_eq!(
+            parse_json5(
+                r#""😀 \x41 \' line \
+continued""#
+            )
+            .unwrap()
+            .kind,
+            Json5Kind::String("😀 A ' line continued".to_string())
+        );
+    }
+
+    #[test]
+    fn writes_json5() {
+        let value = Json5Value::new(Json5Kind::Object(vec![
+            (
+                "123".to_string(),
+                Json5Value::new(Json5Kind::Object(vec![(
+                    "position".to_string(),
+                    Json5Value::new(Json5Kind::Array(vec![
+                        Json5Value::new(Json5Kind::Number("1.0".to_string())),
+                        Json5Value::new(Json5Kind::Number("0.0".to_string())),
+                    ])),
+                )])),
+            ),
+            (
+                "empty".to_string(),
+                Json5Value::new(Json5Kind::Object(vec![])),
+            ),
+        ]));
+
+        let json5 = value.to_json5();
+        assert_eq!(
+            json5,
+            "{\n    \"123\": {\n        position: [1.0, 0.0],\n    },\n    empty: {},\n}"
+        );
+
+        let mut parsed = parse_json5(&json5).unwrap();
+        // Positions aren't compared.
+        fn clear_positions(value: &mut Json5Value) {
+            value.position = Position::default();
+            match &mut value.kind {
+                Json5Kind::Array(values) => values.iter_mut().for_each(clear_positions),
+                Json5Kind::Object(members) => members
+                    .iter_mut()
+                    .for_each(|(_, value)| clear_positions(value)),
+                _ => {}
+            }
+        }
+        clear_positions(&mut parsed);
+        assert_eq!(parsed, value);
+    }
+
+    #[test]
+    fn parses_example_scene() {
+        let scene = parse_json5(include_str!(
+            "../../../iridium_example_project/scenes/scene.json5"
+        ))
+        .unwrap();
+
+        assert!(scene.get("entities").unwrap().as_object().is_some());
+
+        // Older writers left empty fields out, like the camera's name.
+        let camera = scene
+            .get("entities")
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .iter()
+            .find_map(|(_, entity)| entity.get("Camera"))
+            .unwrap();
+        assert_eq!(
+            camera.get("name").unwrap().kind,
+            Json5Kind::String(String::new())
+        );
+    }
+}
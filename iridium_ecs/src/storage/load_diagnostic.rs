@@ -0,0 +1,103 @@
+use std::fmt;
+
+/// What was wrong with part of a saved world.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagnosticKind {
+    /// The component's type isn't registered.
+    UnknownComponent,
+    /// A field of the component is missing.
+    MissingField,
+    /// A field of the component isn't valid for its type.
+    ///
+    /// Components with a manual `ComponentStorage` impl don't say which field.
+    InvalidField,
+    /// The entity is missing the `Name` component.
+    MissingName,
+    /// The id of an entity was not a valid u128.
+    InvalidId,
+    /// The component or resource was saved with a newer schema version than it has.
+    UnsupportedSchemaVersion(u32),
+    /// The entity's prefab couldn't be read, so only the fields it overrides are loaded.
+    ///
+    /// Returns why.
+    InvalidPrefab(String),
+}
+
+/// A problem found when loading a saved world.
+///
+/// Lenient loads skip what it's about and carry on,
+/// others stop with it as a `ReadError`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadDiagnostic {
+    /// What was wrong.
+    pub kind: DiagnosticKind,
+    /// The name of the entity, or its id if it has no name.
+    ///
+    /// `None` for resources.
+    pub entity: Option<String>,
+    /// The type name of the component or resource.
+    pub component: Option<String>,
+    /// The field that was missing or invalid.
+    pub field: Option<String>,
+    /// The type the field should have.
+    pub expected: Option<&'static str>,
+    /// The line in the file, or 0 if it isn't known.
+    pub line: usize,
+}
+
+impl LoadDiagnostic {
+    /// Creates a diagnostic that isn't about a field.
+    #[must_use]
+    pub fn new(
+        kind: DiagnosticKind,
+        entity: Option<&str>,
+        component: Option<&str>,
+        line: usize,
+    ) -> Self {
+        Self {
+            kind,
+            entity: entity.map(ToString::to_string),
+            component: component.map(ToString::to_string),
+            field: None,
+            expected: None,
+            line,
+        }
+    }
+}
+
+impl fmt::Display for LoadDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "Line {}: ", self.line)?;
+        }
+
+        match (&self.entity, &self.component) {
+            (Some(entity), Some(component)) => write!(f, "{component} on \"{entity}\"")?,
+            (Some(entity), None) => write!(f, "Entity \"{entity}\"")?,
+            (None, Some(component)) => write!(f, "Resource {component}")?,
+            (None, None) => write!(f, "Scene")?,
+        }
+        if let Some(field) = &self.field {
+            write!(f, ", field `{field}`")?;
+        }
+
+        match &self.kind {
+            DiagnosticKind::UnknownComponent => write!(f, " isn't a registered component"),
+            DiagnosticKind::MissingField => write!(f, " is missing"),
+            DiagnosticKind::InvalidField => write!(f, " is invalid"),
+            DiagnosticKind::MissingName => write!(f, " has no Name component"),
+            DiagnosticKind::InvalidId => write!(f, " doesn't have a valid id"),
+            DiagnosticKind::UnsupportedSchemaVersion(version) => {
+                write!(f, " was saved with newer schema version {version}")
+            }
+            DiagnosticKind::InvalidPrefab(error) => {
+                write!(f, " names a prefab that couldn't be read: {error}")
+            }
+        }?;
+        if let Some(expected) = self.expected {
+            write!(f, ", expected {expected}")?;
+        }
+
+        Ok(())
+    }
+}
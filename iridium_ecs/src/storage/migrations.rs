@@ -0,0 +1,55 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::StoredComponent;
+
+/// Upgrades a stored component from one schema version to the next.
+pub type Migration = fn(StoredComponent) -> StoredComponent;
+
+/// The migrations used to upgrade components saved with an older schema version.
+///
+/// `World::load` applies them before `ComponentStorage::from_stored`,
+/// so a component's fields can be renamed or retyped without breaking saved scenes.
+#[derive(Default)]
+pub struct Migrations {
+    /// The migrations for each type name, keyed by the version they upgrade from.
+    migrations: HashMap<String, BTreeMap<u32, Migration>>,
+}
+
+impl Migrations {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a migration upgrading the component or resource
+    /// from `from_version` to `from_version + 1`.
+    ///
+    /// Replaces any migration already registered for that version.
+    pub fn register(&mut self, type_name: &str, from_version: u32, migration: Migration) {
+        self.migrations
+            .entry(type_name.to_string())
+            .or_default()
+            .insert(from_version, migration);
+    }
+
+    /// Upgrades a stored component from `from_version` to `to_version`.
+    ///
+    /// Each registered migration in between is applied in order,
+    /// versions without one are left as they are.
+    #[must_use]
+    pub fn migrate(
+        &self,
+        mut stored: StoredComponent,
+        from_version: u32,
+        to_version: u32,
+    ) -> StoredComponent {
+        if let Some(migrations) = self.migrations.get(&stored.type_name) {
+            for migration in migrations.range(from_version..to_version).map(|(_, m)| m) {
+                stored = migration(stored);
+            }
+        }
+
+        stored
+    }
+}
@@ -1,8 +1,29 @@
+mod binary;
+pub use binary::*;
+
 mod component_storage;
 pub use component_storage::*;
 
+mod json5;
+pub use json5::*;
+
+mod load_diagnostic;
+pub use load_diagnostic::*;
+
+mod migrations;
+pub use migrations::*;
+
+mod prefab;
+pub use prefab::*;
+
 mod reader;
 pub use reader::*;
 
+mod scene_format;
+pub use scene_format::*;
+
+mod stored_value;
+pub use stored_value::*;
+
 mod writer;
 pub use writer::*;
@@ -0,0 +1,403 @@
+use iridium_assets::Assets;
+
+use super::{
+    read_component, read_stored_world, save_stored_world_to_file, stored_components,
+    stored_schema_versions, upgrade_component, DiagnosticKind, Diagnostics, LoadDiagnostic,
+    Migrations, ReadError, SourcedComponent, StoredComponent, StoredWorld,
+};
+use crate::{
+    Component, ComponentBox, Entities, Entity, Name, Parent, PrefabInstance, SceneOrigin, World,
+};
+
+/// A reusable entity, which can be placed in scenes many times.
+///
+/// Prefabs are saved as scenes with one entity, in either format.
+/// Scenes reference them by their file's path,
+/// and save only the fields their instances override.
+///
+/// Instances are marked with `PrefabInstance`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prefab {
+    /// The prefab's id, which is the path of its file.
+    pub id: String,
+    /// The prefab's components, upgraded to the schema versions they have now.
+    pub components: Vec<StoredComponent>,
+}
+
+impl Prefab {
+    /// Reads a prefab from its file.
+    ///
+    /// Components saved with an older schema version are upgraded with `migrations`.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the file cannot be read, or if the file is not a valid scene,
+    /// or if it doesn't have exactly one entity.
+    pub fn load(
+        file: &str,
+        entities: &Entities,
+        migrations: &Migrations,
+    ) -> Result<Self, ReadError> {
+        let StoredWorld {
+            entities: stored_entities,
+            schema_versions,
+            ..
+        } = read_stored_world(file, entities, &mut Diagnostics::new(false))?;
+
+        let Ok([(_, _, components)]) = <[_; 1]>::try_from(stored_entities) else {
+            return Err(ReadError::InvalidPrefab(file.to_string()));
+        };
+
+        let components = components
+            .into_iter()
+            // Prefabs can't be nested, and aren't part of a scene or its hierarchy.
+            .filter(|sourced| !is_unsaved_in_prefabs(&sourced.stored.type_name))
+            .map(|SourcedComponent { stored, line, .. }| {
+                let type_name = stored.type_name.clone();
+                upgrade_component(entities, migrations, &schema_versions, stored).map_err(
+                    |version| {
+                        ReadError::Invalid(LoadDiagnostic::new(
+                            DiagnosticKind::UnsupportedSchemaVersion(version),
+                            None,
+                            Some(&type_name),
+                            line,
+                        ))
+                    },
+                )
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            id: file.to_string(),
+            components,
+        })
+    }
+
+    /// The prefab's component of the given type.
+    #[must_use]
+    pub fn component(&self, type_name: &str) -> Option<&StoredComponent> {
+        self.components
+            .iter()
+            .find(|component| component.type_name == type_name)
+    }
+
+    /// An instance's components, with the fields it doesn't override filled in from the prefab.
+    ///
+    /// Components it doesn't have are added.
+    #[must_use]
+    pub fn with_overrides(&self, overrides: Vec<SourcedComponent>) -> Vec<SourcedComponent> {
+        let mut components = overrides;
+
+        for prefab_component in &self.components {
+            match components
+                .iter_mut()
+                .find(|sourced| sourced.stored.type_name == prefab_component.type_name)
+            {
+                Some(sourced) => {
+                    for (key, value) in &prefab_component.fields {
+                        sourced
+                            .stored
+                            .fields
+                            .entry(key.clone())
+                            .or_insert_with(|| value.clone());
+                    }
+                }
+                None => components.push(SourcedComponent::new(prefab_component.clone())),
+            }
+        }
+
+        components
+    }
+
+    /// The fields an instance overrides, for saving it.
+    ///
+    /// Fields that are the same as the prefab's are removed,
+    /// along with components that then have none left.
+    /// `Name` and `PrefabInstance` are always kept.
+    ///
+    /// Components removed from an instance come back when it's reloaded,
+    /// as there's no way to store that.
+    #[must_use]
+    pub fn overrides(&self, components: Vec<StoredComponent>) -> Vec<StoredComponent> {
+        components
+            .into_iter()
+            .filter_map(|mut stored| {
+                if stored.type_name == Name::type_name()
+                    || stored.type_name == PrefabInstance::type_name()
+                {
+                    return Some(stored);
+                }
+
+                let Some(prefab_component) = self.component(&stored.type_name) else {
+                    return Some(stored);
+                };
+
+                stored
+                    .fields
+                    .retain(|key, value| prefab_component.fields.get(key) != Some(value));
+                (!stored.fields.is_empty()).then_some(stored)
+            })
+            .collect()
+    }
+
+    /// Creates the components of a new instance.
+    ///
+    /// The prefab's `Name` isn't included, as entities are given their name when created,
+    /// and neither is a `Parent`, but a `PrefabInstance` is.
+    ///
+    /// # Errors
+    ///
+    /// Returns the problem if one of the components isn't registered, or has an invalid field.
+    pub fn instantiate(
+        &self,
+        entities: &Entities,
+        assets: &Assets,
+    ) -> Result<Vec<ComponentBox>, LoadDiagnostic> {
+        let mut components = self
+            .components
+            .iter()
+            .filter(|stored| {
+                stored.type_name != Name::type_name() && stored.type_name != Parent::type_name()
+            })
+            .map(|stored| {
+                read_component(
+                    entities,
+                    assets,
+                    SourcedComponent::new(stored.clone()),
+                    None,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        components.push(
+            PrefabInstance {
+                prefab: self.id.clone(),
+            }
+            .into(),
+        );
+
+        Ok(components)
+    }
+}
+
+/// Saves an entity as a prefab, in the format chosen by the file's extension.
+///
+/// Its `PrefabInstance` isn't saved, as prefabs can't be nested,
+/// and neither are its `SceneOrigin` and `Parent`.
+///
+/// # Panics
+///
+/// Panics if the entity doesn't exist.
+pub fn save_prefab_to_file(world: &World, entity: Entity, file: &str) {
+    let id = world
+        .entities
+        .persistent_id(entity)
+        .expect("Entity did not exist");
+    let components = stored_components(&world.entities, entity)
+        .into_iter()
+        .filter(|stored| !is_unsaved_in_prefabs(&stored.type_name))
+        .collect::<Vec<_>>();
+    let schema_versions = stored_schema_versions(&world.entities, &components);
+
+    save_stored_world_to_file(
+        StoredWorld {
+            entities: vec![(
+                id,
+                0,
+                components.into_iter().map(SourcedComponent::new).collect(),
+            )],
+            resources: Vec::new(),
+            schema_versions,
+        },
+        file,
+    );
+}
+
+/// Whether a component is left out of prefabs.
+fn is_unsaved_in_prefabs(type_name: &str) -> bool {
+    type_name == PrefabInstance::type_name()
+        || type_name == SceneOrigin::type_name()
+        || type_name == Parent::type_name()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{storage::DiagnosticKind, systems::Systems, Transform};
+    use iridium_maths::VecN;
+    use iridium_reflect::HasStableTypeId;
+
+    fn temp_file(name: &str) -> String {
+        std::env::temp_dir()
+            .join(name)
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// Saves a prefab with a `Transform` of the given scale.
+    fn save_ball_prefab(file: &str, scale: f32) {
+        let mut world = World::new(Entities::default(), Systems::new());
+        let transform = Transform {
+            scale: VecN::new([scale, scale, 1.]),
+            ..Transform::default()
+        };
+        let ball = world
+            .entities
+            .new_entity(None, "Ball", vec![transform.into()]);
+        save_prefab_to_file(&world, ball, file);
+    }
+
+    fn position_and_scale(world: &World, id: u128) -> (VecN<3>, VecN<3>) {
+        let entity = world.entities.entity_from_persistent_id(id).unwrap();
+        let transform = world
+            .entities
+            .entity_component(entity, Transform::stable_type_id())
+            .unwrap();
+        let transform = transform.get::<Transform>();
+        (transform.position, transform.scale)
+    }
+
+    #[test]
+    fn instances_store_overrides() {
+        let prefab = temp_file("iridium_instances_store_overrides_ball.json5");
+        let scene = temp_file("iridium_instances_store_overrides.json5");
+        save_ball_prefab(&prefab, 2.);
+
+        let mut world = World::new(Entities::default(), Systems::new());
+        let transform = Transform {
+            position: VecN::new([1., 2., 0.]),
+            scale: VecN::new([2., 2., 1.]),
+            ..Transform::default()
+        };
+        world.entities.new_entity(
+            Some(5),
+            "Ball 2",
+            vec![
+                PrefabInstance {
+                    prefab: prefab.clone(),
+                }
+                .into(),
+                transform.into(),
+            ],
+        );
+        world.save(&scene);
+
+        // Only the position differs from the prefab.
+        let saved = std::fs::read_to_string(&scene).unwrap();
+        assert!(saved.contains("position: [1.0, 2.0, 0.0],"));
+        assert!(!saved.contains("scale"));
+        assert!(!saved.contains("rotation"));
+
+        world.load(&scene, &Assets::new()).unwrap();
+        assert_eq!(
+            position_and_scale(&world, 5),
+            (VecN::new([1., 2., 0.]), VecN::new([2., 2., 1.]))
+        );
+
+        // Edits to the prefab show when the scene is reloaded.
+        save_ball_prefab(&prefab, 3.);
+        world.load(&scene, &Assets::new()).unwrap();
+        assert_eq!(
+            position_and_scale(&world, 5),
+            (VecN::new([1., 2., 0.]), VecN::new([3., 3., 1.]))
+        );
+
+        std::fs::remove_file(prefab).unwrap();
+        std::fs::remove_file(scene).unwrap();
+    }
+
+    #[test]
+    fn instantiates_through_commands() {
+        let file = temp_file("iridium_instantiates_through_commands.iscene");
+        save_ball_prefab(&file, 2.);
+
+        let mut world = World::new(Entities::default(), Systems::new());
+        let prefab = Prefab::load(&file, &world.entities, &world.migrations).unwrap();
+        std::fs::remove_file(&file).unwrap();
+
+        let commands = world.entities.commands("Spawner");
+        commands
+            .instantiate(&prefab, "Ball 2", Vec::new(), &Assets::new())
+            .unwrap();
+        let moved = Transform {
+            position: VecN::new([4., 0., 0.]),
+            ..Transform::default()
+        };
+        commands
+            .instantiate(&prefab, "Ball 3", vec![moved.into()], &Assets::new())
+            .unwrap();
+        world.entities.process_commands();
+
+        let ball = world.entities.entity_from_name("Ball 2").unwrap();
+        let instance = world
+            .entities
+            .entity_component(ball, PrefabInstance::stable_type_id())
+            .unwrap();
+        assert_eq!(instance.get::<PrefabInstance>().prefab, file);
+        let transform = world
+            .entities
+            .entity_component(ball, Transform::stable_type_id())
+            .unwrap();
+        assert_eq!(transform.get::<Transform>().scale, VecN::new([2., 2., 1.]));
+
+        let moved = world.entities.entity_from_name("Ball 3").unwrap();
+        let transform = world
+            .entities
+            .entity_component(moved, Transform::stable_type_id())
+            .unwrap();
+        assert_eq!(
+            transform.get::<Transform>().position,
+            VecN::new([4., 0., 0.])
+        );
+    }
+
+    #[test]
+    fn prefabs_leave_out_the_parent() {
+        let file = temp_file("iridium_prefabs_leave_out_the_parent.json5");
+        let mut world = World::new(Entities::default(), Systems::new());
+        let parent = world.entities.new_entity(Some(1), "Parent", Vec::new());
+        let ball = world
+            .entities
+            .new_entity(Some(2), "Ball", vec![Transform::default().into()]);
+        world.entities.set_parent(ball, Some(parent));
+        save_prefab_to_file(&world, ball, &file);
+
+        let prefab = Prefab::load(&file, &world.entities, &world.migrations).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert!(prefab.component(Parent::type_name()).is_none());
+
+        // Instances aren't children of the entity the prefab was made from.
+        let commands = world.entities.commands("Spawner");
+        commands
+            .instantiate(&prefab, "Ball 2", Vec::new(), &Assets::new())
+            .unwrap();
+        world.entities.process_commands();
+        let instance = world.entities.entity_from_name("Ball 2").unwrap();
+        assert_eq!(world.entities.parent(instance), None);
+        assert_eq!(world.entities.children(parent), vec![ball]);
+    }
+
+    #[test]
+    fn missing_prefabs_are_reported() {
+        let scene = temp_file("iridium_missing_prefabs_are_reported.json5");
+        std::fs::write(
+            &scene,
+            "{\n    entities: {\n        \"1\": {\n            Name: { name: \"Ball\" },\n            \
+             PrefabInstance: { prefab: \"missing.json5\" },\n        },\n    },\n}",
+        )
+        .unwrap();
+
+        let mut world = World::new(Entities::default(), Systems::new());
+        let diagnostics = world.load_lenient(&scene, &Assets::new()).unwrap();
+        std::fs::remove_file(scene).unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].kind,
+            DiagnosticKind::InvalidPrefab("Couldn't read missing.json5".to_string())
+        );
+        assert_eq!(diagnostics[0].line, 5);
+        assert!(world.entities.entity_from_persistent_id(1).is_some());
+    }
+}
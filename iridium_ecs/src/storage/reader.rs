@@ -1,17 +1,48 @@
 use iridium_assets::Assets;
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
-use crate::{storage::StoredComponentField, ComponentBox, Entities, Name, World};
+use crate::{Component, ComponentBox, Entities, Name, PrefabInstance, SceneOrigin, World};
 
-use super::StoredComponent;
+use super::{
+    parse_json5, read_binary_world, BinaryError, DiagnosticKind, FieldStorage, Json5Error,
+    Json5Value, LoadDiagnostic, Migrations, Prefab, SceneFormat, StoredComponent, StoredValue,
+    FORMAT_VERSION,
+};
+
+/// A stored component, with the lines it and its fields were read from.
+///
+/// The lines are used in `LoadDiagnostic`s.
+pub struct SourcedComponent {
+    /// The component as it is stored.
+    pub stored: StoredComponent,
+    /// The line the component's object starts on.
+    pub line: usize,
+    /// The line each field is on.
+    pub field_lines: HashMap<String, usize>,
+}
+
+impl SourcedComponent {
+    /// A stored component that wasn't read from a json5 file,
+    /// so it has no lines.
+    #[must_use]
+    pub fn new(stored: StoredComponent) -> Self {
+        Self {
+            stored,
+            line: 0,
+            field_lines: HashMap::new(),
+        }
+    }
+}
 
 /// Used in an intermediary step when reading a world.
 ///
 /// This created after the file has been read, and all the fields extracted,
 /// but before the component's fields are turned to components.
 ///
+/// Each entity has its id, the line it starts on, and its components.
+///
 /// This is not guaranteed to make valid components.
-pub type StoredEntities = Vec<(u128, Vec<StoredComponent>)>;
+pub type StoredEntities = Vec<(u128, usize, Vec<SourcedComponent>)>;
 
 /// Used in an intermediary step when reading a world.
 ///
@@ -21,13 +52,28 @@ pub type StoredEntities = Vec<(u128, Vec<StoredComponent>)>;
 /// This is guaranteed to be valid.
 pub type ParsedEntities = Vec<(u128, Name, Vec<ComponentBox>)>;
 
-/// The location an error occurred at.
-#[derive(Debug)]
-pub enum ErrorLocation {
-    /// The error is at this line.
-    Line(usize),
-    /// The error is at the component with this id.
-    Component(u128),
+/// Used in an intermediary step when reading a world.
+///
+/// The resources after the file has been read,
+/// but before they're turned to components.
+pub type StoredResources = Vec<SourcedComponent>;
+
+/// The schema versions components and resources were saved with, by type name.
+///
+/// Types that aren't in it were saved with version 0.
+pub type SchemaVersions = HashMap<String, u32>;
+
+/// A saved world, before the components' fields are turned to components.
+///
+/// Both scene formats are read to and written from this,
+/// which is what lets scenes be converted without loading them.
+pub struct StoredWorld {
+    /// The entities, with their components.
+    pub entities: StoredEntities,
+    /// The resources.
+    pub resources: StoredResources,
+    /// The schema versions the components and resources were saved with.
+    pub schema_versions: SchemaVersions,
 }
 
 /// An error that occurred when reading a saved world.
@@ -35,175 +81,570 @@ pub enum ErrorLocation {
 pub enum ReadError {
     /// The file could not be opened.
     FileNotFound(String),
-    /// The syntax was invalid at the given line.
-    SyntaxError(ErrorLocation),
-    /// The component name was not recognised.
-    UnknownComponent(ErrorLocation, String),
-    /// The component had an invalid field.
-    InvalidField(ErrorLocation),
-    /// The entity is missing the `Name` component.
-    MissingName(u128),
-    /// The id of an entity was not a valid u128.
+    /// The file isn't valid json5, or isn't laid out like a scene.
+    SyntaxError(Json5Error),
+    /// The file isn't a valid binary scene.
+    BinaryError(BinaryError),
+    /// The file was saved with a newer format version than this reads.
+    UnsupportedFormatVersion(u32),
+    /// An entity, component or resource couldn't be read.
+    ///
+    /// Lenient loads skip these instead.
+    Invalid(LoadDiagnostic),
+    /// The prefab's file doesn't have exactly one entity.
     ///
-    /// Returns the invalid id string.
-    InvalidId(String),
+    /// Returns the file.
+    InvalidPrefab(String),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FileNotFound(file) => write!(f, "Couldn't read {file}"),
+            Self::SyntaxError(error) => write!(f, "{error}"),
+            Self::BinaryError(error) => write!(f, "{error}"),
+            Self::UnsupportedFormatVersion(version) => {
+                write!(f, "Saved with newer format version {version}")
+            }
+            Self::Invalid(diagnostic) => write!(f, "{diagnostic}"),
+            Self::InvalidPrefab(file) => write!(f, "{file} doesn't have exactly one entity"),
+        }
+    }
+}
+
+/// The problems found while reading a world.
+pub(crate) struct Diagnostics {
+    /// Whether to skip what can't be read, instead of stopping.
+    lenient: bool,
+    /// The problems found, if lenient.
+    pub(crate) found: Vec<LoadDiagnostic>,
+}
+
+impl Diagnostics {
+    pub(crate) fn new(lenient: bool) -> Self {
+        Self {
+            lenient,
+            found: Vec::new(),
+        }
+    }
+
+    /// Records a problem.
+    ///
+    /// Returns it as an error if not lenient.
+    pub(crate) fn report(&mut self, diagnostic: LoadDiagnostic) -> Result<(), ReadError> {
+        if self.lenient {
+            self.found.push(diagnostic);
+            Ok(())
+        } else {
+            Err(ReadError::Invalid(diagnostic))
+        }
+    }
 }
 
 fn read_file(file: &str) -> Result<String, ReadError> {
     std::fs::read_to_string(file).map_err(|_| ReadError::FileNotFound(file.to_string()))
 }
 
-fn extract_entities(src: &str) -> Result<StoredEntities, ReadError> {
-    enum State {
-        Start,
-        None,
-        Entities,
-        Entity,
-        Component,
+/// The members of a value that should be an object.
+fn expect_object(value: &Json5Value) -> Result<&[(String, Json5Value)], ReadError> {
+    value
+        .as_object()
+        .ok_or_else(|| ReadError::SyntaxError(value.error("Expected an object")))
+}
+
+/// A component or resource, from its type name and its object of fields.
+fn extract_component(type_name: &str, value: &Json5Value) -> Result<SourcedComponent, ReadError> {
+    let members = expect_object(value)?;
+
+    Ok(SourcedComponent {
+        stored: StoredComponent {
+            type_name: type_name.to_string(),
+            fields: members
+                .iter()
+                .map(|(key, value)| (key.clone(), StoredValue::from_json5(value)))
+                .collect(),
+        },
+        line: value.position.line,
+        field_lines: members
+            .iter()
+            .map(|(key, value)| (key.clone(), value.position.line))
+            .collect(),
+    })
+}
+
+/// A version number in the file's header.
+fn extract_version(value: &Json5Value) -> Result<u32, ReadError> {
+    u32::from_stored_value(StoredValue::from_json5(value))
+        .ok_or_else(|| ReadError::SyntaxError(value.error("Expected a version number")))
+}
+
+fn extract_world(src: &str, diagnostics: &mut Diagnostics) -> Result<StoredWorld, ReadError> {
+    let root = parse_json5(src).map_err(ReadError::SyntaxError)?;
+    expect_object(&root)?;
+
+    let format_version = root.get("format_version").map_or(Ok(0), extract_version)?;
+    if format_version > FORMAT_VERSION {
+        return Err(ReadError::UnsupportedFormatVersion(format_version));
     }
 
-    let mut current_component: Option<StoredComponent> = None;
-    let mut current_entity: Option<(u128, Vec<StoredComponent>)> = None;
+    let mut schema_versions = SchemaVersions::new();
+    if let Some(versions) = root.get("schema_versions") {
+        for (type_name, version) in expect_object(versions)? {
+            schema_versions.insert(type_name.clone(), extract_version(version)?);
+        }
+    }
 
     let mut stored_entities = StoredEntities::new();
-    let mut state = State::Start;
+    if let Some(entities) = root.get("entities") {
+        for (id_str, components) in expect_object(entities)? {
+            let line = components.position.line;
+            let Ok(id) = id_str.parse::<u128>() else {
+                diagnostics.report(LoadDiagnostic::new(
+                    DiagnosticKind::InvalidId,
+                    Some(id_str),
+                    None,
+                    line,
+                ))?;
+                continue;
+            };
 
-    for (line_number, line) in src.lines().enumerate() {
-        match &mut state {
-            State::Start => {
-                if line.starts_with('{') {
-                    state = State::None;
-                }
+            let components = expect_object(components)?
+                .iter()
+                .map(|(type_name, fields)| extract_component(type_name, fields))
+                .collect::<Result<_, _>>()?;
+
+            stored_entities.push((id, line, components));
+        }
+    }
+
+    let mut stored_resources = StoredResources::new();
+    if let Some(resources) = root.get("resources") {
+        for (type_name, fields) in expect_object(resources)? {
+            stored_resources.push(extract_component(type_name, fields)?);
+        }
+    }
+
+    Ok(StoredWorld {
+        entities: stored_entities,
+        resources: stored_resources,
+        schema_versions,
+    })
+}
+
+/// Reads a saved world in either format,
+/// without turning the fields into components.
+///
+/// `entities` is used to look up the type names of binary scenes.
+pub(crate) fn read_stored_world(
+    file: &str,
+    entities: &Entities,
+    diagnostics: &mut Diagnostics,
+) -> Result<StoredWorld, ReadError> {
+    match SceneFormat::from_path(file) {
+        SceneFormat::Json5 => extract_world(&read_file(file)?, diagnostics),
+        SceneFormat::Binary => {
+            let bytes =
+                std::fs::read(file).map_err(|_| ReadError::FileNotFound(file.to_string()))?;
+            read_binary_world(&bytes, entities)
+        }
+    }
+}
+
+/// The entity's name for diagnostics.
+///
+/// This is read from the stored `Name`, so it's known before the components are read.
+/// Falls back to the id.
+pub(crate) fn entity_label(id: u128, components: &[SourcedComponent]) -> String {
+    components
+        .iter()
+        .find(|sourced| sourced.stored.type_name == "Name")
+        .and_then(|sourced| sourced.stored.fields.get("name"))
+        .and_then(StoredValue::as_text)
+        .map_or_else(|| id.to_string(), ToString::to_string)
+}
+
+/// Upgrades a stored component saved with an older schema version,
+/// so `from_stored` gets the fields it expects.
+///
+/// Types that aren't registered are left as they are.
+/// Returns the version if it's newer than the type has.
+pub(crate) fn upgrade_component(
+    entities: &Entities,
+    migrations: &Migrations,
+    schema_versions: &SchemaVersions,
+    stored: StoredComponent,
+) -> Result<StoredComponent, u32> {
+    let Some(component_info) = entities.component_info_from_name(&stored.type_name) else {
+        return Ok(stored);
+    };
+
+    let version = schema_versions.get(&stored.type_name).copied().unwrap_or(0);
+    if version > component_info.schema_version {
+        return Err(version);
+    }
+
+    Ok(migrations.migrate(stored, version, component_info.schema_version))
+}
+
+/// Reads a stored component that has been upgraded.
+///
+/// `entity` is `None` for resources.
+///
+/// # Errors
+///
+/// Returns the problem if its type isn't registered, or a field is missing or invalid.
+pub(crate) fn read_component(
+    entities: &Entities,
+    assets: &Assets,
+    sourced: SourcedComponent,
+    entity: Option<&str>,
+) -> Result<ComponentBox, LoadDiagnostic> {
+    let SourcedComponent {
+        stored,
+        line,
+        field_lines,
+    } = sourced;
+
+    let Some(component_info) = entities.component_info_from_name(&stored.type_name) else {
+        return Err(LoadDiagnostic::new(
+            DiagnosticKind::UnknownComponent,
+            entity,
+            Some(&stored.type_name),
+            line,
+        ));
+    };
+
+    let type_name = stored.type_name.clone();
+    (component_info.from_stored)(stored, assets).map_err(|error| {
+        // Fields renamed by a migration aren't in the file,
+        // so they fall back to the component's line.
+        let line = error
+            .field
+            .as_ref()
+            .and_then(|field| field_lines.get(field))
+            .copied()
+            .unwrap_or(line);
+
+        LoadDiagnostic {
+            kind: if error.missing {
+                DiagnosticKind::MissingField
+            } else {
+                DiagnosticKind::InvalidField
+            },
+            entity: entity.map(ToString::to_string),
+            component: Some(type_name),
+            field: error.field,
+            expected: error.expected,
+            line,
+        }
+    })
+}
+
+/// Turns stored components and resources into components.
+struct ComponentReader<'a> {
+    entities: &'a Entities,
+    migrations: &'a Migrations,
+    schema_versions: &'a SchemaVersions,
+    assets: &'a Assets,
+    diagnostics: Diagnostics,
+    /// The prefabs read so far, by id,
+    /// or why they couldn't be read.
+    prefabs: HashMap<String, Result<Prefab, String>>,
+}
+
+impl ComponentReader<'_> {
+    /// Upgrades a component saved with an older schema version.
+    ///
+    /// Returns `None` if it was skipped.
+    fn upgrade(
+        &mut self,
+        sourced: SourcedComponent,
+        entity: Option<&str>,
+    ) -> Result<Option<SourcedComponent>, ReadError> {
+        let SourcedComponent {
+            stored,
+            line,
+            field_lines,
+        } = sourced;
+
+        let type_name = stored.type_name.clone();
+        match upgrade_component(self.entities, self.migrations, self.schema_versions, stored) {
+            Ok(stored) => Ok(Some(SourcedComponent {
+                stored,
+                line,
+                field_lines,
+            })),
+            Err(version) => {
+                self.diagnostics.report(LoadDiagnostic::new(
+                    DiagnosticKind::UnsupportedSchemaVersion(version),
+                    entity,
+                    Some(&type_name),
+                    line,
+                ))?;
+                Ok(None)
             }
-            State::None => {
-                if line.trim_start().starts_with("entities: {") {
-                    state = State::Entities;
-                }
+        }
+    }
+
+    /// Reads an upgraded component.
+    ///
+    /// Returns `None` if it was skipped.
+    fn read_upgraded(
+        &mut self,
+        sourced: SourcedComponent,
+        entity: Option<&str>,
+    ) -> Result<Option<ComponentBox>, ReadError> {
+        // Resources that aren't registered are skipped,
+        // as the runtime doesn't have the editor's resources.
+        // The same goes for FrameHistoryState,
+        // which older scenes stored on the "SystemState" entity.
+        if (entity.is_none() || sourced.stored.type_name == "FrameHistoryState")
+            && self
+                .entities
+                .component_info_from_name(&sourced.stored.type_name)
+                .is_none()
+        {
+            return Ok(None);
+        }
+
+        match read_component(self.entities, self.assets, sourced, entity) {
+            Ok(component) => Ok(Some(component)),
+            Err(diagnostic) => {
+                self.diagnostics.report(diagnostic)?;
+                Ok(None)
             }
-            State::Entities => {
-                if let Some(id_str) = line
-                    .trim_start()
-                    .strip_prefix('"')
-                    .and_then(|line| line.strip_suffix("\": {"))
-                {
-                    let id = id_str
-                        .parse::<u128>()
-                        .map_err(|_| ReadError::InvalidId(id_str.to_string()))?;
-                    current_entity = Some((id, Vec::new()));
-                    state = State::Entity;
-                } else if line.trim() != "}" || line.trim() != "}," {
-                    state = State::None;
-                } else {
-                    return Err(ReadError::SyntaxError(ErrorLocation::Line(line_number)));
-                }
+        }
+    }
+
+    /// Upgrades a component, then reads it.
+    ///
+    /// `entity` is `None` for resources.
+    /// Returns `None` if the component was skipped.
+    fn read(
+        &mut self,
+        sourced: SourcedComponent,
+        entity: Option<&str>,
+    ) -> Result<Option<ComponentBox>, ReadError> {
+        match self.upgrade(sourced, entity)? {
+            Some(sourced) => self.read_upgraded(sourced, entity),
+            None => Ok(None),
+        }
+    }
+
+    /// Fills in the components of a prefab instance from its prefab,
+    /// keeping the instance's fields over the prefab's.
+    ///
+    /// Entities that aren't instances are returned as they are.
+    fn apply_prefab(
+        &mut self,
+        components: Vec<SourcedComponent>,
+        entity: &str,
+    ) -> Result<Vec<SourcedComponent>, ReadError> {
+        let Some((prefab_id, line)) = components
+            .iter()
+            .find(|sourced| sourced.stored.type_name == PrefabInstance::type_name())
+            .and_then(|sourced| {
+                let prefab_id = sourced.stored.fields.get("prefab")?.as_text()?;
+                let line = sourced.field_lines.get("prefab").copied();
+                Some((prefab_id.to_string(), line.unwrap_or(sourced.line)))
+            })
+        else {
+            return Ok(components);
+        };
+
+        // Each prefab is read once per load, so edits to it show when the scene is reloaded.
+        let entities = self.entities;
+        let migrations = self.migrations;
+        let prefab = self
+            .prefabs
+            .entry(prefab_id)
+            .or_insert_with_key(|prefab_id| {
+                Prefab::load(prefab_id, entities, migrations).map_err(|error| error.to_string())
+            });
+
+        match prefab {
+            Ok(prefab) => Ok(prefab.with_overrides(components)),
+            Err(error) => {
+                let diagnostic = LoadDiagnostic {
+                    kind: DiagnosticKind::InvalidPrefab(error.clone()),
+                    entity: Some(entity.to_string()),
+                    component: Some(PrefabInstance::type_name().to_string()),
+                    field: Some("prefab".to_string()),
+                    expected: None,
+                    line,
+                };
+                self.diagnostics.report(diagnostic)?;
+                Ok(components)
             }
-            State::Entity => {
-                if let Some(component_name) = line.strip_suffix(": {") {
-                    let component_name = component_name.trim_start();
-                    current_component = Some(StoredComponent {
-                        type_name: component_name.to_string(),
-                        fields: HashMap::new(),
-                    });
-                    state = State::Component;
-                } else if line.trim() == "}" || line.trim() == "}," {
-                    stored_entities.push(
-                        current_entity.take().expect(
-                            "Something went very wrong in parsing. Code ran in wrong order.",
-                        ),
-                    );
-                    state = State::Entities;
-                } else {
-                    return Err(ReadError::SyntaxError(ErrorLocation::Line(line_number)));
+        }
+    }
+
+    fn parse_components(
+        &mut self,
+        stored_entities: StoredEntities,
+    ) -> Result<ParsedEntities, ReadError> {
+        let mut parsed_entities = Vec::with_capacity(stored_entities.len());
+
+        for (id, line, stored_components) in stored_entities {
+            let label = entity_label(id, &stored_components);
+            let mut upgraded = Vec::with_capacity(stored_components.len());
+            for sourced in stored_components {
+                if let Some(sourced) = self.upgrade(sourced, Some(&label))? {
+                    upgraded.push(sourced);
                 }
             }
-            State::Component => {
-                if let Some((key, value)) = line.split_once(':') {
-                    let key = key.trim().to_string();
-                    let value =
-                        StoredComponentField::from_json5(value.trim().trim_end_matches(','));
-
-                    current_component
-                        .as_mut()
-                        .expect("Something went very wrong in parsing. Code ran in wrong order.")
-                        .fields
-                        .insert(key, value);
-                } else if line.trim() == "}" || line.trim() == "}," {
-                    current_entity
-                        .as_mut()
-                        .expect("Something went very wrong in parsing. Code ran in wrong order.")
-                        .1
-                        .push(current_component.take().expect(
-                            "Something went very wrong in parsing. Code ran in wrong order.",
-                        ));
-                    state = State::Entity;
+
+            let stored_components = self.apply_prefab(upgraded, &label)?;
+            let mut parsed_components = Vec::with_capacity(stored_components.len());
+            let mut name = None;
+
+            for sourced in stored_components {
+                let Some(component) = self.read_upgraded(sourced, Some(&label))? else {
+                    continue;
+                };
+
+                if component.type_name() == "Name" {
+                    name = Some(component.take::<Name>());
                 } else {
-                    return Err(ReadError::SyntaxError(ErrorLocation::Line(line_number)));
+                    parsed_components.push(component);
                 }
             }
+
+            let name = match name {
+                Some(name) => name,
+                None => {
+                    self.diagnostics.report(LoadDiagnostic::new(
+                        DiagnosticKind::MissingName,
+                        Some(&label),
+                        None,
+                        line,
+                    ))?;
+                    // It's still loaded, named by its id.
+                    Name {
+                        name: id.to_string(),
+                    }
+                }
+            };
+
+            parsed_entities.push((id, name, parsed_components));
         }
+
+        Ok(parsed_entities)
     }
 
-    Ok(stored_entities)
-}
+    fn parse_resources(
+        &mut self,
+        stored_resources: StoredResources,
+    ) -> Result<Vec<ComponentBox>, ReadError> {
+        let mut parsed_resources = Vec::with_capacity(stored_resources.len());
 
-fn parse_components(
-    entities: &Entities,
-    assets: &Assets,
-    stored_entities: StoredEntities,
-) -> Result<ParsedEntities, ReadError> {
-    let mut parsed_entities = Vec::with_capacity(stored_entities.len());
-
-    for (id, stored_components) in stored_entities {
-        let mut parsed_components = Vec::with_capacity(stored_components.len());
-        let mut name = None;
-
-        for stored in stored_components {
-            let Some(component_info) = entities
-                .component_info_from_name(&stored.type_name)
-                // If the component isn't registered,
-                else {
-                    // And it's FrameHistoryState
-                    if stored.type_name == "FrameHistoryState" {
-                        // Don't crash.
-                        // This is because the runtime doesn't contain this
-                        continue;
-                    }
+        for sourced in stored_resources {
+            if let Some(resource) = self.read(sourced, None)? {
+                parsed_resources.push(resource);
+            }
+        }
 
-                    return Err(ReadError::UnknownComponent(
-                        ErrorLocation::Component(id),
-                        stored.type_name,
-                    ))
-                };
-            let from_stored_fn = component_info.from_stored;
+        Ok(parsed_resources)
+    }
+}
+
+/// Adds the loaded entities and resources to the world.
+///
+/// Entities are tagged with the scene they were loaded from,
+/// unless they were saved with one.
+///
+/// Additive loads replace only the scene's own entities,
+/// and keep the resources the world already has.
+fn write_components_to_world(
+    parsed_entities: ParsedEntities,
+    parsed_resources: Vec<ComponentBox>,
+    world: &mut World,
+    scene: &str,
+    additive: bool,
+) {
+    if additive {
+        world.entities.unload_scene(scene);
+    } else {
+        world.entities.clear();
+    }
 
-            let component = from_stored_fn(stored, assets)
-                .ok_or(ReadError::InvalidField(ErrorLocation::Component(id)))?;
+    let mut insert_resource = |resource: ComponentBox| {
+        if !additive
+            || world
+                .resources
+                .get_by_type_id(resource.stable_type_id())
+                .is_none()
+        {
+            world.resources.insert(resource);
+        }
+    };
 
-            if component.type_name() == "Name" {
-                name = Some(component);
-            } else {
-                parsed_components.push(component);
+    for (id, name, mut components) in parsed_entities {
+        // Older scenes stored resources on an entity with this name.
+        if name.name == "SystemState" {
+            for resource in components {
+                insert_resource(resource);
             }
+            continue;
         }
 
-        parsed_entities.push((
-            id,
-            name.ok_or(ReadError::MissingName(id))?.take::<Name>(),
-            parsed_components,
-        ));
+        if !components
+            .iter()
+            .any(|component| component.is_type::<SceneOrigin>())
+        {
+            components.push(
+                SceneOrigin {
+                    scene: scene.to_string(),
+                }
+                .into(),
+            );
+        }
+
+        // The parents are resolved once they're all loaded.
+        world
+            .entities
+            .new_entity_unlinked(Some(id), &name.name, components);
+    }
+
+    for resource in parsed_resources {
+        insert_resource(resource);
     }
 
-    Ok(parsed_entities)
+    // Parents are saved by persistent id,
+    // which can only be looked up once every entity exists.
+    world.entities.resolve_parents();
 }
 
-fn write_components_to_world(parsed_entities: ParsedEntities, world: &mut World) {
-    world.entities.clear();
+/// Reads the file, and adds it to the world.
+///
+/// The format is chosen by the file's extension.
+///
+/// Returns the problems that were skipped if lenient.
+fn load_world(
+    file: &str,
+    world: &mut World,
+    assets: &Assets,
+    lenient: bool,
+    additive: bool,
+) -> Result<Vec<LoadDiagnostic>, ReadError> {
+    let mut diagnostics = Diagnostics::new(lenient);
+    let stored_world = read_stored_world(file, &world.entities, &mut diagnostics)?;
+
+    let mut reader = ComponentReader {
+        entities: &world.entities,
+        migrations: &world.migrations,
+        schema_versions: &stored_world.schema_versions,
+        assets,
+        diagnostics,
+        prefabs: HashMap::new(),
+    };
+    let parsed_entities = reader.parse_components(stored_world.entities)?;
+    let parsed_resources = reader.parse_resources(stored_world.resources)?;
+    let diagnostics = reader.diagnostics.found;
 
-    for (id, name, components) in parsed_entities {
-        world.entities.new_entity(Some(id), &name.name, vec![]);
+    write_components_to_world(parsed_entities, parsed_resources, world, file, additive);
 
-        world.entities.add_components(id, components);
-    }
+    Ok(diagnostics)
 }
 
 /// A simple wrapper around `StorageReader` to load a world from a file.
@@ -213,19 +654,377 @@ fn write_components_to_world(parsed_entities: ParsedEntities, world: &mut World)
 ///
 /// # Errors
 ///
-/// Will return an error if the file cannot be read, or if the file is not a valid JSON5 file.
+/// Will return an error if the file cannot be read, or if the file is not a valid scene,
+/// or on the first entity, component or resource that can't be read.
 pub fn load_world_from_file(
     file: &str,
     world: &mut World,
     assets: &Assets,
 ) -> Result<(), ReadError> {
-    let src = read_file(file)?;
+    load_world(file, world, assets, false, false).map(|_| ())
+}
 
-    let stored_entities = extract_entities(&src)?;
+/// Loads a world from a file, skipping entities, components and resources that can't be read.
+///
+/// Returns what was skipped and why.
+///
+/// # Errors
+///
+/// Will return an error if the file cannot be read, or if the file is not a valid scene.
+pub fn load_world_from_file_lenient(
+    file: &str,
+    world: &mut World,
+    assets: &Assets,
+) -> Result<Vec<LoadDiagnostic>, ReadError> {
+    load_world(file, world, assets, true, false)
+}
 
-    let parsed_entities = parse_components(&world.entities, assets, stored_entities)?;
+/// Loads a scene from a file on top of the world's other scenes.
+///
+/// If the scene is already loaded, its entities are replaced.
+/// Resources the world already has are kept.
+///
+/// # Errors
+///
+/// Will return an error if the file cannot be read, or if the file is not a valid scene,
+/// or on the first entity, component or resource that can't be read.
+pub fn load_additive_from_file(
+    file: &str,
+    world: &mut World,
+    assets: &Assets,
+) -> Result<(), ReadError> {
+    load_world(file, world, assets, false, true).map(|_| ())
+}
+
+/// Loads a scene from a file on top of the world's other scenes,
+/// skipping entities, components and resources that can't be read.
+///
+/// Returns what was skipped and why.
+///
+/// # Errors
+///
+/// Will return an error if the file cannot be read, or if the file is not a valid scene.
+pub fn load_additive_from_file_lenient(
+    file: &str,
+    world: &mut World,
+    assets: &Assets,
+) -> Result<Vec<LoadDiagnostic>, ReadError> {
+    load_world(file, world, assets, true, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{storage::ComponentStorage, systems::Systems, Transform};
+    use iridium_ecs_macros::{Component, ComponentStorage, HasStableTypeId, InspectorUi};
+    use iridium_reflect::HasStableTypeId;
+
+    /// Was saved as `speed` before version 1.
+    #[derive(Component, InspectorUi, ComponentStorage, HasStableTypeId)]
+    #[schema_version(1)]
+    struct Speed {
+        metres_per_second: f32,
+    }
+
+    #[test]
+    fn world_round_trips() {
+        let mut world = World::new(Entities::default(), Systems::new());
+        let name = "A \"quoted\" name, with a \\ and a\nnewline";
+        let mut transform = Transform::default();
+        *transform.position.x_mut() = 1.5;
+        world
+            .entities
+            .new_entity(Some(7), name, vec![transform.into()]);
+
+        let file = std::env::temp_dir().join("iridium_world_round_trips.json5");
+        let file = file.to_str().unwrap();
+        world.save(file);
+
+        let mut loaded = World::new(Entities::default(), Systems::new());
+        loaded.load(file, &Assets::new()).unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        let entity = loaded.entities.entity_from_persistent_id(7).unwrap();
+        let loaded_name = loaded
+            .entities
+            .entity_component(entity, Name::stable_type_id())
+            .unwrap();
+        assert_eq!(loaded_name.get::<Name>().name, name);
+
+        let transform = loaded
+            .entities
+            .entity_component(entity, Transform::stable_type_id())
+            .unwrap();
+        assert!((transform.get::<Transform>().position.x() - 1.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn syntax_errors_have_positions() {
+        let src = "{\n    entities: {\n        \"1\": { Name: { name: \"a\" } }\n        \"2\": {},\n    },\n}";
+        let Err(ReadError::SyntaxError(error)) = extract_world(src, &mut Diagnostics::new(false))
+        else {
+            panic!("Expected a syntax error");
+        };
+        assert_eq!((error.position.line, error.position.column), (4, 9));
+
+        let src = "{ entities: { \"1\": { Name: 5 } } }";
+        let Err(ReadError::SyntaxError(error)) = extract_world(src, &mut Diagnostics::new(false))
+        else {
+            panic!("Expected a syntax error");
+        };
+        assert_eq!(error.message, "Expected an object");
+        assert_eq!((error.position.line, error.position.column), (1, 28));
+    }
+
+    #[test]
+    fn reads_single_line_objects() {
+        let StoredWorld {
+            entities,
+            resources,
+            ..
+        } = extract_world(
+            "{ resources: { Gravity: { strength: 9.8 } }, entities: { \"3\": { Name: { name: 'x' } } } }",
+            &mut Diagnostics::new(false),
+        )
+        .unwrap();
+
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].0, 3);
+        assert_eq!(resources[0].stored.type_name, "Gravity");
+        assert_eq!(
+            resources[0].stored.fields["strength"],
+            StoredValue::Number("9.8".to_string())
+        );
+    }
+
+    #[test]
+    fn migrates_older_schema_versions() {
+        let mut world = World::new(Entities::default(), Systems::new());
+        world.entities.register_component::<Speed>();
+        world.migrations.register("Speed", 0, |mut stored| {
+            if let Some(speed) = stored.fields.remove("speed") {
+                stored.fields.insert("metres_per_second".to_string(), speed);
+            }
+            stored
+        });
+
+        let file = std::env::temp_dir().join("iridium_migrates_older_schema_versions.json5");
+        let file = file.to_str().unwrap();
+        std::fs::write(
+            file,
+            "{ entities: { \"1\": { Name: { name: \"a\" }, Speed: { speed: 2.5 } } } }",
+        )
+        .unwrap();
+        world.load(file, &Assets::new()).unwrap();
+
+        let speed = |world: &World| {
+            let entity = world.entities.entity_from_persistent_id(1).unwrap();
+            world
+                .entities
+                .entity_component(entity, Speed::stable_type_id())
+                .unwrap()
+                .get::<Speed>()
+                .metres_per_second
+        };
+        assert!((speed(&world) - 2.5).abs() < f32::EPSILON);
+
+        // Saving writes the versions, so the migration isn't applied again.
+        world.save(file);
+        let saved = std::fs::read_to_string(file).unwrap();
+        assert!(saved.starts_with("{\n    format_version: 1,\n"));
+        assert!(saved.contains("schema_versions: {\n        Speed: 1,\n    },"));
+
+        world.load(file, &Assets::new()).unwrap();
+        std::fs::remove_file(file).unwrap();
+        assert!((speed(&world) - 2.5).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        assert!(matches!(
+            extract_world("{ format_version: 2 }", &mut Diagnostics::new(true)),
+            Err(ReadError::UnsupportedFormatVersion(2))
+        ));
+
+        let mut world = World::new(Entities::default(), Systems::new());
+        world.entities.register_component::<Speed>();
+        let mut reader = ComponentReader {
+            entities: &world.entities,
+            migrations: &world.migrations,
+            schema_versions: &SchemaVersions::from([("Speed".to_string(), 2)]),
+            assets: &Assets::new(),
+            diagnostics: Diagnostics::new(false),
+            prefabs: HashMap::new(),
+        };
+        let result = reader.read(
+            SourcedComponent {
+                stored: Speed {
+                    metres_per_second: 1.,
+                }
+                .to_stored(),
+                line: 3,
+                field_lines: HashMap::new(),
+            },
+            Some("a"),
+        );
+        let Err(ReadError::Invalid(diagnostic)) = result else {
+            panic!("Expected the component to be invalid");
+        };
+        assert_eq!(
+            diagnostic,
+            LoadDiagnostic::new(
+                DiagnosticKind::UnsupportedSchemaVersion(2),
+                Some("a"),
+                Some("Speed"),
+                3
+            )
+        );
+    }
 
-    write_components_to_world(parsed_entities, world);
+    #[test]
+    fn lenient_loads_skip_and_report() {
+        let src = r#"{
+    entities: {
+        "1": {
+            Name: { name: "Ball" },
+            Speed: { metres_per_second: "fast" },
+            Gravity: {},
+        },
+        "2": {
+            Speed: {},
+        },
+        "x": {},
+    },
+}"#;
+        let file = std::env::temp_dir().join("iridium_lenient_loads_skip_and_report.json5");
+        let file = file.to_str().unwrap();
+        std::fs::write(file, src).unwrap();
+
+        let mut world = World::new(Entities::default(), Systems::new());
+        world.entities.register_component::<Speed>();
+
+        // Strict loads stop at the first problem, and leave the world as it was.
+        let result = world.load(file, &Assets::new());
+        assert!(matches!(
+            result,
+            Err(ReadError::Invalid(LoadDiagnostic {
+                kind: DiagnosticKind::InvalidId,
+                line: 11,
+                ..
+            }))
+        ));
+        assert!(world.entities.entity_from_persistent_id(1).is_none());
 
-    Ok(())
+        let diagnostics = world.load_lenient(file, &Assets::new()).unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        assert_eq!(
+            diagnostics,
+            [
+                LoadDiagnostic::new(DiagnosticKind::InvalidId, Some("x"), None, 11),
+                LoadDiagnostic {
+                    kind: DiagnosticKind::InvalidField,
+                    entity: Some("Ball".to_string()),
+                    component: Some("Speed".to_string()),
+                    field: Some("metres_per_second".to_string()),
+                    expected: Some("f32"),
+                    line: 5,
+                },
+                LoadDiagnostic::new(
+                    DiagnosticKind::UnknownComponent,
+                    Some("Ball"),
+                    Some("Gravity"),
+                    6
+                ),
+                LoadDiagnostic {
+                    kind: DiagnosticKind::MissingField,
+                    entity: Some("2".to_string()),
+                    component: Some("Speed".to_string()),
+                    field: Some("metres_per_second".to_string()),
+                    expected: Some("f32"),
+                    line: 9,
+                },
+                LoadDiagnostic::new(DiagnosticKind::MissingName, Some("2"), None, 8),
+            ]
+        );
+        assert_eq!(
+            diagnostics[1].to_string(),
+            "Line 5: Speed on \"Ball\", field `metres_per_second` is invalid, expected f32"
+        );
+
+        // The rest of the entities are still loaded.
+        let ball = world.entities.entity_from_persistent_id(1).unwrap();
+        assert!(world
+            .entities
+            .entity_component(ball, Speed::stable_type_id())
+            .is_none());
+        let unnamed = world.entities.entity_from_persistent_id(2).unwrap();
+        assert_eq!(
+            world
+                .entities
+                .entity_component(unnamed, Name::stable_type_id())
+                .unwrap()
+                .get::<Name>()
+                .name,
+            "2"
+        );
+    }
+
+    #[test]
+    fn scenes_load_additively() {
+        let temp_file = |name: &str| {
+            let file = std::env::temp_dir().join(name);
+            file.to_str().unwrap().to_string()
+        };
+        let level = temp_file("iridium_scenes_load_additively_level.json5");
+        let hud = temp_file("iridium_scenes_load_additively_hud.json5");
+        let snapshot = temp_file("iridium_scenes_load_additively_snapshot.json5");
+
+        let mut world = World::new(Entities::default(), Systems::new());
+        world
+            .entities
+            .new_entity(Some(1), "Wall", vec![Transform::default().into()]);
+        world.save(&level);
+        world.entities.clear();
+        world.entities.new_entity(Some(2), "Score", vec![]);
+        world.save(&hud);
+
+        let mut world = World::new(Entities::default(), Systems::new());
+        world.load(&level, &Assets::new()).unwrap();
+        world.load_additive(&hud, &Assets::new()).unwrap();
+        // Loading a scene again replaces its entities.
+        world.load_additive(&hud, &Assets::new()).unwrap();
+        assert_eq!(world.entities.all_entities().len(), 2);
+        assert_eq!(world.entities.scenes(), vec![hud.clone(), level.clone()]);
+
+        // Snapshots of the whole world keep where entities came from.
+        world.save(&snapshot);
+        world.load(&snapshot, &Assets::new()).unwrap();
+        assert_eq!(world.entities.scenes(), vec![hud.clone(), level.clone()]);
+
+        world.entities.new_entity(
+            Some(3),
+            "Lives",
+            vec![SceneOrigin { scene: hud.clone() }.into()],
+        );
+        world.save_scene(&hud);
+        let saved = std::fs::read_to_string(&hud).unwrap();
+        assert!(saved.contains("Score") && saved.contains("Lives"));
+        assert!(!saved.contains("Wall"));
+        assert!(!saved.contains("SceneOrigin"));
+
+        // Children from other scenes aren't unloaded with their parent.
+        let wall = world.entities.entity_from_persistent_id(1).unwrap();
+        let score = world.entities.entity_from_persistent_id(2).unwrap();
+        world.entities.set_parent(wall, Some(score));
+
+        assert_eq!(world.unload_scene(&hud), 2);
+        assert!(world.entities.entity_from_persistent_id(1).is_some());
+        assert!(world.entities.entity_from_persistent_id(3).is_none());
+        assert_eq!(world.entities.parent(wall), None);
+
+        for file in [level, hud, snapshot] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
 }
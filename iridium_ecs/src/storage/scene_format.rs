@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use super::{
+    entity_label, read_stored_world, save_stored_world_to_file, DiagnosticKind, Diagnostics,
+    LoadDiagnostic, ReadError, SourcedComponent,
+};
+use crate::Entities;
+
+/// The file extension of binary scenes.
+pub const BINARY_SCENE_EXTENSION: &str = "iscene";
+
+/// The formats a scene can be saved in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneFormat {
+    /// Human readable json5, which merges well in version control.
+    Json5,
+    /// Smaller and faster to read, with components keyed by their `StableTypeId`.
+    Binary,
+}
+
+impl SceneFormat {
+    /// The format of the file, from its extension.
+    ///
+    /// Files ending in `BINARY_SCENE_EXTENSION` are binary, the rest are json5.
+    #[must_use]
+    pub fn from_path(file: &str) -> Self {
+        if Path::new(file)
+            .extension()
+            .is_some_and(|extension| extension == BINARY_SCENE_EXTENSION)
+        {
+            Self::Binary
+        } else {
+            Self::Json5
+        }
+    }
+}
+
+/// Removes the components and resources whose types aren't registered,
+/// reporting each of them.
+///
+/// Binary scenes only store type ids, so these can't be written with a name.
+fn remove_unknown_types(
+    components: &mut Vec<SourcedComponent>,
+    entities: &Entities,
+    entity: Option<&str>,
+    diagnostics: &mut Diagnostics,
+) -> Result<(), ReadError> {
+    let mut kept = Vec::with_capacity(components.len());
+
+    for sourced in components.drain(..) {
+        if entities
+            .component_info_from_name(&sourced.stored.type_name)
+            .is_some()
+        {
+            kept.push(sourced);
+        } else {
+            diagnostics.report(LoadDiagnostic::new(
+                DiagnosticKind::UnknownComponent,
+                entity,
+                Some(&sourced.stored.type_name),
+                sourced.line,
+            ))?;
+        }
+    }
+
+    *components = kept;
+    Ok(())
+}
+
+/// Converts a scene between formats, chosen by the files' extensions.
+///
+/// The components' fields are copied as they are, so no assets are needed.
+///
+/// Converting from binary needs the components and resources to be registered in `entities`,
+/// to know their type names.
+/// Those that aren't are left out, and returned.
+///
+/// # Errors
+///
+/// Will return an error if the source file cannot be read, or if it is not a valid scene.
+pub fn convert_scene(
+    src: &str,
+    dst: &str,
+    entities: &Entities,
+) -> Result<Vec<LoadDiagnostic>, ReadError> {
+    let mut diagnostics = Diagnostics::new(true);
+    let mut world = read_stored_world(src, entities, &mut diagnostics)?;
+
+    if SceneFormat::from_path(src) == SceneFormat::Binary {
+        for (id, _, components) in &mut world.entities {
+            let entity = entity_label(*id, components);
+            remove_unknown_types(components, entities, Some(&entity), &mut diagnostics)?;
+        }
+        remove_unknown_types(&mut world.resources, entities, None, &mut diagnostics)?;
+    }
+
+    save_stored_world_to_file(world, dst);
+
+    Ok(diagnostics.found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{systems::Systems, Transform, World};
+
+    fn temp_file(name: &str) -> String {
+        std::env::temp_dir()
+            .join(name)
+            .to_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn format_is_chosen_by_extension() {
+        assert_eq!(
+            SceneFormat::from_path("scenes/level.iscene"),
+            SceneFormat::Binary
+        );
+        assert_eq!(
+            SceneFormat::from_path("scenes/level.json5"),
+            SceneFormat::Json5
+        );
+        assert_eq!(SceneFormat::from_path("iscene"), SceneFormat::Json5);
+    }
+
+    #[test]
+    fn scenes_convert_both_ways() {
+        let mut world = World::new(Entities::default(), Systems::new());
+        let mut transform = Transform::default();
+        *transform.position.y_mut() = -2.25;
+        world
+            .entities
+            .new_entity(Some(4), "Ball", vec![transform.into()]);
+        world
+            .entities
+            .new_entity(Some(9), "Wall", vec![Transform::default().into()]);
+
+        let json5 = temp_file("iridium_scenes_convert_both_ways.json5");
+        let binary = temp_file("iridium_scenes_convert_both_ways.iscene");
+        let converted = temp_file("iridium_scenes_convert_both_ways_converted.json5");
+        world.save(&json5);
+
+        assert!(convert_scene(&json5, &binary, &world.entities)
+            .unwrap()
+            .is_empty());
+        assert!(convert_scene(&binary, &converted, &world.entities)
+            .unwrap()
+            .is_empty());
+
+        let original = std::fs::read_to_string(&json5).unwrap();
+        let binary_len = std::fs::metadata(&binary).unwrap().len();
+        assert_eq!(std::fs::read_to_string(&converted).unwrap(), original);
+        assert!(binary_len < original.len() as u64);
+
+        for file in [json5, binary, converted] {
+            std::fs::remove_file(file).unwrap();
+        }
+    }
+
+    #[test]
+    fn unregistered_types_are_left_out() {
+        let mut world = World::new(Entities::default(), Systems::new());
+        world
+            .entities
+            .new_entity(Some(1), "Ball", vec![Transform::default().into()]);
+
+        let binary = temp_file("iridium_unregistered_types_are_left_out.iscene");
+        let json5 = temp_file("iridium_unregistered_types_are_left_out.json5");
+        world.save(&binary);
+
+        world.entities.unregister_component::<Transform>();
+        let diagnostics = convert_scene(&binary, &json5, &world.entities).unwrap();
+        let converted = std::fs::read_to_string(&json5).unwrap();
+        std::fs::remove_file(binary).unwrap();
+        std::fs::remove_file(json5).unwrap();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::UnknownComponent);
+        assert_eq!(diagnostics[0].entity.as_deref(), Some("Ball"));
+        assert!(!converted.contains("Transform"));
+        assert!(converted.contains("Name"));
+    }
+}
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+
+use iridium_maths::VecN;
+
+use super::{Json5Kind, Json5Value};
+
+/// A value as it is stored.
+///
+/// Fields of components are stored as these,
+/// so they can be nested, like a list of positions or a map.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StoredValue {
+    /// No value, like `None`.
+    Null,
+    /// A boolean.
+    Bool(bool),
+    /// A number.
+    ///
+    /// This is kept as text, so large integers aren't rounded.
+    Number(String),
+    /// A string.
+    String(String),
+    /// A list of values.
+    Array(Vec<StoredValue>),
+    /// Named values, like a nested struct or a map.
+    Object(HashMap<String, StoredValue>),
+}
+
+impl StoredValue {
+    /// The value as text, if it's a string, number or bool.
+    ///
+    /// Older scenes sometimes stored numbers as strings and strings bare,
+    /// so reading is lenient about which of these it is.
+    #[must_use]
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            Self::String(text) | Self::Number(text) => Some(text),
+            Self::Bool(true) => Some("true"),
+            Self::Bool(false) => Some("false"),
+            _ => None,
+        }
+    }
+
+    /// Creates a value from json5.
+    ///
+    /// Bare identifiers, like `ArrowUp`, are read as strings.
+    #[must_use]
+    pub fn from_json5(value: &Json5Value) -> Self {
+        match &value.kind {
+            Json5Kind::Null => Self::Null,
+            Json5Kind::Bool(value) => Self::Bool(*value),
+            Json5Kind::Number(number) => Self::Number(number.clone()),
+            Json5Kind::String(string) | Json5Kind::Identifier(string) => {
+                Self::String(string.clone())
+            }
+            Json5Kind::Array(values) => Self::Array(values.iter().map(Self::from_json5).collect()),
+            Json5Kind::Object(members) => Self::Object(
+                members
+                    .iter()
+                    .map(|(key, value)| (key.clone(), Self::from_json5(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The value as json5.
+    ///
+    /// Objects are sorted by key, so saves are stable.
+    #[must_use]
+    pub fn to_json5(&self) -> Json5Value {
+        Json5Value::new(match self {
+            Self::Null => Json5Kind::Null,
+            Self::Bool(value) => Json5Kind::Bool(*value),
+            Self::Number(number) => Json5Kind::Number(number.clone()),
+            Self::String(string) => Json5Kind::String(string.clone()),
+            Self::Array(values) => Json5Kind::Array(values.iter().map(Self::to_json5).collect()),
+            Self::Object(members) => {
+                let mut members = members
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.to_json5()))
+                    .collect::<Vec<_>>();
+                members.sort_by(|(a, _), (b, _)| a.cmp(b));
+                Json5Kind::Object(members)
+            }
+        })
+    }
+}
+
+/// A type that can be stored as a field of a component.
+///
+/// This can be derived for structs, which are stored as objects.
+pub trait FieldStorage: Sized {
+    /// Creates the value as it is stored.
+    fn to_stored_value(&self) -> StoredValue;
+
+    /// Tries to read the value back.
+    ///
+    /// Returns `None` if the stored value isn't valid for the type.
+    fn from_stored_value(value: StoredValue) -> Option<Self>;
+}
+
+impl FieldStorage for bool {
+    fn to_stored_value(&self) -> StoredValue {
+        StoredValue::Bool(*self)
+    }
+
+    fn from_stored_value(value: StoredValue) -> Option<Self> {
+        value.as_text()?.parse().ok()
+    }
+}
+
+impl FieldStorage for String {
+    fn to_stored_value(&self) -> StoredValue {
+        StoredValue::String(self.clone())
+    }
+
+    fn from_stored_value(value: StoredValue) -> Option<Self> {
+        match value {
+            StoredValue::String(string) => Some(string),
+            value => value.as_text().map(ToString::to_string),
+        }
+    }
+}
+
+macro_rules! impl_field_storage_int {
+    ($($ty:ty),*) => {
+        $(
+            impl FieldStorage for $ty {
+                fn to_stored_value(&self) -> StoredValue {
+                    StoredValue::Number(self.to_string())
+                }
+
+                fn from_stored_value(value: StoredValue) -> Option<Self> {
+                    let text = value.as_text()?;
+                    text.parse().ok().or_else(|| {
+                        // Json5 allows hex integers.
+                        let (negative, digits) = match text.strip_prefix('-') {
+                            Some(digits) => (true, digits),
+                            None => (false, text.trim_start_matches('+')),
+                        };
+                        let hex = digits
+                            .strip_prefix("0x")
+                            .or_else(|| digits.strip_prefix("0X"))?;
+                        let value = i128::from_str_radix(hex, 16).ok()?;
+                        Self::try_from(if negative { -value } else { value }).ok()
+                    })
+                }
+            }
+        )*
+    };
+}
+
+impl_field_storage_int!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Writes a float so it reads back as json5.
+///
+/// `Display` writes infinity as `inf`, which json5 writes as `Infinity`.
+fn float_text(value: f64, text: String) -> String {
+    if value.is_infinite() {
+        if value > 0. {
+            "Infinity".to_string()
+        } else {
+            "-Infinity".to_string()
+        }
+    } else {
+        text
+    }
+}
+
+impl FieldStorage for f32 {
+    fn to_stored_value(&self) -> StoredValue {
+        StoredValue::Number(float_text((*self).into(), self.to_string()))
+    }
+
+    fn from_stored_value(value: StoredValue) -> Option<Self> {
+        value.as_text()?.parse().ok()
+    }
+}
+
+impl FieldStorage for f64 {
+    fn to_stored_value(&self) -> StoredValue {
+        StoredValue::Number(float_text(*self, self.to_string()))
+    }
+
+    fn from_stored_value(value: StoredValue) -> Option<Self> {
+        value.as_text()?.parse().ok()
+    }
+}
+
+/// Stored as an array of numbers, like `[1.0, 0.0]`.
+impl<const N: usize> FieldStorage for VecN<N> {
+    fn to_stored_value(&self) -> StoredValue {
+        StoredValue::Array(
+            self.data
+                .iter()
+                .map(|value| StoredValue::Number(float_text((*value).into(), format!("{value:?}"))))
+                .collect(),
+        )
+    }
+
+    fn from_stored_value(value: StoredValue) -> Option<Self> {
+        match value {
+            StoredValue::Array(values) if values.len() == N => {
+                let mut data = [0.; N];
+                for (value, stored) in data.iter_mut().zip(values) {
+                    *value = f32::from_stored_value(stored)?;
+                }
+                Some(Self::new(data))
+            }
+            // Older scenes could store vectors as strings.
+            StoredValue::String(string) => string.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+impl<T: FieldStorage> FieldStorage for Vec<T> {
+    fn to_stored_value(&self) -> StoredValue {
+        StoredValue::Array(self.iter().map(FieldStorage::to_stored_value).collect())
+    }
+
+    fn from_stored_value(value: StoredValue) -> Option<Self> {
+        match value {
+            StoredValue::Array(values) => values.into_iter().map(T::from_stored_value).collect(),
+            _ => None,
+        }
+    }
+}
+
+/// `None` is stored as `null`.
+impl<T: FieldStorage> FieldStorage for Option<T> {
+    fn to_stored_value(&self) -> StoredValue {
+        self.as_ref()
+            .map_or(StoredValue::Null, FieldStorage::to_stored_value)
+    }
+
+    fn from_stored_value(value: StoredValue) -> Option<Self> {
+        match value {
+            StoredValue::Null => Some(None),
+            value => T::from_stored_value(value).map(Some),
+        }
+    }
+}
+
+impl<T: FieldStorage> FieldStorage for HashMap<String, T> {
+    fn to_stored_value(&self) -> StoredValue {
+        StoredValue::Object(
+            self.iter()
+                .map(|(key, value)| (key.clone(), value.to_stored_value()))
+                .collect(),
+        )
+    }
+
+    fn from_stored_value(value: StoredValue) -> Option<Self> {
+        match value {
+            StoredValue::Object(members) => members
+                .into_iter()
+                .map(|(key, value)| Some((key, T::from_stored_value(value)?)))
+                .collect(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iridium_ecs_macros::FieldStorage;
+
+    use super::*;
+    use crate::storage::parse_json5;
+
+    #[derive(FieldStorage, Debug, PartialEq)]
+    struct Patrol {
+        waypoints: Vec<VecN<2>>,
+        target: Option<String>,
+        speeds: HashMap<String, f32>,
+    }
+
+    /// Writes the value as json5, and reads it back.
+    fn round_trip<T: FieldStorage>(value: &T) -> (String, Option<T>) {
+        let json5 = value.to_stored_value().to_json5().to_json5();
+        let read = T::from_stored_value(StoredValue::from_json5(&parse_json5(&json5).unwrap()));
+        (json5, read)
+    }
+
+    #[test]
+    fn primitives_keep_their_form() {
+        assert_eq!(round_trip(&VecN::new([1.0, 0.5, 0.0])).0, "[1.0, 0.5, 0.0]");
+        assert_eq!(round_trip(&0.002_f32).0, "0.002");
+        assert_eq!(round_trip(&0_f32).0, "0");
+        assert_eq!(round_trip(&true).0, "true");
+        assert_eq!(
+            round_trip(&f64::INFINITY),
+            ("Infinity".to_string(), Some(f64::INFINITY))
+        );
+        assert_eq!(round_trip(&u128::MAX).1, Some(u128::MAX));
+    }
+
+    #[test]
+    fn nested_fields_round_trip() {
+        let patrol = Patrol {
+            waypoints: vec![VecN::new([0., 1.]), VecN::new([2., 3.])],
+            target: None,
+            speeds: HashMap::from([("walk".to_string(), 1.), ("run".to_string(), 2.5)]),
+        };
+
+        let (json5, read) = round_trip(&patrol);
+        assert_eq!(
+            json5,
+            "{\n    speeds: {\n        run: 2.5,\n        walk: 1,\n    },\n    \
+             target: null,\n    waypoints: [\n        [0.0, 1.0],\n        [2.0, 3.0],\n    ],\n}"
+        );
+        assert_eq!(read, Some(patrol));
+
+        let target = Some("player".to_string());
+        assert_eq!(round_trip(&target).1, Some(target));
+    }
+
+    #[test]
+    fn reads_older_forms() {
+        let read = |src: &str| StoredValue::from_json5(&parse_json5(src).unwrap());
+
+        assert_eq!(
+            String::from_stored_value(read("ArrowUp")),
+            Some("ArrowUp".to_string())
+        );
+        assert_eq!(u32::from_stored_value(read("\"12\"")), Some(12));
+        assert_eq!(i32::from_stored_value(read("-0x10")), Some(-16));
+        assert_eq!(
+            VecN::<2>::from_stored_value(read("\"[1.0, 2.0]\"")),
+            Some(VecN::new([1., 2.]))
+        );
+        assert_eq!(VecN::<3>::from_stored_value(read("[1.0, 2.0]")), None);
+    }
+}
@@ -1,5 +1,16 @@
-use crate::{ComponentBox, Entities, World};
-use std::fmt::Write;
+use std::collections::HashMap;
+
+use super::{
+    write_binary_world, write_json5_key, Json5Kind, Json5Value, Prefab, SceneFormat,
+    SchemaVersions, SourcedComponent, StoredComponent, StoredEntities, StoredResources,
+    StoredValue, StoredWorld,
+};
+use crate::{Component, Entities, Entity, PrefabInstance, SceneOrigin, World};
+
+/// The version of the scene format written by `StorageWriter`.
+///
+/// Files without a version are from before it was added, and are read as version 0.
+pub const FORMAT_VERSION: u32 = 1;
 
 /// Manages the process of saving data to a file.
 pub struct StorageWriter {
@@ -18,55 +29,68 @@ impl StorageWriter {
         }
     }
 
-    /// Write a component to the file.
-    fn write_component(&mut self, component: &ComponentBox) {
-        let stored = component.get_trait().to_stored();
-
-        writeln!(&mut self.buffer, "            {}: {{", stored.type_name).unwrap();
-
-        for (key, value) in stored.fields {
-            write!(&mut self.buffer, "                {key}: ").unwrap();
-
-            if value.is_string {
-                write!(&mut self.buffer, "\"{}\"", value.string).unwrap();
-            } else {
-                self.buffer.push_str(&value.string);
-            }
-
-            self.buffer.push_str(",\n");
-        }
-
-        self.buffer.push_str("            },\n");
+    /// Write a member of the root object to the file.
+    fn write_member(&mut self, key: &str, value: &Json5Value) {
+        self.buffer.push_str("    ");
+        write_json5_key(&mut self.buffer, key);
+        self.buffer.push_str(": ");
+        value.write(&mut self.buffer, 1);
+        self.buffer.push_str(",\n");
     }
 
-    /// Write an entity to the file.
-    fn write_entity(&mut self, entities: &Entities, id: u128) {
-        writeln!(&mut self.buffer, "        \"{id}\": {{").unwrap();
-
-        for component in entities
-            .get_entity_components(id)
-            .expect("Entity did not exist")
-        {
-            self.write_component(component);
-        }
-
-        self.buffer.push_str("        },\n");
-    }
+    /// Write a stored world to the file,
+    /// like one read from a binary scene.
+    ///
+    /// Should be called in-between `begin` and `end`.
+    pub fn write_stored_world(&mut self, world: StoredWorld) {
+        let entities = world
+            .entities
+            .into_iter()
+            .map(|(id, _, components)| {
+                let components = components
+                    .into_iter()
+                    .map(|sourced| stored_value(sourced.stored))
+                    .collect();
+                (
+                    id.to_string(),
+                    Json5Value::new(Json5Kind::Object(components)),
+                )
+            })
+            .collect();
+        self.write_member("entities", &Json5Value::new(Json5Kind::Object(entities)));
 
-    /// Write the entities to the file.
-    pub fn write_entities(&mut self, entities: &Entities) {
-        self.buffer.push_str("    entities: {\n");
+        let resources = world
+            .resources
+            .into_iter()
+            .map(|sourced| stored_value(sourced.stored))
+            .collect();
+        self.write_member("resources", &Json5Value::new(Json5Kind::Object(resources)));
 
-        for id in entities.entity_ids() {
-            self.write_entity(entities, id);
+        let mut versions = world
+            .schema_versions
+            .into_iter()
+            .filter(|(_, version)| *version > 0)
+            .map(|(type_name, version)| {
+                (
+                    type_name,
+                    Json5Value::new(Json5Kind::Number(version.to_string())),
+                )
+            })
+            .collect::<Vec<_>>();
+        versions.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if !versions.is_empty() {
+            self.write_member(
+                "schema_versions",
+                &Json5Value::new(Json5Kind::Object(versions)),
+            );
         }
-
-        self.buffer.push_str("    },\n");
     }
 
-    /// Begins the json string.
+    /// Begins the json string, with the format version.
     pub fn begin(&mut self) {
         self.buffer.push_str("{\n");
+        self.buffer
+            .push_str(&format!("    format_version: {FORMAT_VERSION},\n"));
     }
 
     /// Ends the json string.
@@ -81,19 +105,191 @@ impl StorageWriter {
 
     /// Save the world to the file.
     ///
+    /// Prefab instances only store the fields they override,
+    /// and entities loaded from the file don't store their `SceneOrigin`.
+    ///
     /// Should be called in-between `begin` and `end`.
     pub fn save_world(&mut self, world: &World) {
-        self.write_entities(&world.entities);
+        let world = StoredWorld::from_world(world).without_origin(&self.dst_path);
+        self.write_stored_world(world);
+    }
+}
+
+/// A stored component or resource as a json5 object.
+fn stored_value(stored: StoredComponent) -> (String, Json5Value) {
+    (
+        stored.type_name,
+        StoredValue::Object(stored.fields).to_json5(),
+    )
+}
+
+/// The components of an entity as they are stored.
+///
+/// Components that aren't stored, like `Children`, are skipped.
+pub(crate) fn stored_components(entities: &Entities, entity: Entity) -> Vec<StoredComponent> {
+    entities
+        .get_entity_components(entity)
+        .expect("Entity did not exist")
+        .iter()
+        .map(|component| component.get_trait())
+        .filter(|component| component.is_stored())
+        .map(|component| component.to_stored())
+        .collect()
+}
+
+/// The schema versions of the given components and resources.
+///
+/// Only versions above 0 are kept, as that's what missing versions are read as.
+pub(crate) fn stored_schema_versions<'a>(
+    entities: &Entities,
+    components: impl IntoIterator<Item = &'a StoredComponent>,
+) -> SchemaVersions {
+    components
+        .into_iter()
+        .filter_map(|stored| {
+            let version = entities
+                .component_info_from_name(&stored.type_name)?
+                .schema_version;
+            (version > 0).then(|| (stored.type_name.clone(), version))
+        })
+        .collect()
+}
+
+/// The id of the prefab the components are an instance of, if any.
+pub(crate) fn prefab_id(components: &[StoredComponent]) -> Option<String> {
+    components
+        .iter()
+        .find(|stored| stored.type_name == PrefabInstance::type_name())?
+        .fields
+        .get("prefab")?
+        .as_text()
+        .map(ToString::to_string)
+}
+
+impl StoredWorld {
+    /// The world as it is stored.
+    #[must_use]
+    pub fn from_world(world: &World) -> Self {
+        Self::from_entities(world, &world.entities.all_entities())
+    }
+
+    /// Some of the world's entities as they are stored, with all its resources.
+    ///
+    /// Prefab instances only store the fields they override.
+    /// If their prefab can't be read, they're stored in full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an entity doesn't exist.
+    #[must_use]
+    pub fn from_entities(world: &World, entities: &[Entity]) -> Self {
+        let mut prefabs = HashMap::new();
+
+        let entities = entities
+            .iter()
+            .map(|&entity| {
+                let id = world
+                    .entities
+                    .persistent_id(entity)
+                    .expect("Entity did not exist");
+                let mut components = stored_components(&world.entities, entity);
+
+                if let Some(prefab_id) = prefab_id(&components) {
+                    let prefab = prefabs.entry(prefab_id).or_insert_with_key(|prefab_id| {
+                        Prefab::load(prefab_id, &world.entities, &world.migrations).ok()
+                    });
+                    if let Some(prefab) = prefab {
+                        components = prefab.overrides(components);
+                    }
+                }
+
+                (
+                    id,
+                    0,
+                    components.into_iter().map(SourcedComponent::new).collect(),
+                )
+            })
+            .collect::<StoredEntities>();
+
+        let resources = world
+            .resources
+            .all()
+            .into_iter()
+            .map(|resource| resource.get_trait())
+            .filter(|resource| resource.is_stored())
+            .map(|resource| SourcedComponent::new(resource.to_stored()))
+            .collect::<StoredResources>();
+
+        let schema_versions = stored_schema_versions(
+            &world.entities,
+            entities
+                .iter()
+                .flat_map(|(_, _, components)| components)
+                .chain(&resources)
+                .map(|sourced| &sourced.stored),
+        );
+
+        Self {
+            entities,
+            resources,
+            schema_versions,
+        }
+    }
+
+    /// Removes the `SceneOrigin`s naming the scene,
+    /// as they're implied by being saved to it.
+    #[must_use]
+    pub fn without_origin(mut self, scene: &str) -> Self {
+        for (_, _, components) in &mut self.entities {
+            components.retain(|sourced| {
+                sourced.stored.type_name != SceneOrigin::type_name()
+                    || sourced
+                        .stored
+                        .fields
+                        .get("scene")
+                        .and_then(StoredValue::as_text)
+                        != Some(scene)
+            });
+        }
+
+        self
+    }
+}
+
+/// Writes a stored world to a file, in the format chosen by its extension.
+pub fn save_stored_world_to_file(world: StoredWorld, file: &str) {
+    match SceneFormat::from_path(file) {
+        SceneFormat::Json5 => {
+            let mut writer = StorageWriter::new(file.to_string());
+
+            writer.begin();
+            writer.write_stored_world(world);
+            writer.end();
+
+            writer.write();
+        }
+        SceneFormat::Binary => {
+            std::fs::write(file, write_binary_world(&world)).expect("Failed to write file");
+        }
     }
 }
 
 /// A simple wrapper around `StorageWriter` to save the world to a file.
+///
+/// Files ending in `BINARY_SCENE_EXTENSION` are saved in the binary format instead.
+///
+/// Entities loaded from the file aren't saved with their `SceneOrigin`.
 pub fn save_world_to_file(world: &World, file: &str) {
-    let mut writer = StorageWriter::new(file.to_string());
-
-    writer.begin();
-    writer.save_world(world);
-    writer.end();
+    save_stored_world_to_file(StoredWorld::from_world(world).without_origin(file), file);
+}
 
-    writer.write();
+/// Saves the entities loaded from a scene back to its file, with all the resources.
+///
+/// Entities without a `SceneOrigin`, like those spawned at runtime, aren't saved.
+pub fn save_scene_to_file(world: &World, scene: &str) {
+    let entities = world.entities.scene_entities(scene);
+    save_stored_world_to_file(
+        StoredWorld::from_entities(world, &entities).without_origin(scene),
+        scene,
+    );
 }
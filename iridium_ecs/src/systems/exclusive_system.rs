@@ -0,0 +1,32 @@
+use iridium_assets::Assets;
+
+use super::SystemError;
+use crate::{ComponentBox, World};
+
+/// A system that gets the whole world,
+/// for work systems in a parallel stage can't do,
+/// like loading a scene, spawning in bulk or changing the systems.
+///
+/// It runs alone, between stages.
+/// Changes it makes to the stages take effect the next time the schedule runs.
+pub trait ExclusiveSystem: 'static + Send + Sync {
+    /// The name of the system.
+    fn name(&self) -> &'static str;
+    /// The default state of the system as a `Component`.
+    /// This is `None` if the system does not have state.
+    ///
+    /// The state is stored as a resource, which the system can get from the world.
+    fn default_state(&self) -> Option<ComponentBox>;
+    /// The function that runs every frame.
+    ///
+    /// # Errors
+    ///
+    /// The errors the system ran into.
+    /// `World::run_systems` handles them with the `ErrorPolicy`.
+    fn system(
+        &self,
+        world: &mut World,
+        assets: &Assets,
+        delta_time: f64,
+    ) -> Result<(), Vec<SystemError>>;
+}
@@ -0,0 +1,104 @@
+use iridium_ecs_macros::{Component, ComponentStorage, HasStableTypeId, InspectorUi};
+
+/// The timestep of the `FixedUpdate` schedule, stored as a resource.
+///
+/// Each frame, `World::run_systems` adds the delta time to an accumulator,
+/// then runs `FixedUpdate` once for every whole step in it,
+/// passing the step as the delta time.
+///
+/// The time left over is used for the interpolation alpha,
+/// so rendering can blend between the last two steps.
+#[derive(Component, InspectorUi, ComponentStorage, HasStableTypeId)]
+pub struct FixedTime {
+    /// The length of a step, in milliseconds.
+    #[drag_speed(0.1)]
+    pub step: f64,
+    /// The most steps run in a frame.
+    ///
+    /// If a frame would need more, the extra time is dropped,
+    /// so a slow frame doesn't make the next frames slower.
+    pub max_steps: u32,
+    /// The time that hasn't been stepped yet, in milliseconds.
+    #[hidden]
+    #[temporary(0.)]
+    accumulator: f64,
+}
+
+impl Default for FixedTime {
+    fn default() -> Self {
+        Self::new(1000. / 60.)
+    }
+}
+
+impl FixedTime {
+    /// Creates a fixed timestep with the given step, in milliseconds.
+    #[must_use]
+    pub const fn new(step: f64) -> Self {
+        Self {
+            step,
+            max_steps: 5,
+            accumulator: 0.,
+        }
+    }
+
+    /// Adds the frame's delta time, in milliseconds,
+    /// and returns how many steps to run.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn accumulate(&mut self, delta_time: f64) -> u32 {
+        if self.step <= 0. {
+            self.accumulator = 0.;
+            return 0;
+        }
+
+        self.accumulator += delta_time;
+
+        let steps = (self.accumulator / self.step).floor();
+        if steps > f64::from(self.max_steps) {
+            // Drop the time that won't be stepped.
+            self.accumulator %= self.step;
+            return self.max_steps;
+        }
+
+        self.accumulator -= steps * self.step;
+        // Checked against `max_steps` above, so it fits.
+        steps as u32
+    }
+
+    /// How far the time is between the last step and the next,
+    /// from 0 to 1.
+    ///
+    /// Rendering can use this to interpolate between the last two steps.
+    #[must_use]
+    pub fn alpha(&self) -> f64 {
+        if self.step <= 0. {
+            return 0.;
+        }
+
+        (self.accumulator / self.step).clamp(0., 1.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accumulates_steps() {
+        let mut fixed_time = FixedTime::new(10.);
+
+        assert_eq!(fixed_time.accumulate(4.), 0);
+        assert!((fixed_time.alpha() - 0.4).abs() < 1e-9);
+
+        assert_eq!(fixed_time.accumulate(17.), 2);
+        assert!((fixed_time.alpha() - 0.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn limits_steps() {
+        let mut fixed_time = FixedTime::new(10.);
+        fixed_time.max_steps = 3;
+
+        assert_eq!(fixed_time.accumulate(125.), 3);
+        assert!((fixed_time.alpha() - 0.5).abs() < 1e-9);
+    }
+}
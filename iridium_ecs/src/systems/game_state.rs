@@ -0,0 +1,88 @@
+use iridium_ecs_macros::{Component, ComponentStorage, HasStableTypeId, InspectorUi};
+
+/// The state of the game, like a menu, a level or paused, stored as a resource.
+///
+/// Changing state runs the `OnExit` schedule of the old state,
+/// then the `OnEnter` schedule of the new one.
+/// Changes are queued with `set`,
+/// and applied by `World::run_systems` at the start of the next frame.
+///
+/// Use `RunCondition::in_state` to only run a system in a state.
+#[derive(Component, InspectorUi, ComponentStorage, HasStableTypeId)]
+pub struct GameState {
+    /// The current state.
+    #[string]
+    current: String,
+    /// The states to change to, oldest first.
+    #[hidden]
+    #[temporary(Vec::new())]
+    queued: Vec<String>,
+}
+
+impl GameState {
+    /// Creates a game state in the given state.
+    #[must_use]
+    pub fn new(state: &str) -> Self {
+        Self {
+            current: state.to_string(),
+            queued: Vec::new(),
+        }
+    }
+
+    /// The current state.
+    #[must_use]
+    pub fn current(&self) -> &str {
+        &self.current
+    }
+
+    /// Checks if the game is in the given state.
+    #[must_use]
+    pub fn is_in(&self, state: &str) -> bool {
+        self.current == state
+    }
+
+    /// Queues a change to the given state.
+    pub fn set(&mut self, state: &str) {
+        self.queued.push(state.to_string());
+    }
+
+    /// The queued states, oldest first.
+    #[must_use]
+    pub fn queued(&self) -> &[String] {
+        &self.queued
+    }
+
+    /// Applies the next queued change that changes the state,
+    /// returning the old and new state.
+    pub(crate) fn advance(&mut self) -> Option<(String, String)> {
+        while !self.queued.is_empty() {
+            let next = self.queued.remove(0);
+            if next != self.current {
+                let previous = std::mem::replace(&mut self.current, next);
+                return Some((previous, self.current.clone()));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_changes_in_order() {
+        let mut state = GameState::new("Menu");
+        state.set("Menu");
+        state.set("Level");
+        state.set("Paused");
+        assert!(state.is_in("Menu"));
+
+        // Changing to the current state is skipped.
+        assert_eq!(state.advance(), Some(("Menu".into(), "Level".into())));
+        assert_eq!(state.advance(), Some(("Level".into(), "Paused".into())));
+        assert_eq!(state.advance(), None);
+        assert_eq!(state.current(), "Paused");
+    }
+}
@@ -1,4 +1,21 @@
+mod exclusive_system;
+pub use exclusive_system::*;
+mod fixed_time;
+pub use fixed_time::*;
+mod game_state;
+pub use game_state::*;
+mod run_condition;
+pub use run_condition::*;
+mod schedule;
+pub use schedule::*;
 mod system;
 pub use system::*;
+mod system_error;
+pub use system_error::*;
+mod system_stats;
+pub use system_stats::*;
+#[allow(clippy::module_inception)]
 mod systems;
 pub use systems::*;
+mod transform_propagation;
+pub use transform_propagation::*;
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use iridium_reflect::StableTypeId;
+
+use super::GameState;
+use crate::{Component, Resources, SystemTicks};
+
+/// A condition a system needs to run.
+///
+/// Add them with `SystemConfig::run_if`.
+/// A system only runs when all of its conditions are true.
+#[derive(Clone)]
+pub enum RunCondition {
+    /// Runs when the function returns true.
+    Custom(Arc<dyn Fn(&Resources) -> bool + Send + Sync>),
+    /// Runs when the resource with this type id changed since the system last ran.
+    ResourceChanged(StableTypeId),
+    /// Runs once every interval.
+    Every {
+        /// The time between runs, in milliseconds.
+        interval: f64,
+        /// The time since the last run, in milliseconds.
+        elapsed: f64,
+    },
+}
+
+impl RunCondition {
+    /// Runs when the function returns true.
+    pub fn custom(condition: impl Fn(&Resources) -> bool + Send + Sync + 'static) -> Self {
+        Self::Custom(Arc::new(condition))
+    }
+
+    /// Runs when the resource of type `T` equals `value`,
+    /// like only running in a given game state.
+    ///
+    /// Doesn't run if there's no resource of type `T`.
+    pub fn resource_equals<T: Component + PartialEq>(value: T) -> Self {
+        Self::custom(move |resources| {
+            resources
+                .try_get::<T>()
+                .is_some_and(|resource| *resource == value)
+        })
+    }
+
+    /// Runs when the `GameState` is in the given state.
+    ///
+    /// Doesn't run if there's no `GameState`.
+    #[must_use]
+    pub fn in_state(state: &str) -> Self {
+        let state = state.to_string();
+        Self::custom(move |resources| {
+            resources
+                .try_get::<GameState>()
+                .is_some_and(|game_state| game_state.is_in(&state))
+        })
+    }
+
+    /// Runs when the resource of type `T` changed since the system last ran.
+    #[must_use]
+    pub fn resource_changed<T: Component>() -> Self {
+        Self::ResourceChanged(T::stable_type_id())
+    }
+
+    /// Runs once every `seconds`.
+    #[must_use]
+    pub const fn every(seconds: f64) -> Self {
+        Self::Every {
+            interval: seconds * 1000.,
+            elapsed: 0.,
+        }
+    }
+
+    /// Checks the condition.
+    ///
+    /// `system_ticks` are the ticks of the system it's for,
+    /// and `delta_time` is the time since the condition was last checked, in milliseconds.
+    pub(crate) fn check(
+        &mut self,
+        resources: &Resources,
+        system_ticks: SystemTicks,
+        delta_time: f64,
+    ) -> bool {
+        match self {
+            Self::Custom(condition) => condition(resources),
+            Self::ResourceChanged(resource_type) => {
+                resources.is_changed_by_type_id(*resource_type, system_ticks)
+            }
+            Self::Every { interval, elapsed } => {
+                *elapsed += delta_time;
+                if *elapsed >= *interval {
+                    *elapsed -= *interval;
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for RunCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Custom(_) => f.write_str("Custom"),
+            Self::ResourceChanged(resource_type) => f
+                .debug_tuple("ResourceChanged")
+                .field(resource_type)
+                .finish(),
+            Self::Every { interval, elapsed } => f
+                .debug_struct("Every")
+                .field("interval", interval)
+                .field("elapsed", elapsed)
+                .finish(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iridium_ecs_macros::{Component, ComponentStorage, HasStableTypeId, InspectorUi};
+
+    use super::*;
+    use crate::ComponentBox;
+
+    #[derive(Component, InspectorUi, ComponentStorage, HasStableTypeId, PartialEq)]
+    struct Level {
+        number: u32,
+    }
+
+    const TICKS: SystemTicks = SystemTicks {
+        last_run: 0,
+        current: 1,
+    };
+
+    #[test]
+    fn every() {
+        let resources = Resources::new();
+        let mut condition = RunCondition::every(0.1);
+
+        assert!(!condition.check(&resources, TICKS, 60.));
+        assert!(condition.check(&resources, TICKS, 60.));
+        assert!(!condition.check(&resources, TICKS, 60.));
+        assert!(condition.check(&resources, TICKS, 60.));
+    }
+
+    #[test]
+    fn resource_equals() {
+        let mut resources = Resources::new();
+        let mut condition = RunCondition::resource_equals(Level { number: 2 });
+        assert!(!condition.check(&resources, TICKS, 0.));
+
+        resources.insert(ComponentBox::new(Level { number: 1 }));
+        assert!(!condition.check(&resources, TICKS, 0.));
+
+        resources.get_mut::<Level>().number = 2;
+        assert!(condition.check(&resources, TICKS, 0.));
+    }
+}
@@ -0,0 +1,598 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use iridium_reflect::StableTypeId;
+
+use super::RunCondition;
+use crate::{Resources, SystemTicks};
+
+/// When a system runs.
+///
+/// They're ordered in the order they run in.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ScheduleLabel {
+    /// Runs once, before the first frame.
+    Startup,
+    /// Runs when the `GameState` changes from this state,
+    /// and at shutdown.
+    OnExit(String),
+    /// Runs when the `GameState` changes to this state,
+    /// and at startup.
+    OnEnter(String),
+    /// Runs at a fixed rate, set by the `FixedTime` resource,
+    /// so it can run any number of times a frame.
+    ///
+    /// Runs before `Update`.
+    FixedUpdate,
+    /// Runs once a frame.
+    #[default]
+    Update,
+    /// Runs once, when play stops or the world is unloaded.
+    Shutdown,
+}
+
+/// The ordering constraints of a system, or of a set of systems.
+///
+/// Constraints refer to labels, which are either a system name or a set name.
+/// A constraint on a set applies to every system in it.
+///
+/// Constraints between systems in different schedules are ignored.
+#[derive(Default, Clone, Debug)]
+pub struct SystemConfig {
+    /// The schedule this runs in.
+    ///
+    /// Ignored for sets.
+    schedule: ScheduleLabel,
+    /// The labels this runs before.
+    before: Vec<String>,
+    /// The labels this runs after.
+    after: Vec<String>,
+    /// The sets this is in.
+    sets: Vec<String>,
+    /// The conditions this needs to run.
+    ///
+    /// Ignored for sets.
+    conditions: Vec<RunCondition>,
+}
+
+impl SystemConfig {
+    /// Runs this before the system or set with the given label.
+    pub fn before(&mut self, label: &str) -> &mut Self {
+        self.before.push(label.to_string());
+        self
+    }
+
+    /// Runs this after the system or set with the given label.
+    pub fn after(&mut self, label: &str) -> &mut Self {
+        self.after.push(label.to_string());
+        self
+    }
+
+    /// Adds this to a set, so it's ordered with the set.
+    pub fn in_set(&mut self, set: &str) -> &mut Self {
+        self.sets.push(set.to_string());
+        self
+    }
+
+    /// Runs this in the given schedule, instead of `Update`.
+    pub fn in_schedule(&mut self, schedule: ScheduleLabel) -> &mut Self {
+        self.schedule = schedule;
+        self
+    }
+
+    /// The schedule this runs in.
+    #[must_use]
+    pub const fn schedule(&self) -> &ScheduleLabel {
+        &self.schedule
+    }
+
+    /// Only runs this when the condition is true.
+    ///
+    /// Every condition is checked each time this could run,
+    /// so timers keep counting while other conditions are false.
+    pub fn run_if(&mut self, condition: RunCondition) -> &mut Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Checks every condition, returning whether they're all true.
+    pub(crate) fn check_conditions(
+        &mut self,
+        resources: &Resources,
+        system_ticks: SystemTicks,
+        delta_time: f64,
+    ) -> bool {
+        // Not short circuiting, so every condition is checked.
+        let mut all = true;
+        for condition in &mut self.conditions {
+            all &= condition.check(resources, system_ticks, delta_time);
+        }
+        all
+    }
+}
+
+/// An error that occurred when scheduling the systems.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    /// The ordering constraints form a cycle.
+    ///
+    /// Holds the systems in the cycle in order,
+    /// with the first system repeated at the end.
+    Cycle(Vec<String>),
+    /// A constraint refers to a label that isn't a system or a set.
+    UnknownLabel {
+        /// The system or set with the constraint.
+        owner: String,
+        /// The unknown label.
+        label: String,
+    },
+}
+
+impl std::fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cycle(systems) => {
+                write!(f, "system ordering cycle: {}", systems.join(" -> "))
+            }
+            Self::UnknownLabel { owner, label } => {
+                write!(
+                    f,
+                    "{owner} is ordered against {label}, which isn't a system or a set"
+                )
+            }
+        }
+    }
+}
+
+/// A system to be scheduled.
+pub(crate) struct ScheduleEntry<'a> {
+    /// The name of the system.
+    pub name: &'a str,
+    /// The ordering constraints of the system.
+    pub config: &'a SystemConfig,
+    /// The mutable and immutable components of the system,
+    /// from `System::required_components`.
+    pub access: [Vec<StableTypeId>; 2],
+    /// Whether it's an `ExclusiveSystem`, which conflicts with every system.
+    pub exclusive: bool,
+}
+
+/// Checks if two systems can't run at the same time,
+/// because one writes a component the other uses.
+fn conflicts(
+    [a_mut, a_immut]: &[Vec<StableTypeId>; 2],
+    [b_mut, b_immut]: &[Vec<StableTypeId>; 2],
+) -> bool {
+    a_mut
+        .iter()
+        .any(|type_id| b_mut.contains(type_id) || b_immut.contains(type_id))
+        || b_mut.iter().any(|type_id| a_immut.contains(type_id))
+}
+
+/// Splits the systems into stages for each schedule,
+/// where the systems in a stage can run in parallel.
+///
+/// The explicit constraints are followed first.
+/// Systems that conflict but aren't ordered explicitly
+/// run in the order they're given in.
+///
+/// `sets` holds the constraints of each set.
+///
+/// # Errors
+///
+/// If the constraints form a cycle, or refer to an unknown label.
+pub(crate) fn build_stages(
+    systems: &[ScheduleEntry],
+    sets: &HashMap<String, SystemConfig>,
+) -> Result<HashMap<ScheduleLabel, Vec<Vec<String>>>, ScheduleError> {
+    puffin::profile_function!();
+
+    let same_schedule =
+        |a: usize, b: usize| systems[a].config.schedule == systems[b].config.schedule;
+
+    // Every set a system is in, including the sets its sets are in.
+    let system_sets = systems
+        .iter()
+        .map(|system| {
+            let mut found = Vec::new();
+            let mut to_visit = system.config.sets.clone();
+            while let Some(set) = to_visit.pop() {
+                if !found.contains(&set) {
+                    if let Some(config) = sets.get(&set) {
+                        to_visit.extend(config.sets.iter().cloned());
+                    }
+                    found.push(set);
+                }
+            }
+            found
+        })
+        .collect::<Vec<_>>();
+
+    // label => indices of the systems it refers to.
+    let mut labels: HashMap<&str, Vec<usize>> =
+        sets.keys().map(|set| (set.as_str(), Vec::new())).collect();
+    for (index, system) in systems.iter().enumerate() {
+        labels.entry(system.name).or_default().push(index);
+        for set in &system_sets[index] {
+            labels.entry(set).or_default().push(index);
+        }
+    }
+
+    // The edges of the dependency graph, from the system that runs first.
+    let mut successors = vec![BTreeSet::new(); systems.len()];
+    let mut predecessors = vec![BTreeSet::new(); systems.len()];
+    for (index, system) in systems.iter().enumerate() {
+        // The system's own constraints, and those of its sets.
+        let configs = std::iter::once((system.name, system.config)).chain(
+            system_sets[index]
+                .iter()
+                .filter_map(|set| sets.get(set).map(|config| (set.as_str(), config))),
+        );
+
+        for (owner, config) in configs {
+            let constraints = config
+                .before
+                .iter()
+                .map(|label| (label, true))
+                .chain(config.after.iter().map(|label| (label, false)));
+
+            for (label, before) in constraints {
+                let Some(others) = labels.get(label.as_str()) else {
+                    return Err(ScheduleError::UnknownLabel {
+                        owner: owner.to_string(),
+                        label: label.clone(),
+                    });
+                };
+
+                for &other in others
+                    .iter()
+                    .filter(|&&other| other != index && same_schedule(index, other))
+                {
+                    let (first, second) = if before {
+                        (index, other)
+                    } else {
+                        (other, index)
+                    };
+                    successors[first].insert(second);
+                    predecessors[second].insert(first);
+                }
+            }
+        }
+    }
+
+    // Sort the systems topologically,
+    // picking the earliest given system when there's a choice.
+    let mut in_degrees = predecessors.iter().map(BTreeSet::len).collect::<Vec<_>>();
+    let mut ready = (0..systems.len())
+        .filter(|&index| in_degrees[index] == 0)
+        .collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(systems.len());
+    while let Some(index) = ready.pop_first() {
+        order.push(index);
+        for &next in &successors[index] {
+            in_degrees[next] -= 1;
+            if in_degrees[next] == 0 {
+                ready.insert(next);
+            }
+        }
+    }
+
+    if order.len() != systems.len() {
+        return Err(ScheduleError::Cycle(find_cycle(
+            systems,
+            &predecessors,
+            &in_degrees,
+        )));
+    }
+
+    // Put each system in the first stage after its predecessors,
+    // and after the systems before it that it conflicts with.
+    let mut system_stages = vec![0; systems.len()];
+    for (position, &index) in order.iter().enumerate() {
+        let after_predecessors = predecessors[index]
+            .iter()
+            .map(|&predecessor| system_stages[predecessor] + 1);
+        let after_conflicts = order[..position]
+            .iter()
+            .filter(|&&earlier| {
+                same_schedule(earlier, index)
+                    && (systems[earlier].exclusive
+                        || systems[index].exclusive
+                        || conflicts(&systems[earlier].access, &systems[index].access))
+            })
+            .map(|&earlier| system_stages[earlier] + 1);
+
+        system_stages[index] = after_predecessors.chain(after_conflicts).max().unwrap_or(0);
+    }
+
+    let mut schedules: HashMap<ScheduleLabel, Vec<Vec<String>>> = HashMap::new();
+    for index in order {
+        let stages = schedules
+            .entry(systems[index].config.schedule.clone())
+            .or_default();
+        if stages.len() <= system_stages[index] {
+            stages.resize(system_stages[index] + 1, Vec::new());
+        }
+        stages[system_stages[index]].push(systems[index].name.to_string());
+    }
+
+    Ok(schedules)
+}
+
+/// Finds a cycle in the systems that couldn't be sorted,
+/// which are those with predecessors left.
+fn find_cycle(
+    systems: &[ScheduleEntry],
+    predecessors: &[BTreeSet<usize>],
+    in_degrees: &[usize],
+) -> Vec<String> {
+    let unsorted = |index: &usize| in_degrees[*index] > 0;
+
+    // Every unsorted system has an unsorted predecessor,
+    // so walking backwards must loop.
+    let mut current = (0..systems.len())
+        .find(unsorted)
+        .expect("No unsorted systems in cycle");
+    let mut path = Vec::new();
+    let mut visited = HashSet::new();
+    while visited.insert(current) {
+        path.push(current);
+        current = *predecessors[current]
+            .iter()
+            .find(|index| unsorted(index))
+            .expect("Unsorted system without unsorted predecessor");
+    }
+
+    // Keep the loop, in the order the systems run in.
+    let start = path
+        .iter()
+        .position(|&index| index == current)
+        .expect("Cycle start not in path");
+    let mut cycle = path[start..]
+        .iter()
+        .rev()
+        .map(|&index| systems[index].name.to_string())
+        .collect::<Vec<_>>();
+    cycle.insert(0, systems[current].name.to_string());
+
+    cycle
+}
+
+#[cfg(test)]
+mod tests {
+    use iridium_ecs_macros::HasStableTypeId;
+    use iridium_reflect::HasStableTypeId;
+
+    use super::*;
+
+    #[derive(HasStableTypeId)]
+    struct Position;
+
+    #[derive(HasStableTypeId)]
+    struct Velocity;
+
+    fn entry<'a>(
+        name: &'a str,
+        config: &'a SystemConfig,
+        access: [Vec<StableTypeId>; 2],
+    ) -> ScheduleEntry<'a> {
+        ScheduleEntry {
+            name,
+            config,
+            access,
+            exclusive: false,
+        }
+    }
+
+    fn update_stages(
+        systems: &[ScheduleEntry],
+        sets: &HashMap<String, SystemConfig>,
+    ) -> Result<Vec<Vec<String>>, ScheduleError> {
+        build_stages(systems, sets)
+            .map(|mut schedules| schedules.remove(&ScheduleLabel::Update).unwrap_or_default())
+    }
+
+    fn schedule(systems: &[ScheduleEntry]) -> Result<Vec<Vec<String>>, ScheduleError> {
+        update_stages(systems, &HashMap::new())
+    }
+
+    #[test]
+    fn independent_systems_run_together() {
+        let config = SystemConfig::default();
+        let stages = schedule(&[
+            entry("A", &config, [vec![Position::stable_type_id()], vec![]]),
+            entry("B", &config, [vec![Velocity::stable_type_id()], vec![]]),
+            entry("C", &config, [vec![], vec![]]),
+        ]);
+
+        assert_eq!(stages, Ok(vec![vec!["A".into(), "B".into(), "C".into()]]));
+    }
+
+    #[test]
+    fn conflicts_run_in_order_given() {
+        let config = SystemConfig::default();
+        let stages = schedule(&[
+            entry(
+                "Move",
+                &config,
+                [
+                    vec![Position::stable_type_id()],
+                    vec![Velocity::stable_type_id()],
+                ],
+            ),
+            entry("Read", &config, [vec![], vec![Position::stable_type_id()]]),
+            entry(
+                "Accelerate",
+                &config,
+                [vec![Velocity::stable_type_id()], vec![]],
+            ),
+        ]);
+
+        assert_eq!(
+            stages,
+            Ok(vec![
+                vec!["Move".into()],
+                vec!["Read".into(), "Accelerate".into()]
+            ])
+        );
+    }
+
+    #[test]
+    fn explicit_order_wins() {
+        let mut config = SystemConfig::default();
+        config.after("Accelerate");
+        let none = SystemConfig::default();
+
+        let stages = schedule(&[
+            entry(
+                "Move",
+                &config,
+                [
+                    vec![Position::stable_type_id()],
+                    vec![Velocity::stable_type_id()],
+                ],
+            ),
+            entry(
+                "Accelerate",
+                &none,
+                [vec![Velocity::stable_type_id()], vec![]],
+            ),
+            entry("Other", &none, [vec![], vec![]]),
+        ]);
+
+        assert_eq!(
+            stages,
+            Ok(vec![
+                vec!["Accelerate".into(), "Other".into()],
+                vec!["Move".into()]
+            ])
+        );
+    }
+
+    #[test]
+    fn sets_order_their_systems() {
+        let mut physics = SystemConfig::default();
+        physics.in_set("Physics");
+        let mut render = SystemConfig::default();
+        render.in_set("Render");
+
+        let mut sets = HashMap::new();
+        sets.entry("Render".to_string())
+            .or_insert_with(SystemConfig::default)
+            .after("Physics");
+
+        let stages = update_stages(
+            &[
+                entry("Draw", &render, [vec![], vec![]]),
+                entry("Gravity", &physics, [vec![], vec![]]),
+                entry("Collide", &physics, [vec![], vec![]]),
+            ],
+            &sets,
+        );
+
+        assert_eq!(
+            stages,
+            Ok(vec![
+                vec!["Gravity".into(), "Collide".into()],
+                vec!["Draw".into()]
+            ])
+        );
+    }
+
+    #[test]
+    fn cycle_is_an_error() {
+        let mut a = SystemConfig::default();
+        a.before("B");
+        let mut b = SystemConfig::default();
+        b.before("C");
+        let mut c = SystemConfig::default();
+        c.before("A");
+        let none = SystemConfig::default();
+
+        let stages = schedule(&[
+            entry("Other", &none, [vec![], vec![]]),
+            entry("A", &a, [vec![], vec![]]),
+            entry("B", &b, [vec![], vec![]]),
+            entry("C", &c, [vec![], vec![]]),
+        ]);
+
+        assert_eq!(
+            stages,
+            Err(ScheduleError::Cycle(vec![
+                "A".into(),
+                "B".into(),
+                "C".into(),
+                "A".into()
+            ]))
+        );
+    }
+
+    #[test]
+    fn unknown_label_is_an_error() {
+        let mut config = SystemConfig::default();
+        config.after("Missing");
+
+        assert_eq!(
+            schedule(&[entry("A", &config, [vec![], vec![]])]),
+            Err(ScheduleError::UnknownLabel {
+                owner: "A".into(),
+                label: "Missing".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn schedules_are_separate() {
+        let mut fixed = SystemConfig::default();
+        fixed.in_schedule(ScheduleLabel::FixedUpdate);
+        let mut fixed_after = fixed.clone();
+        fixed_after.after("Update");
+        let mut update = SystemConfig::default();
+        update.after("Fixed");
+
+        let schedules = build_stages(
+            &[
+                entry("Fixed", &fixed, [vec![Position::stable_type_id()], vec![]]),
+                entry("FixedAfter", &fixed_after, [vec![], vec![]]),
+                entry(
+                    "Update",
+                    &update,
+                    [vec![Position::stable_type_id()], vec![]],
+                ),
+            ],
+            &HashMap::new(),
+        )
+        .expect("Failed to schedule");
+
+        // Conflicts and constraints between schedules are ignored.
+        assert_eq!(
+            schedules[&ScheduleLabel::FixedUpdate],
+            vec![vec!["Fixed".to_string(), "FixedAfter".to_string()]]
+        );
+        assert_eq!(
+            schedules[&ScheduleLabel::Update],
+            vec![vec!["Update".to_string()]]
+        );
+    }
+
+    #[test]
+    fn exclusive_systems_run_alone() {
+        let config = SystemConfig::default();
+        let mut spawn = entry("Spawn", &config, [vec![], vec![]]);
+        spawn.exclusive = true;
+
+        let stages = schedule(&[
+            entry("A", &config, [vec![Position::stable_type_id()], vec![]]),
+            spawn,
+            entry("B", &config, [vec![Velocity::stable_type_id()], vec![]]),
+        ]);
+
+        assert_eq!(
+            stages,
+            Ok(vec![
+                vec!["A".into()],
+                vec!["Spawn".into()],
+                vec!["B".into()]
+            ])
+        );
+    }
+}
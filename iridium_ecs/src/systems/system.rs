@@ -1,6 +1,7 @@
 use iridium_assets::Assets;
 
-use crate::{ComponentBox, Entities};
+use super::SystemError;
+use crate::{ComponentBox, Entities, Resources};
 use iridium_reflect::StableTypeId;
 
 /// A system is a function that runs every frame.
@@ -11,19 +12,32 @@ pub trait System: 'static + Send + Sync {
     fn state_type_id(&self) -> StableTypeId;
     /// The default state of the system as a `Component`.
     /// This is `None` if the system does not have state.
+    ///
+    /// The state is stored as a resource.
     fn default_state(&self) -> Option<ComponentBox>;
     /// The components that the system requires.
     ///
     /// This is used to determine which components to query for.
     ///
     /// The first is mutable, the second is immutable.
+    ///
+    /// Events are included as `Events<T>`,
+    /// mutable if the system sends them, immutable if it only reads them.
     fn required_components(&self) -> [Vec<StableTypeId>; 2];
     /// The function that runs every frame.
+    ///
+    /// `state` is the system's state resource, `None` if it doesn't have one.
+    ///
+    /// # Errors
+    ///
+    /// The errors the system ran into, one for each entity it failed on.
+    /// `World::run_systems` handles them with the `ErrorPolicy`.
     fn system(
         &self,
         state: Option<&ComponentBox>,
         entities: &Entities,
+        resources: &Resources,
         assets: &Assets,
         delta_time: f64,
-    );
+    ) -> Result<(), Vec<SystemError>>;
 }
@@ -0,0 +1,52 @@
+use crate::Entity;
+
+/// An error returned by a system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemError {
+    /// The name of the system that failed.
+    pub system: &'static str,
+    /// The entity the system failed on,
+    /// or `None` if it wasn't running for an entity.
+    pub entity: Option<Entity>,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl SystemError {
+    /// Creates an error for the given system and entity.
+    #[must_use]
+    pub fn new(system: &'static str, entity: Option<Entity>, message: impl Into<String>) -> Self {
+        Self {
+            system,
+            entity,
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for SystemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.entity {
+            Some(entity) => write!(
+                f,
+                "{} failed on entity {entity}: {}",
+                self.system, self.message
+            ),
+            None => write!(f, "{} failed: {}", self.system, self.message),
+        }
+    }
+}
+
+/// What `World::run_systems` does when a system returns errors.
+///
+/// The errors are returned either way, so they can be logged.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Keep running the system.
+    #[default]
+    Continue,
+    /// Disable the system, so it doesn't run until it's enabled again.
+    DisableSystem,
+    /// Stop running systems, until `Systems::resume` is called.
+    Halt,
+}
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+
+/// The number of samples the rolling averages are over.
+pub const STATS_WINDOW: usize = 60;
+
+/// A value recorded each run, like a duration,
+/// with a rolling average over the last `STATS_WINDOW` runs.
+#[derive(Clone, Debug, Default)]
+pub struct RollingStat {
+    /// The last samples, oldest first.
+    samples: VecDeque<f64>,
+    /// The sum of `samples`.
+    sum: f64,
+}
+
+impl RollingStat {
+    /// Records a sample, dropping the oldest if the window is full.
+    pub fn record(&mut self, value: f64) {
+        if self.samples.len() >= STATS_WINDOW {
+            if let Some(oldest) = self.samples.pop_front() {
+                self.sum -= oldest;
+            }
+        }
+
+        self.samples.push_back(value);
+        self.sum += value;
+    }
+
+    /// The last sample, or 0 if nothing was recorded.
+    #[must_use]
+    pub fn last(&self) -> f64 {
+        self.samples.back().copied().unwrap_or(0.)
+    }
+
+    /// The average of the samples in the window, or 0 if nothing was recorded.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn average(&self) -> f64 {
+        if self.samples.is_empty() {
+            0.
+        } else {
+            self.sum / self.samples.len() as f64
+        }
+    }
+
+    /// The largest sample in the window, or 0 if nothing was recorded.
+    #[must_use]
+    pub fn max(&self) -> f64 {
+        self.samples.iter().copied().fold(0., f64::max)
+    }
+}
+
+/// Stats about how a system ran, recorded by `World::run_systems`.
+#[derive(Clone, Debug, Default)]
+pub struct SystemStats {
+    /// How long each run took, in milliseconds.
+    pub time: RollingStat,
+    /// How many entities each run processed.
+    ///
+    /// Only counted for systems that report them,
+    /// like those made with `system_helper`.
+    pub entities: RollingStat,
+    /// How many times the system has run.
+    pub runs: u64,
+}
+
+impl SystemStats {
+    /// Records a run.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn record(&mut self, time: f64, entities: usize) {
+        self.time.record(time);
+        self.entities.record(entities as f64);
+        self.runs += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_average() {
+        let mut stat = RollingStat::default();
+        assert_eq!(stat.average(), 0.);
+
+        for value in 0..STATS_WINDOW + 10 {
+            stat.record(value as f64);
+        }
+
+        // Only the last `STATS_WINDOW` samples count.
+        assert_eq!(stat.last(), (STATS_WINDOW + 9) as f64);
+        assert_eq!(stat.max(), (STATS_WINDOW + 9) as f64);
+        assert!((stat.average() - (STATS_WINDOW as f64 - 1.) / 2. - 10.).abs() < 1e-9);
+    }
+}
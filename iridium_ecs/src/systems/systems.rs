@@ -1,20 +1,68 @@
 use iridium_assets::Assets;
 use rayon::prelude::*;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
 
-use super::System;
-use crate::{ComponentBox, Entities};
+use super::{
+    build_stages, ErrorPolicy, ExclusiveSystem, FixedTime, GameState, RollingStat, ScheduleEntry,
+    ScheduleError, ScheduleLabel, System, SystemConfig, SystemError, SystemStats,
+    TRANSFORM_PROPAGATION_SYSTEM,
+};
+use crate::{ComponentBox, SystemScope, SystemTicks, Transform, World};
 use iridium_reflect::{HasStableTypeId, StableTypeId};
 
+/// The age a system that hasn't run yet counts as having,
+/// so it sees almost every change.
+const NEVER_RUN_AGE: u32 = u32::MAX / 2;
+
 /// Stores the systems in the world.
+///
+/// The systems are split into stages automatically for each schedule,
+/// from the components they require and their ordering constraints.
+/// Systems in the same stage run in parallel.
 #[derive(Default)]
 pub struct Systems {
     /// The systems in the world.
     ///
     /// The key is the system name.
     systems: HashMap<String, Box<dyn System>>,
+    /// The exclusive systems in the world.
+    ///
+    /// The key is the system name.
+    /// They're shared so one can run while it has the world.
+    exclusive_systems: HashMap<String, Arc<dyn ExclusiveSystem>>,
+    /// The system names, in the order they were added.
+    order: Vec<String>,
+    /// The ordering constraints of each system.
+    configs: HashMap<String, SystemConfig>,
+    /// The ordering constraints of each set.
+    sets: HashMap<String, SystemConfig>,
+    /// The automatic stages of each schedule, or `None` if they need rebuilding.
+    schedules: Option<HashMap<ScheduleLabel, Vec<Vec<String>>>>,
+    /// system name => the change tick it last ran at.
+    last_run_ticks: HashMap<String, u32>,
+    /// The names of the disabled systems.
+    disabled: HashSet<String>,
+    /// Whether `Startup` has run since the last `Shutdown`.
+    started: bool,
+    /// Whether a system failed with `ErrorPolicy::Halt`.
+    halted: bool,
+    /// What to do when a system returns errors.
+    pub error_policy: ErrorPolicy,
+    /// system name => stats about how it ran.
+    system_stats: HashMap<String, SystemStats>,
+    /// How long each stage of each schedule took, in milliseconds.
+    stage_stats: HashMap<ScheduleLabel, Vec<RollingStat>>,
+    /// The last error building the schedules, so it's only reported once.
+    schedule_error: Option<ScheduleError>,
     /// The stages they should run in,
     /// identified by their name.
+    ///
+    /// If this isn't empty, it's used instead of the automatic stages of `Update`,
+    /// and the ordering constraints are ignored.
     pub stages: Vec<Vec<String>>,
 }
 
@@ -29,15 +77,201 @@ impl Systems {
     #[must_use]
     pub fn default_component_states(&self) -> Vec<ComponentBox> {
         self.systems
-            .iter()
-            .filter_map(|(_, system)| system.default_state())
+            .values()
+            .filter_map(|system| system.default_state())
+            .chain(
+                self.exclusive_systems
+                    .values()
+                    .filter_map(|system| system.default_state()),
+            )
             .collect()
     }
 
-    /// Add a system, this doesn't place it in a stage.
-    pub fn add_system(&mut self, system: impl System) {
-        self.systems
-            .insert(system.name().to_string(), Box::new(system));
+    /// Add a system, returning its ordering constraints.
+    ///
+    /// Systems that conflict but aren't ordered run in the order they're added.
+    ///
+    /// This doesn't place it in a manual stage.
+    pub fn add_system(&mut self, system: impl System) -> &mut SystemConfig {
+        let name = system.name().to_string();
+        self.exclusive_systems.remove(&name);
+        self.systems.insert(name.clone(), Box::new(system));
+        self.add_config(name)
+    }
+
+    /// Add an exclusive system, returning its ordering constraints.
+    ///
+    /// It's given a stage of its own, after the systems added before it,
+    /// and before the systems added after it.
+    pub fn add_exclusive_system(&mut self, system: impl ExclusiveSystem) -> &mut SystemConfig {
+        let name = system.name().to_string();
+        self.systems.remove(&name);
+        self.exclusive_systems
+            .insert(name.clone(), Arc::new(system));
+        self.add_config(name)
+    }
+
+    /// Adds a new system to the order, resetting its ordering constraints.
+    fn add_config(&mut self, name: String) -> &mut SystemConfig {
+        if !self.order.contains(&name) {
+            self.order.push(name.clone());
+        }
+        self.schedules = None;
+
+        let config = self.configs.entry(name).or_default();
+        *config = SystemConfig::default();
+        config
+    }
+
+    /// Checks if a system is exclusive.
+    #[must_use]
+    pub fn is_exclusive(&self, name: &str) -> bool {
+        self.exclusive_systems.contains_key(name)
+    }
+
+    /// Enables or disables a system.
+    ///
+    /// Disabled systems stay in their stage, but don't run.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) {
+        if enabled {
+            self.disabled.remove(name);
+        } else {
+            self.disabled.insert(name.to_string());
+        }
+    }
+
+    /// Checks if a system is enabled.
+    #[must_use]
+    pub fn is_enabled(&self, name: &str) -> bool {
+        !self.disabled.contains(name)
+    }
+
+    /// Checks if the systems were halted by a failing system,
+    /// with `ErrorPolicy::Halt`.
+    ///
+    /// Halted systems don't run until `resume` is called.
+    #[must_use]
+    pub const fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Lets the systems run again after they were halted.
+    pub fn resume(&mut self) {
+        self.halted = false;
+    }
+
+    /// Stats about how each system ran, keyed by the system name.
+    ///
+    /// This includes the transform propagation, as `TRANSFORM_PROPAGATION_SYSTEM`.
+    #[must_use]
+    pub const fn system_stats(&self) -> &HashMap<String, SystemStats> {
+        &self.system_stats
+    }
+
+    /// How long each stage of a schedule took, in milliseconds.
+    ///
+    /// Stages that haven't run yet aren't included.
+    #[must_use]
+    pub fn stage_stats(&self, schedule: &ScheduleLabel) -> &[RollingStat] {
+        self.stage_stats.get(schedule).map_or(&[], Vec::as_slice)
+    }
+
+    /// Clears the system and stage stats.
+    pub fn clear_stats(&mut self) {
+        self.system_stats.clear();
+        self.stage_stats.clear();
+    }
+
+    /// Gets the ordering constraints of a set of systems.
+    pub fn configure_set(&mut self, set: &str) -> &mut SystemConfig {
+        self.schedules = None;
+        self.sets.entry(set.to_string()).or_default()
+    }
+
+    /// Builds the automatic stages, if they aren't already built.
+    ///
+    /// This is done by `World::run_systems`,
+    /// but can be done earlier to check the constraints.
+    ///
+    /// # Errors
+    ///
+    /// If the ordering constraints form a cycle, or refer to an unknown label.
+    pub fn build_schedule(&mut self) -> Result<(), ScheduleError> {
+        if self.schedules.is_some() {
+            return Ok(());
+        }
+
+        let entries = self
+            .order
+            .iter()
+            .map(|name| ScheduleEntry {
+                name,
+                config: &self.configs[name],
+                access: self
+                    .systems
+                    .get(name)
+                    .map_or_else(Default::default, |system| system.required_components()),
+                exclusive: self.is_exclusive(name),
+            })
+            .collect::<Vec<_>>();
+        self.schedules = Some(build_stages(&entries, &self.sets)?);
+
+        Ok(())
+    }
+
+    /// Builds the automatic stages like `build_schedule`,
+    /// but returns the error as a `SystemError` from `Scheduler`,
+    /// only the first time building fails with it.
+    ///
+    /// This lets it be logged with the other system errors, without repeating every frame.
+    /// It's called before each schedule runs, and can be called after adding the systems
+    /// to report a bad configuration when it's loaded.
+    pub fn check_schedule(&mut self) -> Option<SystemError> {
+        match self.build_schedule() {
+            Ok(()) => {
+                self.schedule_error = None;
+                None
+            }
+            Err(error) => {
+                let message = error.to_string();
+                let reported = self.schedule_error.replace(error);
+                (reported != self.schedule_error)
+                    .then(|| SystemError::new("Scheduler", None, message))
+            }
+        }
+    }
+
+    /// The stages the systems in a schedule run in.
+    ///
+    /// For `Update`, this is `stages` if it isn't empty.
+    /// Otherwise it's the automatic stages,
+    /// which are empty if they haven't been built.
+    #[must_use]
+    pub fn active_stages(&self, schedule: &ScheduleLabel) -> &[Vec<String>] {
+        if *schedule == ScheduleLabel::Update && !self.stages.is_empty() {
+            return &self.stages;
+        }
+
+        self.schedules
+            .as_ref()
+            .and_then(|schedules| schedules.get(schedule))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// The schedules with systems in them, and `Update`,
+    /// in the order they run in.
+    #[must_use]
+    pub fn schedule_labels(&self) -> Vec<ScheduleLabel> {
+        let mut labels = self
+            .schedules
+            .iter()
+            .flat_map(HashMap::keys)
+            .chain([&ScheduleLabel::Update])
+            .cloned()
+            .collect::<Vec<_>>();
+        labels.sort();
+        labels.dedup();
+        labels
     }
 
     /// Gets a system by name.
@@ -119,31 +353,346 @@ impl Systems {
         true
     }
 
-    /// Executes the systems.
-    pub fn run_systems(&mut self, entities: &mut Entities, delta_time: f64, assets: &Assets) {
+    /// Runs the systems for a frame, see `World::run_systems`.
+    pub(crate) fn run_systems(
+        world: &mut World,
+        delta_time: f64,
+        assets: &Assets,
+    ) -> Vec<SystemError> {
+        if world.systems.halted {
+            return Vec::new();
+        }
+
+        let mut errors = Vec::new();
+
+        if !world.systems.started {
+            errors.extend(Self::run_startup(world, assets));
+        }
+
+        // Change the game state.
+        // It's only borrowed mutably if there are changes, so it's only marked changed then.
+        while world
+            .resources
+            .try_get::<GameState>()
+            .is_some_and(|game_state| !game_state.queued().is_empty())
+        {
+            let Some((previous, next)) = world.resources.get_mut::<GameState>().advance() else {
+                break;
+            };
+
+            errors.extend(Self::run_schedule(
+                world,
+                &ScheduleLabel::OnExit(previous),
+                0.,
+                assets,
+            ));
+            errors.extend(Self::run_schedule(
+                world,
+                &ScheduleLabel::OnEnter(next),
+                0.,
+                assets,
+            ));
+        }
+
+        // Work out how many fixed steps fit in this frame.
+        let (fixed_steps, step) = world
+            .resources
+            .try_get_mut::<FixedTime>()
+            .map_or((0, 0.), |mut fixed_time| {
+                (fixed_time.accumulate(delta_time), fixed_time.step)
+            });
+
+        for _ in 0..fixed_steps {
+            // Remember where entities were, so rendering can interpolate across the step.
+            world.entities.start_fixed_step();
+            errors.extend(Self::run_schedule(
+                world,
+                &ScheduleLabel::FixedUpdate,
+                step,
+                assets,
+            ));
+        }
+        errors.extend(Self::run_schedule(
+            world,
+            &ScheduleLabel::Update,
+            delta_time,
+            assets,
+        ));
+
+        // Drop the events sent last frame.
+        world.resources.update_events();
+
+        errors
+    }
+
+    /// Runs the startup schedules, see `World::run_startup`.
+    pub(crate) fn run_startup(world: &mut World, assets: &Assets) -> Vec<SystemError> {
+        world.systems.started = true;
+
+        let mut errors = Self::run_schedule(world, &ScheduleLabel::Startup, 0., assets);
+
+        let state = world
+            .resources
+            .try_get::<GameState>()
+            .map(|game_state| game_state.current().to_string());
+        if let Some(state) = state {
+            errors.extend(Self::run_schedule(
+                world,
+                &ScheduleLabel::OnEnter(state),
+                0.,
+                assets,
+            ));
+        }
+
+        errors
+    }
+
+    /// Runs the shutdown schedules, see `World::run_shutdown`.
+    pub(crate) fn run_shutdown(world: &mut World, assets: &Assets) -> Vec<SystemError> {
+        if !world.systems.started {
+            return Vec::new();
+        }
+        world.systems.started = false;
+        world.systems.halted = false;
+
+        let mut errors = Vec::new();
+
+        let state = world
+            .resources
+            .try_get::<GameState>()
+            .map(|game_state| game_state.current().to_string());
+        if let Some(state) = state {
+            errors.extend(Self::run_schedule(
+                world,
+                &ScheduleLabel::OnExit(state),
+                0.,
+                assets,
+            ));
+        }
+
+        errors.extend(Self::run_schedule(
+            world,
+            &ScheduleLabel::Shutdown,
+            0.,
+            assets,
+        ));
+
+        errors
+    }
+
+    /// Runs the stages of a schedule once,
+    /// updating the global transforms after stages that move entities and at the end.
+    ///
+    /// In each stage, the systems run in parallel,
+    /// then the exclusive systems run one at a time.
+    ///
+    /// Returns the errors the systems returned,
+    /// and stops after the stage a system failed in if the policy is to halt.
+    /// Nothing runs if the systems can't be scheduled.
+    fn run_schedule(
+        world: &mut World,
+        schedule: &ScheduleLabel,
+        delta_time: f64,
+        assets: &Assets,
+    ) -> Vec<SystemError> {
+        if world.systems.halted {
+            return Vec::new();
+        }
+
+        // Constraints can change while running, such as in the editor,
+        // so a bad one skips the schedule instead of stopping the game.
+        if let Some(error) = world.systems.check_schedule() {
+            return vec![error];
+        }
+        if world.systems.schedules.is_none() {
+            return Vec::new();
+        }
+
+        // Copied, as exclusive systems can change the systems while they run.
+        // Changes to the stages take effect the next time the schedule runs.
+        let stages = world.systems.active_stages(schedule).to_vec();
+        let mut errors = Vec::new();
+
         // Run each stage, not in parallel.
-        self.stages.iter().for_each(|stage| {
-            // Run each system in the stage in parallel.
-            stage.par_iter().for_each(|name| {
-                // Get the system.
-                let system = self.systems.get(name).expect("System in stage not found");
-
-                // Get the type id of the system state.
-                let state_type_id = system.state_type_id();
-                // Get the system state component.
-                let state = if state_type_id == <()>::stable_type_id() {
-                    None
-                } else {
-                    Some(entities.get_by_type_id(state_type_id))
+        for (index, stage) in stages.iter().enumerate() {
+            let World {
+                entities,
+                resources,
+                systems,
+                ..
+            } = &mut *world;
+
+            let current = entities.change_tick();
+            // Resources changed in this stage are marked with the same tick as components.
+            resources.set_change_tick(current);
+
+            let system_ticks = |name: &str| SystemTicks {
+                last_run: systems
+                    .last_run_ticks
+                    .get(name)
+                    .copied()
+                    .unwrap_or_else(|| current.wrapping_sub(NEVER_RUN_AGE)),
+                current,
+            };
+
+            // Only the enabled systems with all their conditions true run.
+            let running = stage
+                .iter()
+                .filter(|name| {
+                    !systems.disabled.contains(*name)
+                        && systems.configs.get_mut(*name).is_none_or(|config| {
+                            config.check_conditions(resources, system_ticks(name), delta_time)
+                        })
+                })
+                .collect::<Vec<_>>();
+
+            // Each system only sees changes since it last ran.
+            let (exclusive, parallel): (Vec<_>, Vec<_>) = running
+                .into_iter()
+                .map(|name| (name, system_ticks(name).last_run))
+                .partition(|(name, _)| systems.exclusive_systems.contains_key(*name));
+
+            // Later stages can read the global transforms,
+            // so they're updated after stages that move entities.
+            // Exclusive systems can do anything, so they're assumed to.
+            let moves_entities = !exclusive.is_empty()
+                || parallel.iter().any(|(name, _)| {
+                    systems.systems.get(*name).is_some_and(|system| {
+                        system.required_components()[0].contains(&Transform::stable_type_id())
+                    })
+                });
+
+            // Run each system in the stage in parallel, timing them.
+            let stage_start = Instant::now();
+            let entities = &*entities;
+            let mut runs = parallel
+                .par_iter()
+                .filter_map(|&(name, last_run)| {
+                    // Get the system, which an exclusive system could've replaced.
+                    let system = systems.systems.get(name)?;
+
+                    // Get the type id of the system state.
+                    let state_type_id = system.state_type_id();
+                    // Get the system state resource.
+                    let state =
+                        if state_type_id == <()>::stable_type_id() {
+                            None
+                        } else {
+                            Some(resources.get_by_type_id(state_type_id).unwrap_or_else(|| {
+                                panic!("State of {name} not found in resources")
+                            }))
+                        };
+
+                    // Run the system, marking it as running so borrow conflicts can name it,
+                    // and so it can count the entities it processes.
+                    let scope =
+                        SystemScope::enter(system.name()).with_last_run_tick(Some(last_run));
+                    let start = Instant::now();
+                    let result = system.system(state, entities, resources, assets, delta_time);
+                    let time = start.elapsed().as_secs_f64() * 1000.;
+
+                    Some((name, time, scope.processed_entities(), result))
+                })
+                .collect::<Vec<_>>();
+
+            // Run the exclusive systems alone, with the whole world.
+            for (name, last_run) in exclusive {
+                let Some(system) = world.systems.exclusive_systems.get(name).cloned() else {
+                    continue;
                 };
 
-                // Run the system.
-                system.system(state, entities, assets, delta_time);
-            });
+                let scope = SystemScope::enter(system.name()).with_last_run_tick(Some(last_run));
+                let start = Instant::now();
+                let result = system.system(world, assets, delta_time);
+                let time = start.elapsed().as_secs_f64() * 1000.;
+
+                runs.push((name, time, scope.processed_entities(), result));
+            }
+
+            let systems = &mut world.systems;
+
+            // Record the stage time, adding stats for stages that haven't run yet.
+            let stage_stats = systems.stage_stats.entry(schedule.clone()).or_default();
+            if stage_stats.len() <= index {
+                stage_stats.resize_with(index + 1, RollingStat::default);
+            }
+            stage_stats[index].record(stage_start.elapsed().as_secs_f64() * 1000.);
+
+            let mut stage_errors = Vec::new();
+            for (name, time, processed, result) in runs {
+                systems.last_run_ticks.insert(name.clone(), current);
+                systems
+                    .system_stats
+                    .entry(name.clone())
+                    .or_default()
+                    .record(time, processed);
+                if let Err(system_errors) = result {
+                    stage_errors.extend(system_errors);
+                }
+            }
+
+            if !stage_errors.is_empty() {
+                match systems.error_policy {
+                    ErrorPolicy::Continue => {}
+                    ErrorPolicy::DisableSystem => {
+                        for error in &stage_errors {
+                            systems.disabled.insert(error.system.to_string());
+                        }
+                    }
+                    ErrorPolicy::Halt => systems.halted = true,
+                }
+                errors.extend(stage_errors);
+            }
+
+            // Anything changed after this is seen by every stage.
+            world.entities.advance_tick();
 
             // Process the commands.
-            entities.process_commands();
-        });
+            world.entities.process_commands();
+
+            if world.systems.halted {
+                break;
+            }
+
+            if moves_entities {
+                Self::propagate_transforms(world, schedule);
+            }
+        }
+
+        // Update the global transforms for rendering,
+        // after any commands that added or moved entities.
+        Self::propagate_transforms(world, schedule);
+
+        // Drop the stats of stages that no longer exist.
+        let stage_count = world.systems.active_stages(schedule).len();
+        if let Some(stage_stats) = world.systems.stage_stats.get_mut(schedule) {
+            stage_stats.truncate(stage_count);
+        }
+
+        errors
+    }
+
+    /// Updates the global transforms after running a schedule's systems, timing it.
+    ///
+    /// In `FixedUpdate`, moved entities keep where they were before the step,
+    /// so rendering can interpolate them.
+    fn propagate_transforms(world: &mut World, schedule: &ScheduleLabel) {
+        let scope = SystemScope::enter(TRANSFORM_PROPAGATION_SYSTEM);
+        let start = Instant::now();
+        if *schedule == ScheduleLabel::FixedUpdate {
+            world.entities.propagate_fixed_transforms();
+        } else {
+            world.entities.propagate_transforms();
+        }
+        world
+            .systems
+            .system_stats
+            .entry(TRANSFORM_PROPAGATION_SYSTEM.to_string())
+            .or_default()
+            .record(
+                start.elapsed().as_secs_f64() * 1000.,
+                scope.processed_entities(),
+            );
     }
 
     /// Find errors in a stage.
@@ -196,22 +745,28 @@ impl Systems {
         all_errors
     }
 
-    /// Find errors in all the stages.
+    /// Find errors in all the stages of a schedule.
     /// This will check mutability rules are followed.
+    ///
+    /// Access to `Events<T>` is checked like component access,
+    /// sending events is mutable and reading them is immutable.
+    ///
+    /// Names in manual stages that aren't systems are skipped,
+    /// as they're not run.
     #[must_use]
-    pub fn find_errors(&self) -> Vec<HashSet<StableTypeId>> {
+    pub fn find_errors(&self, schedule: &ScheduleLabel) -> Vec<HashSet<StableTypeId>> {
         puffin::profile_function!();
 
         // Get the inputs of each system.
-        self.stages
+        self.active_stages(schedule)
             .iter()
             .map(|stage| {
                 stage
                     .iter()
-                    .map(|name| {
-                        let system = self.systems.get(name).expect("System in stage not found");
-                        system.required_components()
-                    })
+                    // Exclusive systems run alone, so they can't conflict.
+                    .filter(|name| !self.is_exclusive(name))
+                    .filter_map(|name| self.systems.get(name))
+                    .map(|system| system.required_components())
                     .collect::<Vec<_>>()
             })
             // Find the errors in each stage.
@@ -222,9 +777,241 @@ impl Systems {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
     use iridium_ecs_macros::HasStableTypeId;
 
     use super::*;
+    use crate::{systems::RunCondition, Entities, Entity, GlobalTransform, Name, Resources};
+
+    /// Counts how many times it runs.
+    struct CountSystem {
+        name: &'static str,
+        runs: Arc<AtomicU32>,
+    }
+
+    impl System for CountSystem {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn state_type_id(&self) -> StableTypeId {
+            <()>::stable_type_id()
+        }
+
+        fn default_state(&self) -> Option<ComponentBox> {
+            None
+        }
+
+        fn required_components(&self) -> [Vec<StableTypeId>; 2] {
+            [vec![], vec![]]
+        }
+
+        fn system(
+            &self,
+            _state: Option<&ComponentBox>,
+            _entities: &Entities,
+            _resources: &Resources,
+            _assets: &Assets,
+            _delta_time: f64,
+        ) -> Result<(), Vec<SystemError>> {
+            self.runs.fetch_add(1, Ordering::Relaxed);
+            SystemScope::add_processed_entities(3);
+            Ok(())
+        }
+    }
+
+    /// Fails every time it runs.
+    struct FailSystem {
+        runs: Arc<AtomicU32>,
+    }
+
+    impl System for FailSystem {
+        fn name(&self) -> &'static str {
+            "FailSystem"
+        }
+
+        fn state_type_id(&self) -> StableTypeId {
+            <()>::stable_type_id()
+        }
+
+        fn default_state(&self) -> Option<ComponentBox> {
+            None
+        }
+
+        fn required_components(&self) -> [Vec<StableTypeId>; 2] {
+            [vec![], vec![]]
+        }
+
+        fn system(
+            &self,
+            _state: Option<&ComponentBox>,
+            _entities: &Entities,
+            _resources: &Resources,
+            _assets: &Assets,
+            _delta_time: f64,
+        ) -> Result<(), Vec<SystemError>> {
+            self.runs.fetch_add(1, Ordering::Relaxed);
+            Err(vec![SystemError::new(self.name(), None, "Failed")])
+        }
+    }
+
+    /// Disables `After` and adds a `Name`.
+    struct ToggleSystem;
+
+    impl ExclusiveSystem for ToggleSystem {
+        fn name(&self) -> &'static str {
+            "ToggleSystem"
+        }
+
+        fn default_state(&self) -> Option<ComponentBox> {
+            None
+        }
+
+        fn system(
+            &self,
+            world: &mut World,
+            _assets: &Assets,
+            _delta_time: f64,
+        ) -> Result<(), Vec<SystemError>> {
+            world.systems.set_enabled("After", false);
+            world.insert_resource(Name {
+                name: "Toggled".to_owned(),
+            });
+            Ok(())
+        }
+    }
+
+    /// Moves an entity along x by 1.
+    struct MoveSystem(Entity);
+
+    impl System for MoveSystem {
+        fn name(&self) -> &'static str {
+            "MoveSystem"
+        }
+
+        fn state_type_id(&self) -> StableTypeId {
+            <()>::stable_type_id()
+        }
+
+        fn default_state(&self) -> Option<ComponentBox> {
+            None
+        }
+
+        fn required_components(&self) -> [Vec<StableTypeId>; 2] {
+            [vec![Transform::stable_type_id()], vec![]]
+        }
+
+        fn system(
+            &self,
+            _state: Option<&ComponentBox>,
+            entities: &Entities,
+            _resources: &Resources,
+            _assets: &Assets,
+            _delta_time: f64,
+        ) -> Result<(), Vec<SystemError>> {
+            let mut transform = entities
+                .entity_component_mut(self.0, Transform::stable_type_id())
+                .expect("Entity has no transform");
+            *transform.get_mut::<Transform>().position.x_mut() += 1.;
+            Ok(())
+        }
+    }
+
+    /// Fails if an entity's global transform is behind its transform.
+    struct CheckGlobalSystem(Entity);
+
+    impl System for CheckGlobalSystem {
+        fn name(&self) -> &'static str {
+            "CheckGlobalSystem"
+        }
+
+        fn state_type_id(&self) -> StableTypeId {
+            <()>::stable_type_id()
+        }
+
+        fn default_state(&self) -> Option<ComponentBox> {
+            None
+        }
+
+        fn required_components(&self) -> [Vec<StableTypeId>; 2] {
+            [
+                vec![],
+                vec![
+                    Transform::stable_type_id(),
+                    GlobalTransform::stable_type_id(),
+                ],
+            ]
+        }
+
+        fn system(
+            &self,
+            _state: Option<&ComponentBox>,
+            entities: &Entities,
+            _resources: &Resources,
+            _assets: &Assets,
+            _delta_time: f64,
+        ) -> Result<(), Vec<SystemError>> {
+            let local = entities
+                .entity_component(self.0, Transform::stable_type_id())
+                .expect("Entity has no transform")
+                .get::<Transform>()
+                .position;
+            let global = entities
+                .entity_component(self.0, GlobalTransform::stable_type_id())
+                .expect("Entity has no global transform")
+                .get::<GlobalTransform>()
+                .position;
+
+            if local == global {
+                Ok(())
+            } else {
+                Err(vec![SystemError::new(self.name(), None, "Stale")])
+            }
+        }
+    }
+
+    /// Counts the times it sees an entity's transform changed.
+    struct ChangedSystem {
+        name: &'static str,
+        entity: Entity,
+        changes: Arc<AtomicU32>,
+    }
+
+    impl System for ChangedSystem {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn state_type_id(&self) -> StableTypeId {
+            <()>::stable_type_id()
+        }
+
+        fn default_state(&self) -> Option<ComponentBox> {
+            None
+        }
+
+        fn required_components(&self) -> [Vec<StableTypeId>; 2] {
+            [vec![], vec![Transform::stable_type_id()]]
+        }
+
+        fn system(
+            &self,
+            _state: Option<&ComponentBox>,
+            entities: &Entities,
+            _resources: &Resources,
+            _assets: &Assets,
+            _delta_time: f64,
+        ) -> Result<(), Vec<SystemError>> {
+            if entities.is_changed::<Transform>(self.entity) {
+                self.changes.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(())
+        }
+    }
 
     #[derive(HasStableTypeId)]
     struct Test1;
@@ -310,4 +1097,344 @@ mod tests {
             2
         );
     }
+
+    #[test]
+    fn changes_are_per_system() {
+        let mut entities = Entities::default();
+        let entity = entities.new_entity(None, "Moved", vec![Transform::default().into()]);
+        let often_changes = Arc::new(AtomicU32::new(0));
+        let rarely_changes = Arc::new(AtomicU32::new(0));
+
+        let mut systems = Systems::new();
+        systems.add_system(ChangedSystem {
+            name: "Often",
+            entity,
+            changes: Arc::clone(&often_changes),
+        });
+        systems.add_system(ChangedSystem {
+            name: "Rarely",
+            entity,
+            changes: Arc::clone(&rarely_changes),
+        });
+        systems.set_enabled("Rarely", false);
+
+        let mut world = World::new(entities, systems);
+        let assets = Assets::new();
+
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(world.systems.active_stages(&ScheduleLabel::Update).len(), 1);
+        assert_eq!(often_changes.load(Ordering::Relaxed), 1);
+
+        // A system in the same stage that hasn't run yet doesn't show old changes again.
+        world.systems.set_enabled("Rarely", true);
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(often_changes.load(Ordering::Relaxed), 1);
+        assert_eq!(rarely_changes.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn fixed_update_runs_per_step() {
+        let fixed_runs = Arc::new(AtomicU32::new(0));
+        let update_runs = Arc::new(AtomicU32::new(0));
+
+        let mut systems = Systems::new();
+        systems
+            .add_system(CountSystem {
+                name: "Fixed",
+                runs: Arc::clone(&fixed_runs),
+            })
+            .in_schedule(ScheduleLabel::FixedUpdate);
+        systems.add_system(CountSystem {
+            name: "Update",
+            runs: Arc::clone(&update_runs),
+        });
+
+        let mut world = World::new(Entities::default(), systems);
+        world.insert_resource(FixedTime::new(10.));
+        let assets = Assets::new();
+
+        assert!(world.run_systems(25., &assets).is_empty());
+        assert_eq!(fixed_runs.load(Ordering::Relaxed), 2);
+        assert_eq!(update_runs.load(Ordering::Relaxed), 1);
+
+        // The 5ms left over makes a step with this frame.
+        assert!(world.run_systems(5., &assets).is_empty());
+        assert_eq!(fixed_runs.load(Ordering::Relaxed), 3);
+        assert_eq!(update_runs.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn fixed_steps_propagate_and_interpolate() {
+        let mut entities = Entities::default();
+        let entity = entities.new_entity(None, "Moving", vec![Transform::default().into()]);
+
+        let mut systems = Systems::new();
+        systems
+            .add_system(MoveSystem(entity))
+            .in_schedule(ScheduleLabel::FixedUpdate);
+        systems
+            .add_system(CheckGlobalSystem(entity))
+            .in_schedule(ScheduleLabel::FixedUpdate)
+            .after("MoveSystem");
+
+        let mut world = World::new(entities, systems);
+        world.insert_resource(FixedTime::new(10.));
+        let assets = Assets::new();
+
+        // Moved entities are propagated before the next stage reads them.
+        assert!(world.run_systems(25., &assets).is_empty());
+
+        // The last step moved it from 1 to 2, and the frame is half way to the next.
+        let global = *world
+            .entities
+            .entity_component(entity, GlobalTransform::stable_type_id())
+            .unwrap()
+            .get::<GlobalTransform>();
+        assert_eq!(global.position.x(), 2.);
+        let alpha = world.resource::<FixedTime>().alpha();
+        assert!((alpha - 0.5).abs() < 1e-9);
+        #[allow(clippy::cast_possible_truncation)]
+        let interpolated = global.interpolated(alpha as f32);
+        assert!((interpolated.position.x() - 1.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn disabled_systems_and_conditions() {
+        let a_runs = Arc::new(AtomicU32::new(0));
+        let b_runs = Arc::new(AtomicU32::new(0));
+
+        let mut systems = Systems::new();
+        systems.add_system(CountSystem {
+            name: "A",
+            runs: Arc::clone(&a_runs),
+        });
+        systems
+            .add_system(CountSystem {
+                name: "B",
+                runs: Arc::clone(&b_runs),
+            })
+            .run_if(RunCondition::custom(Resources::contains::<Name>));
+
+        let mut world = World::new(Entities::default(), systems);
+        let assets = Assets::new();
+
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(a_runs.load(Ordering::Relaxed), 1);
+        assert_eq!(b_runs.load(Ordering::Relaxed), 0);
+
+        world.systems.set_enabled("A", false);
+        world.insert_resource(Name {
+            name: "Name".to_owned(),
+        });
+
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(a_runs.load(Ordering::Relaxed), 1);
+        assert_eq!(b_runs.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn startup_state_changes_and_shutdown() {
+        let labels = [
+            ("Startup", ScheduleLabel::Startup),
+            ("EnterMenu", ScheduleLabel::OnEnter("Menu".into())),
+            ("ExitMenu", ScheduleLabel::OnExit("Menu".into())),
+            ("EnterLevel", ScheduleLabel::OnEnter("Level".into())),
+            ("ExitLevel", ScheduleLabel::OnExit("Level".into())),
+            ("Shutdown", ScheduleLabel::Shutdown),
+        ];
+
+        let mut systems = Systems::new();
+        let runs = labels.map(|(name, label)| {
+            let runs = Arc::new(AtomicU32::new(0));
+            systems
+                .add_system(CountSystem {
+                    name,
+                    runs: Arc::clone(&runs),
+                })
+                .in_schedule(label);
+            runs
+        });
+        let counts = || runs.each_ref().map(|runs| runs.load(Ordering::Relaxed));
+
+        let mut world = World::new(Entities::default(), systems);
+        world.insert_resource(GameState::new("Menu"));
+        let assets = Assets::new();
+
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(counts(), [1, 1, 0, 0, 0, 0]);
+
+        world.resource_mut::<GameState>().set("Level");
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(counts(), [1, 1, 1, 1, 0, 0]);
+
+        assert!(world.run_shutdown(&assets).is_empty());
+        assert!(world.run_shutdown(&assets).is_empty());
+        assert_eq!(counts(), [1, 1, 1, 1, 1, 1]);
+
+        // Playing again starts again.
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(counts(), [2, 1, 1, 2, 1, 1]);
+    }
+
+    #[test]
+    fn error_policies() {
+        let fail_runs = Arc::new(AtomicU32::new(0));
+        let later_runs = Arc::new(AtomicU32::new(0));
+
+        let mut systems = Systems::new();
+        systems.add_system(FailSystem {
+            runs: Arc::clone(&fail_runs),
+        });
+        systems
+            .add_system(CountSystem {
+                name: "Later",
+                runs: Arc::clone(&later_runs),
+            })
+            .after("FailSystem");
+
+        let mut world = World::new(Entities::default(), systems);
+        let assets = Assets::new();
+
+        // Continue keeps running everything.
+        let errors = world.run_systems(0., &assets);
+        assert_eq!(errors, vec![SystemError::new("FailSystem", None, "Failed")]);
+        assert_eq!(errors[0].to_string(), "FailSystem failed: Failed");
+        assert_eq!(later_runs.load(Ordering::Relaxed), 1);
+
+        // Halt stops the stages after it, until resumed.
+        world.systems.error_policy = ErrorPolicy::Halt;
+        assert_eq!(world.run_systems(0., &assets).len(), 1);
+        assert!(world.systems.is_halted());
+        assert_eq!(later_runs.load(Ordering::Relaxed), 1);
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(fail_runs.load(Ordering::Relaxed), 2);
+
+        // Disable stops the failing system.
+        world.systems.resume();
+        world.systems.error_policy = ErrorPolicy::DisableSystem;
+        assert_eq!(world.run_systems(0., &assets).len(), 1);
+        assert!(!world.systems.is_enabled("FailSystem"));
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(fail_runs.load(Ordering::Relaxed), 3);
+        assert_eq!(later_runs.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn schedule_errors_skip_schedules() {
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let mut systems = Systems::new();
+        systems
+            .add_system(CountSystem {
+                name: "A",
+                runs: Arc::clone(&runs),
+            })
+            .after("B");
+        systems
+            .add_system(CountSystem {
+                name: "B",
+                runs: Arc::clone(&runs),
+            })
+            .after("A");
+
+        let mut world = World::new(Entities::default(), systems);
+        let assets = Assets::new();
+
+        // The error is returned once, not every frame, and nothing runs.
+        let errors = world.run_systems(0., &assets);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "Scheduler failed: system ordering cycle: A -> B -> A"
+        );
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert!(world.systems.check_schedule().is_none());
+        assert_eq!(runs.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn unknown_systems_in_stages_are_skipped() {
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let mut systems = Systems::new();
+        systems.add_system(CountSystem {
+            name: "Known",
+            runs: Arc::clone(&runs),
+        });
+        systems.stages = vec![vec!["Known".to_string(), "Missing".to_string()]];
+
+        assert_eq!(
+            systems.find_errors(&ScheduleLabel::Update),
+            vec![HashSet::new()]
+        );
+
+        let mut world = World::new(Entities::default(), systems);
+        assert!(world.run_systems(0., &Assets::new()).is_empty());
+        assert_eq!(runs.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn records_stats() {
+        let runs = Arc::new(AtomicU32::new(0));
+
+        let mut systems = Systems::new();
+        systems.add_system(CountSystem {
+            name: "A",
+            runs: Arc::clone(&runs),
+        });
+
+        let mut world = World::new(Entities::default(), systems);
+        let assets = Assets::new();
+
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert!(world.run_systems(0., &assets).is_empty());
+
+        let stats = &world.systems.system_stats()["A"];
+        assert_eq!(stats.runs, 2);
+        assert_eq!(stats.entities.last(), 3.);
+        assert_eq!(world.systems.stage_stats(&ScheduleLabel::Update).len(), 1);
+        assert!(world
+            .systems
+            .stage_stats(&ScheduleLabel::FixedUpdate)
+            .is_empty());
+
+        world.systems.clear_stats();
+        assert!(world.systems.system_stats().is_empty());
+    }
+
+    #[test]
+    fn exclusive_systems() {
+        let before_runs = Arc::new(AtomicU32::new(0));
+        let after_runs = Arc::new(AtomicU32::new(0));
+
+        let mut systems = Systems::new();
+        systems.add_system(CountSystem {
+            name: "Before",
+            runs: Arc::clone(&before_runs),
+        });
+        systems.add_exclusive_system(ToggleSystem);
+        systems.add_system(CountSystem {
+            name: "After",
+            runs: Arc::clone(&after_runs),
+        });
+
+        let mut world = World::new(Entities::default(), systems);
+        let assets = Assets::new();
+
+        assert!(world.run_systems(0., &assets).is_empty());
+        assert_eq!(
+            world.systems.active_stages(&ScheduleLabel::Update),
+            [
+                vec!["Before".to_string()],
+                vec!["ToggleSystem".to_string()],
+                vec!["After".to_string()]
+            ]
+        );
+
+        // It ran between the stages, so its changes are seen by the stage after.
+        assert_eq!(before_runs.load(Ordering::Relaxed), 1);
+        assert_eq!(after_runs.load(Ordering::Relaxed), 0);
+        assert_eq!(world.resource::<Name>().name, "Toggled");
+    }
 }
@@ -0,0 +1,8 @@
+/// The name of the built-in transform propagation, in the system stats.
+///
+/// `World::run_systems` computes the `GlobalTransform` of every entity from the hierarchy
+/// after stages that move entities and at the end of each schedule,
+/// so it isn't a system that can be added to the stages.
+/// In fixed steps, it keeps where entities were before the step for interpolation,
+/// which a system couldn't know to do.
+pub const TRANSFORM_PROPAGATION_SYSTEM: &str = "TransformPropagationSystem";
@@ -1,7 +1,11 @@
 use crate::{
-    storage::{load_world_from_file, save_world_to_file, ReadError},
-    systems::Systems,
-    Entities,
+    storage::{
+        load_additive_from_file, load_additive_from_file_lenient, load_world_from_file,
+        load_world_from_file_lenient, save_scene_to_file, save_world_to_file, LoadDiagnostic,
+        Migrations, ReadError,
+    },
+    systems::{FixedTime, GameState, SystemError, Systems},
+    Component, Entities, Event, Events, Mut, Ref, Resources,
 };
 use iridium_assets::Assets;
 
@@ -9,41 +13,250 @@ use iridium_assets::Assets;
 ///
 /// The top level struct for the ECS.
 ///
-/// Stores all entities, components and systems.
+/// Stores all entities, components, resources and systems.
 pub struct World {
     /// The entities.
     ///
     /// This is separate to world to allow sharing references to entities without systems.
     pub entities: Entities,
+    /// The resources, such as system states.
+    pub resources: Resources,
     /// The systems.
     pub systems: Systems,
+    /// The migrations applied to components saved with an older schema version.
+    pub migrations: Migrations,
 }
 
 impl World {
     /// Creates a new world with the given entities and systems.
     ///
-    /// System state is automatically added here.
+    /// System state is automatically added to the resources here,
+    /// along with the default `FixedTime`.
+    /// `GameState` is registered, but not added.
     #[must_use]
-    pub fn new(mut entities: Entities, systems: Systems) -> Self {
-        // Add the system state to the world.
-        let system_state = entities.new_entity(None, "SystemState", vec![]);
+    pub fn new(entities: Entities, systems: Systems) -> Self {
+        let mut world = Self {
+            entities,
+            resources: Resources::new(),
+            systems,
+            migrations: Migrations::new(),
+        };
 
-        entities.add_components(system_state, systems.default_component_states());
+        world.entities.register_component::<FixedTime>();
+        world.entities.register_component::<GameState>();
+        world.insert_resource(FixedTime::default());
+        world.insert_default_states();
 
-        Self { entities, systems }
+        world
+    }
+
+    /// Adds the default state of every system that doesn't have its state yet.
+    ///
+    /// Should be called after adding systems with state.
+    pub fn insert_default_states(&mut self) {
+        for state in self.systems.default_component_states() {
+            if self
+                .resources
+                .get_by_type_id(state.stable_type_id())
+                .is_none()
+            {
+                self.resources.insert(state);
+            }
+        }
+    }
+
+    /// Adds a resource, replacing the existing one of the same type.
+    ///
+    /// The resource type should be registered as a component,
+    /// so it can be loaded.
+    pub fn insert_resource<T: Component>(&mut self, resource: T) {
+        self.resources.insert(resource.into());
+    }
+
+    /// Adds an `Events<T>` resource for sending events of type `T` between systems.
+    ///
+    /// `Events<T>` is also registered as a component,
+    /// so it can be used in `System::required_components`.
+    pub fn add_event<T: Event>(&mut self) {
+        self.entities.register_component::<Events<T>>();
+        self.resources.add_events::<T>();
+    }
+
+    /// Gets the resource of type `T`.
+    ///
+    /// # Panics
+    ///
+    /// If there is no resource of type `T`.
+    #[must_use]
+    pub fn resource<T: Component>(&self) -> Ref<'_, T> {
+        self.resources.get()
+    }
+
+    /// Gets the resource of type `T` mutably.
+    ///
+    /// # Panics
+    ///
+    /// If there is no resource of type `T`.
+    #[must_use]
+    pub fn resource_mut<T: Component>(&mut self) -> Mut<'_, T> {
+        self.resources.get_mut()
+    }
+
+    /// Gets the resource of type `T`, or `None` if there isn't one.
+    #[must_use]
+    pub fn try_resource<T: Component>(&self) -> Option<Ref<'_, T>> {
+        self.resources.try_get()
+    }
+
+    /// Removes the resource of type `T`, returning it if it existed.
+    pub fn remove_resource<T: Component>(&mut self) -> Option<T> {
+        self.resources.remove()
+    }
+
+    /// Runs the systems for a frame.
+    ///
+    /// Before the first frame, `run_startup` is called.
+    /// Then the queued `GameState` changes are applied,
+    /// running `OnExit` of the old state and `OnEnter` of the new one.
+    ///
+    /// `FixedUpdate` runs once for each step the `FixedTime` resource says fits,
+    /// with the step as the delta time, then `Update` runs once.
+    /// `FixedUpdate` doesn't run if there's no `FixedTime` resource.
+    ///
+    /// The change tick advances after each stage,
+    /// so systems see the changes made since they last ran.
+    ///
+    /// The global transforms are updated after stages that move entities and after each schedule,
+    /// then the events are updated, so they expire after two frames.
+    ///
+    /// Returns the errors the systems returned this frame,
+    /// after handling them with the `error_policy` of the systems.
+    /// Nothing runs while the systems are halted,
+    /// or while they can't be scheduled, which is returned once as an error.
+    ///
+    /// # Panics
+    ///
+    /// If a system's state isn't in the resources.
+    #[must_use]
+    pub fn run_systems(&mut self, delta_time: f64, assets: &Assets) -> Vec<SystemError> {
+        Systems::run_systems(self, delta_time, assets)
+    }
+
+    /// Runs `Startup`, then `OnEnter` of the current `GameState`.
+    ///
+    /// `run_systems` calls this before its first frame,
+    /// and again after `run_shutdown`.
+    ///
+    /// Returns the errors the systems returned.
+    ///
+    /// # Panics
+    ///
+    /// If a system's state isn't in the resources.
+    #[must_use]
+    pub fn run_startup(&mut self, assets: &Assets) -> Vec<SystemError> {
+        Systems::run_startup(self, assets)
+    }
+
+    /// Runs `OnExit` of the current `GameState`, then `Shutdown`,
+    /// if `Startup` has run since the last shutdown.
+    ///
+    /// Should be called when play stops or the world is unloaded.
+    /// This resumes the systems if they were halted, so they can shut down.
+    ///
+    /// Returns the errors the systems returned.
+    ///
+    /// # Panics
+    ///
+    /// If a system's state isn't in the resources.
+    #[must_use]
+    pub fn run_shutdown(&mut self, assets: &Assets) -> Vec<SystemError> {
+        Systems::run_shutdown(self, assets)
     }
 
     /// Saves the world's state to the given file.
+    ///
+    /// Files ending in `BINARY_SCENE_EXTENSION` are saved in the binary format,
+    /// the rest as json5.
     pub fn save(&self, file: &str) {
         save_world_to_file(self, file);
     }
 
+    /// Saves the entities loaded from a scene back to its file,
+    /// so scenes loaded together can be edited separately.
+    ///
+    /// All the resources are saved with it.
+    pub fn save_scene(&self, scene: &str) {
+        save_scene_to_file(self, scene);
+    }
+
     /// Loads the world's state from the given file.
     ///
+    /// The entities are replaced, and tagged with the file as their `SceneOrigin`.
+    ///
+    /// The format is chosen by the file's extension, like `save`.
+    ///
+    /// Components saved with an older schema version are upgraded with `migrations` first.
+    ///
     /// # Errors
     ///
-    /// Will return an error if the file cannot be read, or if the file is not a valid JSON5 file.
+    /// Will return an error if the file cannot be read, or if the file is not a valid scene.
     pub fn load(&mut self, file: &str, assets: &Assets) -> Result<(), ReadError> {
         load_world_from_file(file, self, assets)
     }
+
+    /// Loads the world's state from the given file,
+    /// skipping entities, components and resources that can't be read.
+    ///
+    /// Returns what was skipped and why, so it can be shown to the user.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the file cannot be read, or if the file is not a valid scene.
+    pub fn load_lenient(
+        &mut self,
+        file: &str,
+        assets: &Assets,
+    ) -> Result<Vec<LoadDiagnostic>, ReadError> {
+        load_world_from_file_lenient(file, self, assets)
+    }
+
+    /// Loads a scene on top of the ones already loaded, such as a HUD over a level.
+    ///
+    /// Its entities are tagged with the file as their `SceneOrigin`,
+    /// and replace the scene's entities if it was already loaded.
+    /// Resources the world already has are kept.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the file cannot be read, or if the file is not a valid scene.
+    pub fn load_additive(&mut self, file: &str, assets: &Assets) -> Result<(), ReadError> {
+        load_additive_from_file(file, self, assets)
+    }
+
+    /// Loads a scene on top of the ones already loaded,
+    /// skipping entities, components and resources that can't be read.
+    ///
+    /// Returns what was skipped and why.
+    ///
+    /// # Errors
+    ///
+    /// Will return an error if the file cannot be read, or if the file is not a valid scene.
+    pub fn load_additive_lenient(
+        &mut self,
+        file: &str,
+        assets: &Assets,
+    ) -> Result<Vec<LoadDiagnostic>, ReadError> {
+        load_additive_from_file_lenient(file, self, assets)
+    }
+
+    /// Deletes the entities loaded from a scene.
+    ///
+    /// Children of its entities that were loaded from other scenes are kept,
+    /// and become root entities.
+    /// The resources it loaded are kept.
+    ///
+    /// Returns how many entities were deleted.
+    pub fn unload_scene(&mut self, scene: &str) -> usize {
+        self.entities.unload_scene(scene)
+    }
 }
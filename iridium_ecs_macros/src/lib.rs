@@ -2,8 +2,6 @@
 
 mod system_helper;
 
-use std::hash::{Hash, Hasher};
-
 use proc_macro::TokenStream;
 use quote::quote;
 use syn::parse_macro_input;
@@ -18,6 +16,39 @@ fn get_ecs_crate() -> proc_macro2::TokenStream {
 
 /// This macro simplifies the creation of a system.
 ///
+/// The components can be `&T`, `&mut T`, `Option<&T>` or `Option<&mut T>`,
+/// and filtered with `With<T>`, `Without<T>`, `Changed<T>` and `Added<T>`.
+///
+/// ```ignore
+/// #[system_helper((), par_iter, &mut Transform, Option<&Velocity>, Without<Wall>)]
+/// ```
+///
+/// Extra parameters go after a `;`, and are passed to the function after the delta time.
+/// They can be named queries, resources, events and a commands buffer.
+/// Queries are passed as slices of the query's items.
+///
+/// ```ignore
+/// #[system_helper(
+///     (),
+///     iter,
+///     &Wall;
+///     balls: query(&mut Velocity, &GlobalTransform),
+///     input: res(&InputState),
+///     hits: events(&mut Hit),
+///     commands: commands
+/// )]
+/// ```
+///
+/// `events(&mut T)` sends events, `events(&T)` reads them as `&[&T]`.
+/// In `par_iter`, the parameters are shared between threads, so they can't be mutable.
+///
+/// With the `exclusive` mode, it implements `ExclusiveSystem` instead,
+/// and the function gets `&mut World`.
+///
+/// ```ignore
+/// #[system_helper(SpawnerState, exclusive)]
+/// ```
+///
 /// # Panics
 ///
 /// Will panic if the input is invalid.
@@ -36,11 +67,7 @@ pub fn derive_has_stable_type_id(tokens: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(tokens as syn::DeriveInput);
     let struct_name = &ast.ident;
 
-    let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    struct_name.to_string().hash(&mut hasher);
-    // hasher.write(struct_name.to_string().as_bytes());
-    // hasher.write_u8(0xFF);
-    let stable_type_id = hasher.finish();
+    let stable_type_id = iridium_reflect::stable_type_id_from_name(&struct_name.to_string());
 
     quote! {
         impl iridium_reflect::HasStableTypeId for #struct_name {
@@ -120,6 +147,9 @@ pub fn derive_component(tokens: TokenStream) -> TokenStream {
 
                 Ok(updated)
             }
+            fn empty_column(&self) -> Box<dyn #ecs_crate::ComponentColumn> {
+                Box::new(#ecs_crate::TypedColumn::<Self>::new())
+            }
         }
     }
     .to_string()
@@ -127,119 +157,252 @@ pub fn derive_component(tokens: TokenStream) -> TokenStream {
     .expect("Failed to parse derive macro output")
 }
 
-/// Derive macro generating an impl of the trait `ComponentStorage`.
-///
-/// For now this is quite limited, it only works for empty structs.
-///
-/// # Panics
+/// The type of a field as it's written, like `Vec<VecN<2>>`.
+fn type_string(ty: &syn::Type) -> String {
+    quote!(#ty)
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(" <", "<")
+        .replace("< ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+}
+
+/// Takes the field out of `stored`, and reads it with the expression,
+/// which reads a `StoredValue` called `value` to an `Option`.
 ///
-/// Panics if it's derived on something that isn't a struct,
-/// or a non-empty tuple struct.
-#[allow(clippy::too_many_lines)]
-#[proc_macro_derive(ComponentStorage, attributes(temporary, string))]
-pub fn derive_component_storage(tokens: TokenStream) -> TokenStream {
-    let ast = parse_macro_input!(tokens as syn::DeriveInput);
-    let struct_name = &ast.ident;
+/// Returns a `FieldError` naming the field and its type if it's missing,
+/// or if the expression returns `None`.
+fn read_field(
+    name: &syn::Ident,
+    ty: &syn::Type,
+    read: proc_macro2::TokenStream,
+    ecs_crate: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let expected = type_string(ty);
 
-    let ecs_crate = get_ecs_crate();
+    quote! {
+        #name: {
+            let value = stored.fields.remove(stringify!(#name)).ok_or_else(|| {
+                #ecs_crate::storage::FieldError::missing(stringify!(#name), #expected)
+            })?;
+            #read.ok_or_else(|| {
+                #ecs_crate::storage::FieldError::invalid(stringify!(#name), #expected)
+            })?
+        }
+    }
+}
 
-    let syn::Data::Struct(data) = ast.data else {
+/// The code storing and reading a struct's fields,
+/// shared by `ComponentStorage` and `FieldStorage`.
+///
+/// The first reads `Self` from a `StoredComponent` called `stored`,
+/// returning a `FieldError` if a field is missing or invalid.
+/// The second creates the `HashMap` of stored fields from `self`.
+///
+/// Fields with `#[temporary(value)]` aren't stored, and are set to the value when reading.
+/// Fields with `#[string]` are stored as strings using `Display` and `FromStr`,
+/// the rest use `FieldStorage`.
+fn stored_fields(
+    data: &syn::Data,
+    ecs_crate: &proc_macro2::TokenStream,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let syn::Data::Struct(data) = data else {
         panic!("ComponentStorage can only be derived on a struct")
     };
 
-    let (from_stored, to_stored) = {
-        match data.fields {
-            syn::Fields::Unit => (
-                quote! { Some(Self) },
-                quote! {
-                    #ecs_crate::storage::StoredComponent {
-                        type_name: stringify!(#struct_name).to_string(),
-                        fields: std::collections::HashMap::new(),
+    match &data.fields {
+        syn::Fields::Unit => (
+            quote! { Ok(Self) },
+            quote! { std::collections::HashMap::new() },
+        ),
+        syn::Fields::Named(fields) => {
+            let mut temporary_fields: Vec<(&syn::Ident, proc_macro2::TokenStream)> = Vec::new();
+            let mut read_fields = Vec::new();
+            let mut write_fields = Vec::new();
+
+            for field in &fields.named {
+                let Some(name) = field.ident.as_ref() else {
+                    continue;
+                };
+
+                if let Some(temp_attr) = field
+                    .attrs
+                    .iter()
+                    .find(|attr| attr.path.is_ident("temporary"))
+                {
+                    if let Ok(tokens) = temp_attr.parse_args() {
+                        temporary_fields.push((name, tokens));
                     }
-                },
-            ),
-            syn::Fields::Named(fields) => {
-                let mut temporary_fields: Vec<(&syn::Ident, proc_macro2::TokenStream)> = Vec::new();
+                } else if field.attrs.iter().any(|attr| attr.path.is_ident("string")) {
+                    let ty = &field.ty;
+                    read_fields.push(read_field(
+                        name,
+                        ty,
+                        quote! {
+                            <String as #ecs_crate::storage::FieldStorage>::from_stored_value(value)
+                                .and_then(|string| string.parse::<#ty>().ok())
+                        },
+                        ecs_crate,
+                    ));
+                    write_fields.push(quote! {
+                        map.insert(
+                            stringify!(#name).to_string(),
+                            #ecs_crate::storage::StoredValue::String(self.#name.to_string()),
+                        );
+                    });
+                } else {
+                    let ty = &field.ty;
+                    read_fields.push(read_field(
+                        name,
+                        ty,
+                        quote! { <#ty as #ecs_crate::storage::FieldStorage>::from_stored_value(value) },
+                        ecs_crate,
+                    ));
+                    write_fields.push(quote! {
+                        map.insert(
+                            stringify!(#name).to_string(),
+                            #ecs_crate::storage::FieldStorage::to_stored_value(&self.#name),
+                        );
+                    });
+                }
+            }
 
-                let (fields1, is_string): (Vec<&syn::Ident>, Vec<bool>) = fields
-                    .named
-                    .iter()
-                    .filter_map(|field| {
-                        if let Some(temp_attr) = field
-                            .attrs
-                            .iter()
-                            .find(|attr| attr.path.is_ident("temporary"))
-                        {
-                            temporary_fields
-                                .push((field.ident.as_ref()?, temp_attr.parse_args().ok()?));
-                            None
-                        } else {
-                            Some((
-                                field.ident.as_ref()?,
-                                field.attrs.iter().any(|attr| attr.path.is_ident("string")),
-                            ))
-                        }
+            let (temp_fields, temp_tokens): (Vec<&syn::Ident>, Vec<proc_macro2::TokenStream>) =
+                temporary_fields.into_iter().unzip();
+
+            (
+                quote! {
+                    Ok(Self {
+                        #(#read_fields,)*
+                        #(#temp_fields: #temp_tokens,)*
                     })
-                    .unzip();
-
-                let (temp_fields, temp_tokens): (Vec<&syn::Ident>, Vec<proc_macro2::TokenStream>) =
-                    temporary_fields.into_iter().unzip();
-
-                let fields2 = fields1.clone();
-                let fields3 = fields1.clone();
-                let fields4 = fields1.clone();
-
-                (
-                    quote! {
-                        Some(Self {
-                            #(#fields1: stored.get(stringify!(#fields2))?.parse().ok()?,)*
-                            #(#temp_fields: #temp_tokens,)*
-                        })
-                    },
-                    quote! {
-                        #ecs_crate::storage::StoredComponent {
-                            type_name: stringify!(#struct_name).to_string(),
-                            fields: {
-                                let mut map = std::collections::HashMap::new();
-                                #(map.insert(
-                                    stringify!(#fields3).to_string(),
-                                    #ecs_crate::storage::StoredComponentField::new(
-                                        self.#fields4.to_string(),
-                                        #is_string,
-                                    ),
-                                );)*
-                                map
-                            },
-                        }
-                    },
-                )
-            }
-            syn::Fields::Unnamed(fields) if fields.unnamed.is_empty() => (
-                quote! { Some(Self()) },
+                },
                 quote! {
-                    #ecs_crate::storage::StoredComponent {
-                        type_name: stringify!(#struct_name).to_string(),
-                        fields: std::collections::HashMap::new(),
+                    {
+                        let mut map = std::collections::HashMap::new();
+                        #(#write_fields)*
+                        map
                     }
                 },
-            ),
-            syn::Fields::Unnamed(_) => {
-                panic!("ComponentStorage cannot be derived on non-empty tuple struct")
-            }
+            )
         }
-    };
+        syn::Fields::Unnamed(fields) if fields.unnamed.is_empty() => (
+            quote! { Ok(Self()) },
+            quote! { std::collections::HashMap::new() },
+        ),
+        syn::Fields::Unnamed(_) => {
+            panic!("ComponentStorage cannot be derived on non-empty tuple struct")
+        }
+    }
+}
+
+/// Derive macro generating an impl of the trait `ComponentStorage`.
+///
+/// Fields are stored with `FieldStorage`, or as strings with `#[string]`,
+/// and fields with `#[temporary(value)]` aren't stored.
+///
+/// The schema version is set with `#[schema_version(1)]` on the struct.
+///
+/// # Panics
+///
+/// Panics if it's derived on something that isn't a struct,
+/// or a non-empty tuple struct,
+/// or if the schema version isn't an integer.
+#[proc_macro_derive(ComponentStorage, attributes(temporary, string, schema_version))]
+pub fn derive_component_storage(tokens: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(tokens as syn::DeriveInput);
+    let struct_name = &ast.ident;
+
+    let ecs_crate = get_ecs_crate();
+
+    let (from_stored, to_stored) = stored_fields(&ast.data, &ecs_crate);
+
+    let schema_version = ast
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("schema_version"))
+        .map(|attr| {
+            let version = attr
+                .parse_args::<syn::LitInt>()
+                .expect("`schema_version` should be an integer");
+            quote! {
+                fn schema_version() -> u32 {
+                    #version
+                }
+            }
+        });
 
     quote! {
         impl #ecs_crate::storage::ComponentStorage for #struct_name {
             fn from_stored(
+                stored: #ecs_crate::storage::StoredComponent,
+                assets: &iridium_assets::Assets,
+            ) -> Option<Self> {
+                Self::try_from_stored(stored, assets).ok()
+            }
+
+            #[allow(unused_mut)]
+            fn try_from_stored(
                 mut stored: #ecs_crate::storage::StoredComponent,
                 _assets: &iridium_assets::Assets,
-            ) -> Option<Self> {
+            ) -> Result<Self, #ecs_crate::storage::FieldError> {
                 #from_stored
             }
 
             fn to_stored(&self) -> #ecs_crate::storage::StoredComponent {
-                #to_stored
+                #ecs_crate::storage::StoredComponent {
+                    type_name: stringify!(#struct_name).to_string(),
+                    fields: #to_stored,
+                }
+            }
+
+            #schema_version
+        }
+    }
+    .to_string()
+    .parse()
+    .expect("Failed to parse derive macro output")
+}
+
+/// Derive macro generating an impl of the trait `FieldStorage`,
+/// so a struct can be stored as a field of a component.
+///
+/// It's stored as an object, with the same attributes as `ComponentStorage`.
+///
+/// # Panics
+///
+/// Panics if it's derived on something that isn't a struct,
+/// or a non-empty tuple struct.
+#[proc_macro_derive(FieldStorage, attributes(temporary, string))]
+pub fn derive_field_storage(tokens: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(tokens as syn::DeriveInput);
+    let struct_name = &ast.ident;
+
+    let ecs_crate = get_ecs_crate();
+
+    let (from_stored, to_stored) = stored_fields(&ast.data, &ecs_crate);
+
+    quote! {
+        impl #ecs_crate::storage::FieldStorage for #struct_name {
+            fn to_stored_value(&self) -> #ecs_crate::storage::StoredValue {
+                #ecs_crate::storage::StoredValue::Object(#to_stored)
+            }
+
+            fn from_stored_value(value: #ecs_crate::storage::StoredValue) -> Option<Self> {
+                let #ecs_crate::storage::StoredValue::Object(fields) = value else {
+                    return None;
+                };
+                #[allow(unused_mut, unused_variables)]
+                let mut stored = #ecs_crate::storage::StoredComponent {
+                    type_name: stringify!(#struct_name).to_string(),
+                    fields,
+                };
+                #[allow(unused_mut)]
+                let mut from_stored = || -> Result<Self, #ecs_crate::storage::FieldError> {
+                    #from_stored
+                };
+                from_stored().ok()
             }
         }
     }
@@ -5,6 +5,7 @@ use syn::Type;
 pub struct Input {
     state: Type,
     mode: Mode,
+    params: Vec<SystemParam>,
 }
 
 /// Parse the input to this macro.
@@ -20,7 +21,57 @@ impl syn::parse::Parse for Input {
         // Logic for this is below in the `Mode` enum.
         let mode = input.parse()?;
 
-        Ok(Self { state, mode })
+        // Parse the extra parameters, after a semicolon.
+        let params = if input.parse::<Option<syn::Token![;]>>()?.is_some() {
+            input
+                .parse_terminated::<_, syn::Token![,]>(SystemParam::parse)?
+                .into_iter()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Ok(Self {
+            state,
+            mode,
+            params,
+        })
+    }
+}
+
+/// How an input to a system is used in the query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputKind {
+    /// `&T` or `&mut T`, the entity must have the component.
+    Required,
+    /// `Option<&T>` or `Option<&mut T>`, the component is given if the entity has it.
+    Optional,
+    /// `With<T>`, the entity must have the component, but it isn't given.
+    With,
+    /// `Without<T>`, the entity must not have the component.
+    Without,
+    /// `Changed<T>`, the component must have changed since the system last ran.
+    Changed,
+    /// `Added<T>`, the component must have been added since the system last ran.
+    Added,
+}
+
+impl InputKind {
+    /// Gets the kind from the name of a wrapper type, like `Option` or `With`.
+    fn from_wrapper(name: &str) -> Option<Self> {
+        match name {
+            "Option" => Some(Self::Optional),
+            "With" => Some(Self::With),
+            "Without" => Some(Self::Without),
+            "Changed" => Some(Self::Changed),
+            "Added" => Some(Self::Added),
+            _ => None,
+        }
+    }
+
+    /// Whether the component is given to the system function.
+    const fn is_fetched(self) -> bool {
+        matches!(self, Self::Required | Self::Optional)
     }
 }
 
@@ -28,57 +79,175 @@ impl syn::parse::Parse for Input {
 struct SystemInput {
     ty: Type,
     mutable: bool,
+    kind: InputKind,
 }
 
 /// Output the input as a string.
 ///
-/// This will be "&mut X" or "&X" depending on whether the input is mutable.
+/// This will be "&mut X" or "&X" depending on whether the input is mutable,
+/// wrapped in the filter if there is one, like "Option<&X>" or "Without<X>".
 impl std::fmt::Debug for SystemInput {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "&{}{}",
-            if self.mutable { "mut " } else { "" },
-            quote::ToTokens::to_token_stream(&self.ty)
-        )
+        let ty = quote::ToTokens::to_token_stream(&self.ty);
+        let reference = if self.mutable { "&mut " } else { "&" };
+
+        match self.kind {
+            InputKind::Required => write!(f, "{reference}{ty}"),
+            InputKind::Optional => write!(f, "Option<{reference}{ty}>"),
+            kind => write!(f, "{kind:?}<{ty}>"),
+        }
     }
 }
 
 /// Parse this from a `ParseStream`.
 impl syn::parse::Parse for SystemInput {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        // Pass through the '&';
-        input.parse::<syn::Token![&]>()?;
+        // A plain reference is a required component.
+        if input.peek(syn::Token![&]) {
+            let Type::Reference(reference) = input.parse::<Type>()? else {
+                unreachable!("Type starting with '&' is a reference");
+            };
+
+            return Ok(Self {
+                ty: *reference.elem,
+                mutable: reference.mutability.is_some(),
+                kind: InputKind::Required,
+            });
+        }
 
-        // Check if mutable.
-        let mutable = if input.peek(syn::Token![mut]) {
-            input.parse::<syn::Token![mut]>()?;
-            true
-        } else {
-            false
+        // Otherwise it's a wrapper, like `Option<&T>` or `Without<T>`.
+        let wrapper: syn::TypePath = input.parse()?;
+        let segment = wrapper
+            .path
+            .segments
+            .last()
+            .ok_or_else(|| syn::Error::new_spanned(&wrapper, "Expected a type"))?;
+
+        let kind = InputKind::from_wrapper(&segment.ident.to_string()).ok_or_else(|| {
+            syn::Error::new_spanned(
+                &segment.ident,
+                "Expected `&T`, `&mut T`, `Option<&T>`, `With<T>`, `Without<T>`, `Changed<T>` or `Added<T>`",
+            )
+        })?;
+
+        // Get the type inside the wrapper.
+        let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+            return Err(syn::Error::new_spanned(
+                segment,
+                "Expected a generic argument",
+            ));
+        };
+        let Some(syn::GenericArgument::Type(inner)) = arguments.args.first() else {
+            return Err(syn::Error::new_spanned(
+                arguments,
+                "Expected a type argument",
+            ));
         };
 
-        // Parse the type.
-        let ty: syn::Type = input.parse()?;
+        match (kind, inner) {
+            // Optional components are references, like required ones.
+            (InputKind::Optional, Type::Reference(reference)) => Ok(Self {
+                ty: (*reference.elem).clone(),
+                mutable: reference.mutability.is_some(),
+                kind,
+            }),
+            (InputKind::Optional, _) => Err(syn::Error::new_spanned(
+                inner,
+                "Expected `Option<&T>` or `Option<&mut T>`",
+            )),
+            // Filters aren't given to the system, so can't be mutable.
+            _ => Ok(Self {
+                ty: inner.clone(),
+                mutable: false,
+                kind,
+            }),
+        }
+    }
+}
+
+/// An extra parameter given to the system function,
+/// after the delta time.
+enum SystemParam {
+    /// `name: query(&mut A, &B, Without<C>)`, another query.
+    ///
+    /// Given as `&mut [(Entity, Mut<A>, Ref<B>)]`, with the guards like `query!` returns,
+    /// or as `&[...]` if nothing in it is mutable.
+    Query {
+        name: syn::Ident,
+        inputs: Vec<SystemInput>,
+    },
+    /// `name: res(&T)` or `name: res(&mut T)`, a resource.
+    ///
+    /// Given as `&T` or `&mut T`.
+    Resource {
+        name: syn::Ident,
+        ty: Type,
+        mutable: bool,
+    },
+    /// `name: events(&mut T)`, for sending events.
+    ///
+    /// Given as `&mut Events<T>`.
+    EventWriter { name: syn::Ident, ty: Type },
+    /// `name: events(&T)`, for reading events.
+    ///
+    /// Given as `&[&T]`, the events the system hasn't read yet.
+    EventReader { name: syn::Ident, ty: Type },
+    /// `name: commands`, for changing entities at the end of the stage.
+    ///
+    /// Given as `Commands`.
+    Commands { name: syn::Ident },
+}
 
-        Ok(Self { ty, mutable })
+impl SystemParam {
+    /// The name of the parameter.
+    const fn name(&self) -> &syn::Ident {
+        match self {
+            Self::Query { name, .. }
+            | Self::Resource { name, .. }
+            | Self::EventWriter { name, .. }
+            | Self::EventReader { name, .. }
+            | Self::Commands { name } => name,
+        }
     }
 }
 
-/// Turn this into a `TokenStream` for use in `quote!`.
-impl quote::ToTokens for SystemInput {
-    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-        // Add the '&'.
-        tokens.extend(quote! { & });
+/// Parse this from a `ParseStream`.
+impl syn::parse::Parse for SystemParam {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let name = input.parse::<syn::Ident>()?;
+        input.parse::<syn::Token![:]>()?;
+        let kind = input.parse::<syn::Ident>()?;
+
+        if kind == "commands" {
+            return Ok(Self::Commands { name });
+        }
+
+        let content;
+        syn::parenthesized!(content in input);
 
-        // Add the 'mut' if necessary.
-        if self.mutable {
-            tokens.extend(quote! { mut });
+        if kind == "query" {
+            let inputs = content
+                .parse_terminated::<_, syn::Token![,]>(SystemInput::parse)?
+                .into_iter()
+                .collect();
+            return Ok(Self::Query { name, inputs });
         }
 
-        // Add the type.
-        let ty = &self.ty;
-        tokens.extend(quote! { #ty });
+        let Type::Reference(reference) = content.parse::<Type>()? else {
+            return Err(syn::Error::new_spanned(kind, "Expected `&T` or `&mut T`"));
+        };
+        let ty = *reference.elem;
+        let mutable = reference.mutability.is_some();
+
+        match kind.to_string().as_str() {
+            "res" => Ok(Self::Resource { name, ty, mutable }),
+            "events" if mutable => Ok(Self::EventWriter { name, ty }),
+            "events" => Ok(Self::EventReader { name, ty }),
+            _ => Err(syn::Error::new_spanned(
+                kind,
+                "Expected `query`, `res`, `events` or `commands`",
+            )),
+        }
     }
 }
 
@@ -94,15 +263,20 @@ enum Mode {
     /// - `&Entities`
     /// - `&Assets`
     /// - `f64` (delta time)
+    /// - The extra parameters, in order
     Once,
     /// Run the system function for each entity with the given components.
     ///
     /// The system function will be called with the following arguments:
     /// - `&mut State`
     /// - `&Entities`
-    /// - `(&mut Component1, &Component2, ...)`
+    /// - `(Entity, &mut Component1, &Component2, ..., Option<&mut Optional1>, Option<&Optional2>, ...)`
     /// - `&Assets`
     /// - `f64` (delta time)
+    /// - The extra parameters, in order
+    ///
+    /// The components are ordered like in `query!`,
+    /// and filters like `Without<T>` aren't given.
     Iter(Vec<SystemInput>),
     /// Run the system function for each entity with the given components.
     ///
@@ -111,65 +285,160 @@ enum Mode {
     /// The system function will be called with the following arguments:
     /// - `&State`
     /// - `&Entities`
-    /// - `(&mut Component1, &Component2, ...)`
+    /// - The same components as `Iter`
     /// - `&Assets`
     /// - `f64` (delta time)
+    /// - The extra parameters, in order
     ///
-    /// Note that the state is immutable.
+    /// Note that the state and the extra parameters are immutable.
     /// This is because the state is shared between all threads.
     /// If you need to mutate the state, use a locking mechanism,
     /// or consider another solution, as if each thread locks the state,
     /// the benefit of parallelism is lost.
     ParIter(Vec<SystemInput>),
+    /// Run the system function once per frame, alone, as an `ExclusiveSystem`.
+    ///
+    /// The system function will be called with the following arguments:
+    /// - `&mut World`
+    /// - `&Assets`
+    /// - `f64` (delta time)
+    ///
+    /// The state isn't given, as it's in the world,
+    /// but its default is added to the resources like other systems.
+    Exclusive,
 }
 
 impl Mode {
     /// Gets the system inputs.
     ///
-    /// if the mode is `Once`, this will return an empty vector.
+    /// if the mode is `Once` or `Exclusive`, this will return an empty vector.
     fn inputs(&self) -> &[SystemInput] {
         match self {
-            Self::Once => &[],
+            Self::Once | Self::Exclusive => &[],
             Self::Iter(inputs) | Self::ParIter(inputs) => inputs,
         }
     }
+}
 
-    /// Splits the inputs between mutable and immutable.
-    fn split_inputs(&self) -> (Vec<&SystemInput>, Vec<&SystemInput>) {
-        let mut mutable = Vec::new();
-        let mut immutable = Vec::new();
+/// Gets the types of the inputs of the given kind and mutability.
+fn input_types(inputs: &[SystemInput], kind: InputKind, mutable: bool) -> Vec<&Type> {
+    inputs
+        .iter()
+        .filter(|input| input.kind == kind && input.mutable == mutable)
+        .map(|input| &input.ty)
+        .collect()
+}
 
-        for input in self.inputs() {
-            if input.mutable {
-                mutable.push(input);
-            } else {
-                immutable.push(input);
-            }
-        }
+/// Gets the `query!` for the inputs.
+fn query_expr(inputs: &[SystemInput]) -> proc_macro2::TokenStream {
+    let mutable_types = input_types(inputs, InputKind::Required, true);
+    let immutable_types = input_types(inputs, InputKind::Required, false);
+    let optional_mutable_types = input_types(inputs, InputKind::Optional, true);
+    let optional_immutable_types = input_types(inputs, InputKind::Optional, false);
+    let with_types = input_types(inputs, InputKind::With, false);
+    let without_types = input_types(inputs, InputKind::Without, false);
+    let changed_types = input_types(inputs, InputKind::Changed, false);
+    let added_types = input_types(inputs, InputKind::Added, false);
 
-        (mutable, immutable)
+    quote! {
+        iridium_ecs::query!(
+            entities,
+            [
+                #(mut #mutable_types, )*;
+                #(#immutable_types, )*
+            ],
+            optional [
+                #(mut #optional_mutable_types, )*;
+                #(#optional_immutable_types, )*
+            ],
+            with [#(#with_types, )*],
+            without [#(#without_types, )*],
+            changed [#(#changed_types, )*],
+            added [#(#added_types, )*]
+        )
     }
 }
 
+/// Gets the patterns to bind the guards returned by the query,
+/// and the expressions to turn them into the references given to the system.
+///
+/// These are in the same order as the query returns them.
+fn fetched_bindings(
+    inputs: &[SystemInput],
+) -> (Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>) {
+    let order = [
+        (InputKind::Required, true),
+        (InputKind::Required, false),
+        (InputKind::Optional, true),
+        (InputKind::Optional, false),
+    ];
+
+    order
+        .into_iter()
+        .flat_map(|(kind, mutable)| {
+            input_types(inputs, kind, mutable)
+                .into_iter()
+                .map(move |_| (kind, mutable))
+        })
+        .enumerate()
+        .map(|(index, (kind, mutable))| {
+            let ident = quote::format_ident!("component_{index}");
+            let pattern = if mutable {
+                quote! { mut #ident }
+            } else {
+                quote! { #ident }
+            };
+            let expr = match (kind, mutable) {
+                (InputKind::Optional, true) => quote! { #ident.as_deref_mut() },
+                (InputKind::Optional, false) => quote! { #ident.as_deref() },
+                (_, true) => quote! { &mut *#ident },
+                (_, false) => quote! { &*#ident },
+            };
+            (pattern, expr)
+        })
+        .unzip()
+}
+
+/// Gets the types of the components given to the system,
+/// split between mutable and immutable.
+fn accessed_types(inputs: &[SystemInput]) -> (Vec<&Type>, Vec<&Type>) {
+    let inputs = inputs.iter().filter(|input| input.kind.is_fetched());
+
+    let (mutable, immutable): (Vec<_>, Vec<_>) = inputs.partition(|input| input.mutable);
+
+    (
+        mutable.into_iter().map(|input| &input.ty).collect(),
+        immutable.into_iter().map(|input| &input.ty).collect(),
+    )
+}
+
 /// Parse the mode from a `ParseStream`.
 impl syn::parse::Parse for Mode {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         // Get the mode name.
         let mode = input.parse::<syn::Ident>()?.to_string();
 
-        // If once, return.
+        // If once or exclusive, return.
         if mode == "once" {
             return Ok(Self::Once);
         }
+        if mode == "exclusive" {
+            return Ok(Self::Exclusive);
+        }
 
         // Parse the next comma.
         input.parse::<syn::Token![,]>()?;
 
-        // Get the types of the components.
-        let component_types: Vec<SystemInput> = input
-            .parse_terminated::<_, syn::Token![,]>(SystemInput::parse)?
-            .into_iter()
-            .collect();
+        // Get the types of the components, up to the extra parameters.
+        let mut component_types = Vec::new();
+        while !input.is_empty() && !input.peek(syn::Token![;]) {
+            component_types.push(input.parse::<SystemInput>()?);
+
+            if input.is_empty() || input.peek(syn::Token![;]) {
+                break;
+            }
+            input.parse::<syn::Token![,]>()?;
+        }
 
         // Return the mode.
         match mode.as_str() {
@@ -180,36 +449,212 @@ impl syn::parse::Parse for Mode {
     }
 }
 
+/// Checks the extra parameters can be given to the system together.
+///
+/// Components and resources borrowed mutably by one parameter
+/// can't be used by another, or by the main query,
+/// and in `par_iter` the parameters are shared, so they can't be mutable.
+fn check_params(mode: &Mode, params: &[SystemParam]) -> syn::Result<()> {
+    let Some(first) = params.first() else {
+        return Ok(());
+    };
+
+    if matches!(mode, Mode::Exclusive) {
+        return Err(syn::Error::new_spanned(
+            first.name(),
+            "Exclusive systems get the world, so they can't have parameters",
+        ));
+    }
+
+    // Each parameter's borrows, as (name, type, mutable).
+    // Resources and events are kept apart from components, as they're stored separately.
+    let mut borrows = vec![];
+    for (name, (mutable, immutable)) in std::iter::once((None, accessed_types(mode.inputs())))
+        .chain(params.iter().filter_map(|param| match param {
+            SystemParam::Query { name, inputs } => Some((Some(name), accessed_types(inputs))),
+            _ => None,
+        }))
+    {
+        for (ty, is_mutable) in mutable
+            .into_iter()
+            .map(|ty| (ty, true))
+            .chain(immutable.into_iter().map(|ty| (ty, false)))
+        {
+            let ty = quote::ToTokens::to_token_stream(ty).to_string();
+            borrows.push((name, format!("component {ty}"), is_mutable));
+        }
+    }
+    for param in params {
+        let (ty, mutable) = match param {
+            SystemParam::Resource { ty, mutable, .. } => (ty, *mutable),
+            SystemParam::EventWriter { ty, .. } => (ty, true),
+            SystemParam::EventReader { ty, .. } => (ty, false),
+            SystemParam::Query { .. } | SystemParam::Commands { .. } => continue,
+        };
+        let ty = quote::ToTokens::to_token_stream(ty).to_string();
+        let kind = if matches!(param, SystemParam::Resource { .. }) {
+            "resource"
+        } else {
+            "events"
+        };
+        borrows.push((Some(param.name()), format!("{kind} {ty}"), mutable));
+    }
+
+    for (index, (name, ty, mutable)) in borrows.iter().enumerate() {
+        let Some(name) = name else {
+            continue;
+        };
+
+        if *mutable && matches!(mode, Mode::ParIter(_)) {
+            return Err(syn::Error::new_spanned(
+                name,
+                format!(
+                    "`par_iter` shares the parameters between threads, so {ty} can't be mutable"
+                ),
+            ));
+        }
+
+        let conflict = borrows.iter().enumerate().find(
+            |(other_index, (other_name, other_ty, other_mutable))| {
+                *other_index != index
+                    && other_name != &Some(*name)
+                    && other_ty == ty
+                    && (*mutable || *other_mutable)
+            },
+        );
+        if let Some((_, (other_name, ..))) = conflict {
+            let other = other_name.map_or_else(
+                || "the main query".to_string(),
+                |other| format!("`{other}`"),
+            );
+            return Err(syn::Error::new_spanned(
+                name,
+                format!("{ty} is borrowed by `{name}` and {other}, and one of them is mutable"),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
 /// This macro simplifies the creation of a system.
 ///
 /// # Panics
 ///
 /// Will panic if the input is invalid.
 #[allow(clippy::too_many_lines, clippy::needless_pass_by_value)]
-pub fn system_helper(Input { state, mode }: Input, ast: syn::ItemImpl) -> proc_macro::TokenStream {
+pub fn system_helper(
+    Input {
+        state,
+        mode,
+        params,
+    }: Input,
+    ast: syn::ItemImpl,
+) -> proc_macro::TokenStream {
     // Get the name of the system.
     let Type::Path(self_type) = &*ast.self_ty else {
         panic!("`system` must be implemented for a struct");
     };
 
-    // Split the inputs.
-    let (mutable_inputs, immutable_inputs) = mode.split_inputs();
+    if let Err(error) = check_params(&mode, &params) {
+        return error.to_compile_error().into();
+    }
 
-    // Get the types of the inputs.
-    let mutable_inputs_types = mutable_inputs
-        .iter()
-        .map(|input| &input.ty)
-        .collect::<Vec<_>>();
-    let immutable_inputs_types = immutable_inputs
-        .iter()
-        .map(|input| &input.ty)
-        .collect::<Vec<_>>();
+    // The types used by the system, for scheduling.
+    let (mut accessed_mutable_types, mut accessed_immutable_types): (Vec<_>, Vec<_>) = {
+        let (mutable, immutable) = accessed_types(mode.inputs());
+        (
+            mutable
+                .into_iter()
+                .map(quote::ToTokens::to_token_stream)
+                .collect(),
+            immutable
+                .into_iter()
+                .map(quote::ToTokens::to_token_stream)
+                .collect(),
+        )
+    };
 
-    // An expr to get the state.
-    let let_state_expr = if quote::ToTokens::to_token_stream(&state).to_string() == "()" {
-        quote! { let state = (); }
+    // The query returns guards, which are kept alive while the system gets references.
+    let (guard_patterns, component_exprs) = fetched_bindings(mode.inputs());
+
+    // The query used by the iter modes.
+    let query = query_expr(mode.inputs());
+
+    // The statements getting the extra parameters before the system runs,
+    // and the expressions passing them to it.
+    // In `par_iter`, they're shared between threads, so they're immutable.
+    let mut param_setup = Vec::new();
+    let mut param_args = Vec::new();
+    for param in &params {
+        let name = param.name();
+        match param {
+            SystemParam::Query { inputs, .. } => {
+                let (mutable, immutable) = accessed_types(inputs);
+                accessed_mutable_types.extend(mutable.iter().map(|ty| quote! { #ty }));
+                accessed_immutable_types.extend(immutable.iter().map(|ty| quote! { #ty }));
+
+                let query = query_expr(inputs);
+                if mutable.is_empty() {
+                    param_setup.push(quote! { let #name = #query.collect::<Vec<_>>(); });
+                    param_args.push(quote! { &#name[..] });
+                } else {
+                    param_setup.push(quote! { let mut #name = #query.collect::<Vec<_>>(); });
+                    param_args.push(quote! { &mut #name[..] });
+                }
+            }
+            SystemParam::Resource { ty, mutable, .. } => {
+                if *mutable {
+                    accessed_mutable_types.push(quote! { #ty });
+                    param_setup.push(quote! { let mut #name = resources.get_mut::<#ty>(); });
+                    param_args.push(quote! { &mut *#name });
+                } else {
+                    accessed_immutable_types.push(quote! { #ty });
+                    param_setup.push(quote! { let #name = resources.get::<#ty>(); });
+                    param_args.push(quote! { &*#name });
+                }
+            }
+            SystemParam::EventWriter { ty, .. } => {
+                accessed_mutable_types.push(quote! { iridium_ecs::Events<#ty> });
+                param_setup.push(quote! {
+                    let mut #name = resources.get_mut::<iridium_ecs::Events<#ty>>();
+                });
+                param_args.push(quote! { &mut *#name });
+            }
+            SystemParam::EventReader { ty, .. } => {
+                accessed_immutable_types.push(quote! { iridium_ecs::Events<#ty> });
+                let events = quote::format_ident!("{name}_events");
+                param_setup.push(quote! {
+                    let #events = resources.get::<iridium_ecs::Events<#ty>>();
+                    let #name = #events.read(stringify!(#self_type)).collect::<Vec<_>>();
+                });
+                param_args.push(quote! { &#name[..] });
+            }
+            SystemParam::Commands { .. } => {
+                param_setup.push(quote! {
+                    let #name = entities.commands(stringify!(#self_type));
+                });
+                param_args.push(quote! { #name });
+            }
+        }
+    }
+
+    let has_state = quote::ToTokens::to_token_stream(&state).to_string() != "()";
+
+    // An expr to get the state, and the expr to pass it to the system.
+    // In `par_iter`, the state is shared, so it's borrowed immutably.
+    let (let_state_expr, state_arg) = if !has_state {
+        (quote! { let state = (); }, quote! { state })
+    } else if matches!(mode, Mode::ParIter(_)) {
+        (
+            quote! { let state = state.unwrap_or_else(|| unreachable!()).get::<#state>(); },
+            quote! { &*state },
+        )
     } else {
-        quote! { let state = state.unwrap_or_else(|| unreachable!()).get_mut::<#state>(); }
+        (
+            quote! { let mut state = state.unwrap_or_else(|| unreachable!()).get_mut::<#state>(); },
+            quote! { &mut *state },
+        )
     };
 
     // An expr for default state.
@@ -219,6 +664,36 @@ pub fn system_helper(Input { state, mode }: Input, ast: syn::ItemImpl) -> proc_m
         quote! { Some(#state::default().into()) }
     };
 
+    // Exclusive systems implement their own trait.
+    if matches!(mode, Mode::Exclusive) {
+        return quote! {
+            impl iridium_ecs::systems::ExclusiveSystem for #self_type {
+                fn name(&self) -> &'static str {
+                    stringify!(#self_type)
+                }
+
+                fn default_state(&self) -> Option<iridium_ecs::ComponentBox> {
+                    #default_state_expr
+                }
+
+                fn system(
+                    &self,
+                    world: &mut iridium_ecs::World,
+                    assets: &iridium_assets::Assets,
+                    delta_time: f64,
+                ) -> Result<(), Vec<iridium_ecs::systems::SystemError>> {
+                    // Run the system.
+                    Self::system(world, assets, delta_time).map_err(|error| {
+                        vec![iridium_ecs::systems::SystemError::new(stringify!(#self_type), None, error)]
+                    })
+                }
+            }
+        }
+        .to_string()
+        .parse()
+        .expect("Failed to parse the generated code.");
+    }
+
     // The system function.
     let system_fn = match mode {
         Mode::Once => quote! {
@@ -226,15 +701,18 @@ pub fn system_helper(Input { state, mode }: Input, ast: syn::ItemImpl) -> proc_m
                 &self,
                 state: Option<&iridium_ecs::ComponentBox>,
                 entities: &iridium_ecs::Entities,
+                resources: &iridium_ecs::Resources,
                 assets: &iridium_assets::Assets,
                 delta_time: f64,
-            ) {
+            ) -> Result<(), Vec<iridium_ecs::systems::SystemError>> {
                 // Get the state as its real type.
                 #let_state_expr
+                // Get the extra parameters.
+                #(#param_setup)*
                 // Run the system.
-                if let Err(error) = Self::system(state, entities, assets, delta_time) {
-                    entities.get::<iridium_core::LogState>().error(error);
-                }
+                Self::system(#state_arg, entities, resources, assets, delta_time #(, #param_args)*).map_err(|error| {
+                    vec![iridium_ecs::systems::SystemError::new(stringify!(#self_type), None, error)]
+                })
             }
         },
 
@@ -243,31 +721,51 @@ pub fn system_helper(Input { state, mode }: Input, ast: syn::ItemImpl) -> proc_m
                 &self,
                 state: Option<&iridium_ecs::ComponentBox>,
                 entities: &iridium_ecs::Entities,
+                resources: &iridium_ecs::Resources,
                 assets: &iridium_assets::Assets,
                 delta_time: f64,
-            ) {
+            ) -> Result<(), Vec<iridium_ecs::systems::SystemError>> {
                 // Get the state as its real type.
                 // The system can mutate this.
                 #let_state_expr
+                // Get the extra parameters.
+                #(#param_setup)*
+
+                let mut errors = Vec::new();
+                let mut processed = 0;
 
                 // Query the entities.
-                iridium_ecs::query!(entities, [
-                    #(mut #mutable_inputs_types, )*;
-                    #(#immutable_inputs_types, )*
-                ])
+                #query
                     // For each entity with the given components.
-                    .for_each(|components| {
+                    .for_each(|(entity, #(#guard_patterns, )*)| {
+                        processed += 1;
+
                         // Run the system.
                         if let Err(error) = Self::system(
-                            state,
+                            #state_arg,
                             entities,
-                            components,
+                            resources,
+                            (entity, #(#component_exprs, )*),
                             assets,
                             delta_time,
+                            #(#param_args, )*
                         ) {
-                            entities.get::<iridium_core::LogState>().error(error);
+                            errors.push(iridium_ecs::systems::SystemError::new(
+                                stringify!(#self_type),
+                                Some(entity),
+                                error,
+                            ));
                         }
                     });
+
+                // Count the entities for the system stats.
+                iridium_ecs::SystemScope::add_processed_entities(processed);
+
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors)
+                }
             }
         },
 
@@ -276,39 +774,64 @@ pub fn system_helper(Input { state, mode }: Input, ast: syn::ItemImpl) -> proc_m
                 &self,
                 state: Option<&iridium_ecs::ComponentBox>,
                 entities: &iridium_ecs::Entities,
+                resources: &iridium_ecs::Resources,
                 assets: &iridium_assets::Assets,
                 delta_time: f64,
-            ) {
+            ) -> Result<(), Vec<iridium_ecs::systems::SystemError>> {
                 use rayon::prelude::*;
 
                 // Get the state as its real type.
                 // The system can't mutate this directly as it's shared.
                 #let_state_expr
+                // Get the extra parameters.
+                #(#param_setup)*
 
-                // Query the entities.
-                iridium_ecs::query!(entities, [
-                    #(mut #mutable_inputs_types, )*;
-                    #(#immutable_inputs_types, )*
-                ])
-                    // Run the query stuff now.
-                    .collect::<Vec<_>>()
+                // Query the entities, running the query stuff now.
+                let fetched = #query.collect::<Vec<_>>();
+
+                // Count the entities for the system stats.
+                iridium_ecs::SystemScope::add_processed_entities(fetched.len());
+
+                let last_run_tick = iridium_ecs::SystemScope::last_run_tick();
+                let errors = fetched
                     // Create a parallel iterator.
                     .into_par_iter()
                     // For each entity with the given components.
-                    .for_each(|components| {
+                    .filter_map(|(entity, #(#guard_patterns, )*)| {
+                        // This runs on other threads, so mark the system as running there too.
+                        let _scope = iridium_ecs::SystemScope::enter(stringify!(#self_type))
+                            .with_last_run_tick(last_run_tick);
+
                         // Run the system.
-                        if let Err(error) = Self::system(
-                            state,
+                        Self::system(
+                            #state_arg,
                             entities,
-                            components,
+                            resources,
+                            (entity, #(#component_exprs, )*),
                             assets,
                             delta_time,
-                        ) {
-                            entities.get::<iridium_core::LogState>().error(error);
-                        }
-                    });
+                            #(#param_args, )*
+                        )
+                        .err()
+                        .map(|error| {
+                            iridium_ecs::systems::SystemError::new(
+                                stringify!(#self_type),
+                                Some(entity),
+                                error,
+                            )
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors)
+                }
             }
         },
+
+        Mode::Exclusive => unreachable!("Exclusive systems are generated above"),
     };
 
     quote! {
@@ -328,10 +851,10 @@ pub fn system_helper(Input { state, mode }: Input, ast: syn::ItemImpl) -> proc_m
             fn required_components(&self) -> [Vec<iridium_reflect::StableTypeId>; 2] {
                 [
                     vec![#(
-                        <#mutable_inputs_types as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                        <#accessed_mutable_types as iridium_reflect::HasStableTypeId>::stable_type_id(),
                     )*],
                     vec![#(
-                        <#immutable_inputs_types as iridium_reflect::HasStableTypeId>::stable_type_id(),
+                        <#accessed_immutable_types as iridium_reflect::HasStableTypeId>::stable_type_id(),
                     )*],
                 ]
             }
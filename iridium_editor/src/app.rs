@@ -169,7 +169,7 @@ impl App {
             &mut self.ui_state,
         );
 
-        let input_state = world.entities.get::<InputState>();
+        let mut input_state = world.resources.get_mut::<InputState>();
         input_state.process_old_inputs();
         for event in game_events {
             match event {
@@ -201,15 +201,22 @@ impl App {
             }
         }
 
+        drop(input_state);
+
         self.egui_state
             .draw(window, input, &mut self.ui_state, world, assets);
 
         // Drawing the UI could've changed an Asset id,
         // so these need to be updated here.
-        let log_state = world.entities.get::<LogState>();
         match world.entities.update_assets(assets) {
-            Ok(count) if count > 0 => log_state.info(format!("Updated {count} assets")),
-            Err(error) => log_state.error(format!("Updating assets failed: {error}")),
+            Ok(count) if count > 0 => world
+                .resources
+                .get_mut::<LogState>()
+                .info(format!("Updated {count} assets")),
+            Err(error) => world
+                .resources
+                .get_mut::<LogState>()
+                .error(format!("Updating assets failed: {error}")),
             _ => {}
         }
 
@@ -219,6 +226,10 @@ impl App {
         // so will not affect performance.
         world.entities.process_commands();
 
+        // Transforms may have been edited in the UI,
+        // so the global transforms need updating even when paused.
+        world.entities.propagate_transforms();
+
         // Get the surface texture to render to.
         let output = self
             .surface
@@ -263,6 +274,7 @@ impl App {
             // Run the rendering system for the entities in the world.
             Renderer2DSystem::run(
                 &world.entities,
+                &world.resources,
                 assets,
                 &self.device,
                 &mut render_pass,
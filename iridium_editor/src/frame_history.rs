@@ -2,8 +2,8 @@ use std::{collections::VecDeque, time::SystemTime};
 
 use iridium_assets::Assets;
 use iridium_ecs::{
-    storage::{ComponentStorage, StoredComponent, StoredComponentField},
-    Entities,
+    storage::{ComponentStorage, FieldStorage, StoredComponent},
+    Entities, Resources,
 };
 use iridium_ecs_macros::{system_helper, Component, HasStableTypeId, InspectorUi};
 use iridium_map_utils::fast_map;
@@ -58,12 +58,12 @@ impl FrameHistoryState {
 
 impl ComponentStorage for FrameHistoryState {
     fn from_stored(mut stored: StoredComponent, _assets: &Assets) -> Option<Self> {
-        let max_frames = stored.get("max_frames")?.parse().ok()?;
+        let max_frames = stored.get("max_frames")?;
 
         Some(Self {
             frames: VecDeque::with_capacity(max_frames),
             max_frames,
-            max_age: stored.get("max_age")?.parse().ok()?,
+            max_age: stored.get("max_age")?,
         })
     }
 
@@ -71,8 +71,8 @@ impl ComponentStorage for FrameHistoryState {
         StoredComponent {
             type_name: "FrameHistoryState".to_string(),
             fields: fast_map! {
-                "max_frames" => StoredComponentField::new(self.max_frames.to_string(), false),
-                "max_age" => StoredComponentField::new(self.max_age.to_string(), false),
+                "max_frames" => self.max_frames.to_stored_value(),
+                "max_age" => self.max_age.to_stored_value(),
             },
         }
     }
@@ -85,6 +85,7 @@ impl FrameHistorySystem {
     fn system(
         state: &mut FrameHistoryState,
         _entities: &Entities,
+        _resources: &Resources,
         _assets: &Assets,
         delta_time: f64,
     ) -> Result<(), String> {
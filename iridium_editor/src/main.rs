@@ -63,20 +63,15 @@ fn main() {
     world.entities.register_component::<LogState>();
     world.entities.register_component::<FrameHistoryState>();
     world.entities.register_component_with_default::<Camera>();
-    world.entities.add_components(
-        world
-            .entities
-            .entity_id_from_name("SystemState")
-            .expect("SystemState entity not found"),
-        vec![
-            Renderer2DState {
-                active_camera: String::new(),
-                camera_gpu_data: Some(camera_gpu_data),
-            }
-            .into(),
-            InputState::default().into(),
-        ],
-    );
+
+    // Add the engine resources.
+    world.insert_resource(Renderer2DState {
+        active_camera: String::new(),
+        camera_gpu_data: Some(camera_gpu_data),
+        last_render_tick: None,
+    });
+    world.insert_resource(InputState::default());
+    world.insert_resource(LogState::default());
 
     world.systems.add_system(FrameHistorySystem);
 
@@ -91,8 +86,7 @@ fn main() {
     // Load the assets.
     if let Err(e) = project.load_assets(
         world
-            .entities
-            .get::<Renderer2DState>()
+            .resource::<Renderer2DState>()
             .camera_gpu_data
             .as_ref()
             .expect("CameraGpuData not found"),
@@ -106,16 +100,33 @@ fn main() {
 
     // Run the init system.
     project.init_system(&mut world, &assets);
+    world.insert_default_states();
 
-    // Prepend `FrameHistorySystem` to stages.
-    let mut stages = vec![vec!["FrameHistorySystem".to_string()]];
-    stages.extend(world.systems.stages);
-    world.systems.stages = stages;
+    // Report bad system ordering now, rather than when the systems first run.
+    let schedule_error = world.systems.check_schedule();
+    world
+        .resources
+        .get_mut::<LogState>()
+        .system_errors(schedule_error);
+
+    // Prepend `FrameHistorySystem` to the manual stages, if the project uses them.
+    if !world.systems.stages.is_empty() {
+        let mut stages = vec![vec!["FrameHistorySystem".to_string()]];
+        stages.extend(world.systems.stages);
+        world.systems.stages = stages;
+    }
 
     // Open the default scene.
     let default_scene = project.project_settings.default_scene;
-    match world.load(&default_scene, &assets) {
-        Ok(_) => app.ui_state.open_scene = Some(default_scene),
+    // Parts of the scene that can't be read are skipped, and shown in the log.
+    match world.load_lenient(&default_scene, &assets) {
+        Ok(diagnostics) => {
+            let mut log = world.resources.get_mut::<LogState>();
+            for diagnostic in diagnostics {
+                log.warning(format!("{default_scene}: {diagnostic}"));
+            }
+            app.ui_state.open_scene = Some(default_scene);
+        }
         Err(e) => println!("Failed to load default scene with error: {e:?}"),
     }
 
@@ -127,35 +138,35 @@ fn main() {
         Event::WindowEvent {
             ref event,
             window_id,
-        } if window_id == window.id() => {
             // If the app didn't handle the event itself.
-            if !app.input(event) {
-                match event {
-                    WindowEvent::CloseRequested
-                    // Make sure to remove this at some point,
-                    // but during dev it's really useful
-                    | WindowEvent::KeyboardInput {
-                        input:
-                            KeyboardInput {
-                                state: ElementState::Pressed,
-                                virtual_keycode: Some(VirtualKeyCode::Escape),
-                                ..
-                            },
+        } if window_id == window.id() && !app.input(event) => match event {
+            WindowEvent::CloseRequested
+            // Make sure to remove this at some point,
+            // but during dev it's really useful
+            | WindowEvent::KeyboardInput {
+                input:
+                    KeyboardInput {
+                        state: ElementState::Pressed,
+                        virtual_keycode: Some(VirtualKeyCode::Escape),
                         ..
-                    } => {
-                        // Exit the app.
-                        *control_flow = ControlFlow::Exit;
-                    }
-                    WindowEvent::Resized(physical_size) => {
-                        app.resize((physical_size.width, physical_size.height));
-                    }
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        app.resize((new_inner_size.width, new_inner_size.height));
-                    }
-                    _ => {}
-                }
+                    },
+                ..
+            } => {
+                // The world is unloaded, so run the shutdown systems if it's playing.
+                let errors = world.run_shutdown(&assets);
+                world.resources.get_mut::<LogState>().system_errors(errors);
+
+                // Exit the app.
+                *control_flow = ControlFlow::Exit;
             }
-        }
+            WindowEvent::Resized(physical_size) => {
+                app.resize((physical_size.width, physical_size.height));
+            }
+            WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                app.resize((new_inner_size.width, new_inner_size.height));
+            }
+            _ => {}
+        },
         // Redraw the window.
         Event::RedrawRequested(window_id) if window_id == window.id() => {
             // Start a new frame.
@@ -179,9 +190,19 @@ fn main() {
             if matches!(app.ui_state.play_state(), PlayState::Play) {
                 puffin::profile_scope!("Systems");
                 // Run the systems.
-                world
-                    .systems
-                    .run_systems(&mut world.entities, delta_time, &assets);
+                let errors = world.run_systems(delta_time, &assets);
+                world.resources.get_mut::<LogState>().system_errors(errors);
+
+                // A system failed with the halt policy, so pause play.
+                // The systems are resumed so they run again when play does.
+                if world.systems.is_halted() {
+                    world.systems.resume();
+                    app.ui_state.pause();
+                    world
+                        .resources
+                        .get_mut::<LogState>()
+                        .warning("A system failed, so play was paused");
+                }
             }
 
             // Render the app and game.
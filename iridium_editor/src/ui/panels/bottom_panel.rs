@@ -11,7 +11,7 @@ fn load_texture(context: &egui::Context, path: &str) -> ((usize, usize), egui::T
     let image_rgba = dynamic_image.to_rgba8();
 
     let texture = context.load_texture(
-        path.split('/').last().unwrap_or(path),
+        path.split('/').next_back().unwrap_or(path),
         egui::ColorImage::from_rgba_unmultiplied([size.0 as usize, size.1 as usize], &image_rgba),
         egui::TextureOptions {
             magnification: egui::TextureFilter::Nearest,
@@ -107,7 +107,7 @@ impl PanelUi for BottomPanel {
                             .max_width(f32::INFINITY)
                             .always_show_scroll(true)
                             .show(ui, |ui| {
-                                let log = world.entities.get::<LogState>();
+                                let log = world.resources.get_mut::<LogState>();
 
                                 for entry in log.entries() {
                                     match entry.log_type {
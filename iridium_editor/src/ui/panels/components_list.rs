@@ -1,22 +1,80 @@
 use iridium_assets::Assets;
-use iridium_ecs::{ComponentBox, Name};
+use iridium_ecs::{
+    systems::{ErrorPolicy, ScheduleLabel},
+    ComponentBox, ComponentRef, Name,
+};
 use iridium_reflect::StableTypeId;
-use std::collections::HashSet;
 
 use crate::ui::PanelUi;
 
 /// A widget to edit a component.
-pub fn component_widget(ui: &mut egui::Ui, id: impl std::hash::Hash, component: &ComponentBox) {
-    egui::CollapsingHeader::new(component.type_name())
-        .default_open(true)
-        .show(ui, |ui| {
-            egui::Grid::new(id).show(ui, |ui| {
-                component.get_trait_mut().ui(ui);
+///
+/// If `removable`, the header has a button to remove the component,
+/// and this returns true when it's clicked.
+pub fn component_widget(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    component: &mut ComponentRef,
+    removable: bool,
+) -> bool {
+    let type_name = component.type_name();
+
+    inspector_widget(ui, id, type_name, removable, |ui| {
+        component.get_trait_mut().ui(ui);
+    })
+}
+
+/// A widget to edit a resource.
+pub fn resource_widget(ui: &mut egui::Ui, id: impl std::hash::Hash, resource: &ComponentBox) {
+    inspector_widget(ui, id, resource.type_name(), false, |ui| {
+        resource.get_trait_mut().ui(ui);
+    });
+}
+
+/// A collapsing header with the inspector UI of a component or resource.
+///
+/// `component_ui` is only called when the header is open,
+/// so the component is only borrowed then.
+fn inspector_widget(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    type_name: &str,
+    removable: bool,
+    component_ui: impl FnOnce(&mut egui::Ui),
+) -> bool {
+    let mut remove = false;
+
+    egui::collapsing_header::CollapsingState::load_with_default_open(
+        ui.ctx(),
+        ui.make_persistent_id(type_name),
+        true,
+    )
+    .show_header(ui, |ui| {
+        ui.label(type_name);
+
+        if removable {
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                remove = ui
+                    .small_button("x")
+                    .on_hover_text("Remove component")
+                    .clicked();
             });
+        }
+    })
+    .body(|ui| {
+        egui::Grid::new(id).show(ui, |ui| {
+            component_ui(ui);
         });
+    });
+
+    remove
 }
 
-/// A widget to view / reorder systems in stages.
+/// A widget to view / reorder systems in stages,
+/// for each schedule, and enable or disable them.
+///
+/// The automatic stages can only be viewed,
+/// but the `Update` ones can be copied to the manual stages to reorder them.
 pub fn system_stages_widget(ui: &mut egui::Ui, world: &mut iridium_ecs::World) {
     puffin::profile_function!();
 
@@ -25,15 +83,63 @@ pub fn system_stages_widget(ui: &mut egui::Ui, world: &mut iridium_ecs::World) {
         .auto_shrink([false, false])
         .max_width(f32::INFINITY)
         .show(ui, |ui| {
+            let manual = !world.systems.stages.is_empty();
+
+            ui.horizontal(|ui| {
+                if manual {
+                    ui.label("Manual stages");
+                    if ui.button("Use automatic stages").clicked() {
+                        world.systems.stages.clear();
+                    }
+                } else {
+                    ui.label("Automatic stages");
+                    if ui.button("Edit stages").clicked() {
+                        world.systems.stages =
+                            world.systems.active_stages(&ScheduleLabel::Update).to_vec();
+                    }
+                }
+            });
+
+            // What happens when a system fails.
+            let error_policy = &mut world.systems.error_policy;
+            egui::ComboBox::from_label("On system error")
+                .selected_text(format!("{error_policy:?}"))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(error_policy, ErrorPolicy::Continue, "Continue");
+                    ui.selectable_value(error_policy, ErrorPolicy::DisableSystem, "DisableSystem");
+                    ui.selectable_value(error_policy, ErrorPolicy::Halt, "Halt");
+                });
+
+            if let Err(e) = world.systems.build_schedule() {
+                ui.colored_label(ui.visuals().error_fg_color, e.to_string());
+            }
+
+            // Every schedule's stages, with the schedule they're in.
             world
                 .systems
-                .stages
-                .clone()
+                .schedule_labels()
+                .into_iter()
+                .flat_map(|schedule| {
+                    let stages = world.systems.active_stages(&schedule).to_vec();
+                    let errors = world.systems.find_errors(&schedule);
+                    stages
+                        .into_iter()
+                        .enumerate()
+                        .zip(errors)
+                        .map(move |stage| (schedule.clone(), stage))
+                })
+                .collect::<Vec<_>>()
                 .into_iter()
-                .enumerate()
-                .zip(world.systems.find_errors().into_iter())
-                .for_each(|((index, mut stage), errors)| {
+                .for_each(|(schedule, ((index, mut stage), errors))| {
+                    // Only the `Update` stages can be set manually.
+                    let manual = manual && schedule == ScheduleLabel::Update;
+
+                    if index == 0 {
+                        ui.strong(format!("{schedule:?}"));
+                    }
+
                     egui::CollapsingHeader::new(format!("Stage {index}"))
+                        .id_source((&schedule, index))
                         .default_open(true)
                         .show(ui, |ui| {
                             // Sort systems by name.
@@ -44,12 +150,14 @@ pub fn system_stages_widget(ui: &mut egui::Ui, world: &mut iridium_ecs::World) {
                                 .clone()
                                 .into_iter()
                                 // Get the inputs of each system.
+                                // Exclusive systems get the whole world instead.
                                 .map(|system_name| {
                                     world
                                         .systems
                                         .get_system(&system_name)
-                                        .expect("System in stage not found")
-                                        .required_components()
+                                        .map_or_else(Default::default, |system| {
+                                            system.required_components()
+                                        })
                                 })
                                 .map(|[a, b]| (a, b))
                                 .unzip();
@@ -60,8 +168,23 @@ pub fn system_stages_widget(ui: &mut egui::Ui, world: &mut iridium_ecs::World) {
                                 .zip(immut_inputs)
                                 .for_each(|((system_name, mut_inputs), immut_inputs)| {
                                     ui.horizontal(|ui| {
+                                        // Whether the system runs.
+                                        let mut enabled = world.systems.is_enabled(&system_name);
+                                        if ui.checkbox(&mut enabled, "").changed() {
+                                            world.systems.set_enabled(&system_name, enabled);
+                                        }
+
                                         // The system name.
-                                        ui.label(&system_name);
+                                        if world.systems.is_exclusive(&system_name) {
+                                            ui.label(format!("{system_name} (exclusive)"));
+                                        } else {
+                                            ui.label(&system_name);
+                                        }
+
+                                        // Only manual stages can be reordered.
+                                        if !manual {
+                                            return;
+                                        }
 
                                         // Place the buttons on the right.
                                         ui.with_layout(
@@ -120,69 +243,26 @@ pub fn system_stages_widget(ui: &mut egui::Ui, world: &mut iridium_ecs::World) {
                         });
                 });
 
-            if ui.button("Add stage").clicked() {
+            if manual && ui.button("Add stage").clicked() {
                 world.systems.stages.push(vec![]);
             }
         });
 }
 
-/// A widget to edit system states.
-pub fn system_states_widget(ui: &mut egui::Ui, world: &mut iridium_ecs::World) {
+/// A widget to edit the resources, such as system states.
+pub fn resources_widget(ui: &mut egui::Ui, world: &mut iridium_ecs::World) {
     egui::ScrollArea::new([false, true])
         .always_show_scroll(true)
         .auto_shrink([false, false])
         .max_width(f32::INFINITY)
         .show(ui, |ui| {
-            world
-                .entities
-                // Get the system state.
-                .get_entity_components(
-                    // Get the id of the system state.
-                    world
-                        .entities
-                        .entity_id_from_name("SystemState")
-                        .expect("SystemState not found"),
-                )
-                .unwrap_or_default()
-                .into_iter()
-                .enumerate()
-                .for_each(|(index, component)| {
-                    // Don't show Name.
-                    if component.type_name() == "Name" {
-                        return;
-                    }
-
-                    // Show the component.
-                    component_widget(ui, index, component);
-                    ui.separator();
-                });
+            for (index, resource) in world.resources.all().into_iter().enumerate() {
+                resource_widget(ui, index, resource);
+                ui.separator();
+            }
 
-            if ui.button("Add system state").clicked() {
-                // Get the id of the system state.
-                let system_state_id = world
-                    .entities
-                    .entity_id_from_name("SystemState")
-                    .expect("SystemState not found");
-
-                // Get the default states of every system.
-                let states = world.systems.default_component_states();
-
-                // Get the type ids of the states already in the world.
-                let already_added: HashSet<StableTypeId> = world
-                    .entities
-                    .get_entity_component_types(system_state_id)
-                    .expect("SystemState not found")
-                    .into_iter()
-                    .collect();
-
-                // Add the states that aren't already in the world.
-                world.entities.add_components(
-                    system_state_id,
-                    states
-                        .into_iter()
-                        .filter(|state| !already_added.contains(&state.stable_type_id()))
-                        .collect(),
-                );
+            if ui.button("Add system states").clicked() {
+                world.insert_default_states();
             }
         });
 }
@@ -211,13 +291,13 @@ impl PanelUi for ComponentsList {
                     system_stages_widget(ui, world);
                 });
 
-            egui::TopBottomPanel::bottom("system_state")
+            egui::TopBottomPanel::bottom("resources")
                 .resizable(true)
                 .frame(egui::Frame::none())
                 .show_inside(ui, |ui| {
                     ui.add(egui::Separator::default().spacing(0.));
 
-                    system_states_widget(ui, world);
+                    resources_widget(ui, world);
                 });
 
             egui::ScrollArea::new([false, true])
@@ -269,19 +349,31 @@ impl PanelUi for ComponentsList {
                         ui.separator();
                         ui.add_space(10.);
 
+                        // The component to remove, if its button was clicked.
+                        let mut to_remove = None;
+
                         // For each component,
-                        for (index, component) in components.into_iter().enumerate() {
+                        for (index, mut component) in components.into_iter().enumerate() {
                             // Except Name.
                             if component.is_type::<Name>() {
                                 continue;
                             }
 
                             // Render the component.
-                            component_widget(ui, index, component);
+                            if component_widget(ui, index, &mut component, true) {
+                                to_remove = Some(component.stable_type_id());
+                            }
 
                             // Separator.
                             ui.separator();
                         }
+
+                        // Remove it once the components aren't borrowed.
+                        if let Some(component_type) = to_remove {
+                            world
+                                .entities
+                                .remove_component_by_type_id(id, component_type);
+                        }
                     }
                 });
         });
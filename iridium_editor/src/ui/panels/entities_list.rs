@@ -1,5 +1,6 @@
 use iridium_assets::Assets;
-use iridium_ecs::{query, EntityCommand, Name, World};
+use iridium_ecs::{query, Entity, EntityCommand, Name, World};
+use iridium_reflect::HasStableTypeId;
 
 use crate::ui::{PanelUi, UiState};
 
@@ -32,7 +33,9 @@ impl PanelUi for EntitiesList {
 
             ui.menu_button("Add Entity", |ui| {
                 if ui.button("Empty").clicked() {
-                    world.entities.new_entity(None, "New Entity", vec![]);
+                    world
+                        .entities
+                        .new_entity(None, "New Entity", ui_state.new_entity_components());
                     ui.close_menu();
                 }
             });
@@ -50,46 +53,23 @@ impl PanelUi for EntitiesList {
                 .show(ui, |ui| {
                     ui.add_space(10.);
 
-                    for (id, Name { name }) in {
-                        let mut entities = query!(world.entities, [; Name]).collect::<Vec<_>>();
-                        entities.sort_by_key(|(_, name)| &name.name);
-                        entities
-                    } {
-                        if name == "SystemState" {
-                            continue;
-                        }
-
-                        if !name
-                            .to_lowercase()
-                            .contains(&self.name_filter.to_lowercase())
-                        {
-                            continue;
-                        }
-
-                        let mut rich_text = egui::RichText::new(name);
-
-                        if let Some(selected_id) = ui_state.selected_entity {
-                            if selected_id == id {
-                                rich_text = rich_text.strong();
-                            }
-                        }
-
-                        let label = ui.add(egui::Label::new(rich_text).sense(egui::Sense::click()));
-
-                        if label.clicked() {
-                            ui_state.selected_entity = Some(id);
-                        }
-
-                        let popup = ui.make_persistent_id(format!("Entity {id} popup"));
-                        if label.secondary_clicked() {
-                            ui.memory_mut(|mem| mem.toggle_popup(popup));
-                        }
-                        egui::popup::popup_below_widget(ui, popup, &label, |ui| {
-                            ui.set_min_width(80.);
-                            if ui.button("delete").clicked() {
-                                world.entities.send_cmd(EntityCommand::DeleteEntity(id));
-                            }
-                        });
+                    // While filtering, matching entities are shown flat,
+                    // as their ancestors may not match.
+                    let entities = if self.name_filter.is_empty() {
+                        world.entities.root_entities()
+                    } else {
+                        query!(world.entities, [; Name])
+                            .filter(|(_, name)| {
+                                name.name
+                                    .to_lowercase()
+                                    .contains(&self.name_filter.to_lowercase())
+                            })
+                            .map(|(entity, _)| entity)
+                            .collect()
+                    };
+
+                    for entity in sorted_by_name(world, entities) {
+                        entity_tree_ui(ui, ui_state, world, entity, self.name_filter.is_empty());
                     }
 
                     ui.add_space(30.);
@@ -97,3 +77,96 @@ impl PanelUi for EntitiesList {
         });
     }
 }
+
+/// Gets the name of an entity.
+fn entity_name(world: &World, entity: Entity) -> String {
+    world
+        .entities
+        .entity_component(entity, Name::stable_type_id())
+        .map_or_else(String::new, |name| name.get::<Name>().name.clone())
+}
+
+/// Sorts entities by their name.
+fn sorted_by_name(world: &World, mut entities: Vec<Entity>) -> Vec<Entity> {
+    entities.sort_by_cached_key(|entity| entity_name(world, *entity));
+    entities
+}
+
+/// Draws an entity, and if `show_children` is true, its children below it.
+fn entity_tree_ui(
+    ui: &mut egui::Ui,
+    ui_state: &mut UiState,
+    world: &mut World,
+    id: Entity,
+    show_children: bool,
+) {
+    let children = if show_children {
+        world.entities.children(id)
+    } else {
+        Vec::new()
+    };
+
+    if children.is_empty() {
+        entity_label_ui(ui, ui_state, world, id);
+        return;
+    }
+
+    egui::collapsing_header::CollapsingState::load_with_default_open(
+        ui.ctx(),
+        ui.make_persistent_id(format!("Entity {id} children")),
+        false,
+    )
+    .show_header(ui, |ui| {
+        entity_label_ui(ui, ui_state, world, id);
+    })
+    .body(|ui| {
+        for child in sorted_by_name(world, children) {
+            entity_tree_ui(ui, ui_state, world, child, true);
+        }
+    });
+}
+
+/// Draws the selectable name of an entity, with a popup to edit it.
+fn entity_label_ui(ui: &mut egui::Ui, ui_state: &mut UiState, world: &mut World, id: Entity) {
+    let mut rich_text = egui::RichText::new(entity_name(world, id));
+
+    if let Some(selected_id) = ui_state.selected_entity {
+        if selected_id == id {
+            rich_text = rich_text.strong();
+        }
+    }
+
+    let label = ui.add(egui::Label::new(rich_text).sense(egui::Sense::click()));
+
+    if label.clicked() {
+        ui_state.selected_entity = Some(id);
+    }
+
+    let popup = ui.make_persistent_id(format!("Entity {id} popup"));
+    if label.secondary_clicked() {
+        ui.memory_mut(|mem| mem.toggle_popup(popup));
+    }
+    egui::popup::popup_below_widget(ui, popup, &label, |ui| {
+        ui.set_min_width(80.);
+        if ui.button("add child").clicked() {
+            let child =
+                world
+                    .entities
+                    .new_entity(None, "New Entity", ui_state.new_entity_components());
+            world.entities.set_parent(child, Some(id));
+        }
+        if let Some(selected_id) = ui_state.selected_entity {
+            if selected_id != id && ui.button("parent selected").clicked() {
+                world
+                    .entities
+                    .send_cmd(EntityCommand::SetParent(selected_id, Some(id)));
+            }
+        }
+        if world.entities.parent(id).is_some() && ui.button("unparent").clicked() {
+            world.entities.send_cmd(EntityCommand::SetParent(id, None));
+        }
+        if ui.button("delete").clicked() {
+            world.entities.send_cmd(EntityCommand::DespawnRecursive(id));
+        }
+    });
+}
@@ -1,4 +1,6 @@
 use iridium_assets::Assets;
+use iridium_core::LogState;
+use iridium_ecs::systems::{RollingStat, Systems};
 
 use crate::{play_state::PlayState, ui::PanelUi, FrameHistoryState};
 
@@ -22,9 +24,12 @@ impl PanelUi for TopPanel {
                 if let [menus, buttons, stats] = columns {
                     menus.horizontal(|ui| {
                         ui.menu_button("File", |ui| {
-                            if let Some(scene) = &ui_state.open_scene {
+                            if ui_state.open_scene.is_some() {
+                                // Each loaded scene is saved back to its own file.
                                 if ui.button("Save").clicked() {
-                                    world.save(scene);
+                                    for scene in world.entities.scenes() {
+                                        world.save_scene(&scene);
+                                    }
                                     ui.close_menu();
                                 }
                             } else {
@@ -75,6 +80,8 @@ impl PanelUi for TopPanel {
                                     )
                                     .clicked()
                                 {
+                                    let errors = world.run_shutdown(assets);
+                                    world.resources.get_mut::<LogState>().system_errors(errors);
                                     world
                                         .load("temp.json5", assets)
                                         .expect("Save file disappeared");
@@ -90,8 +97,9 @@ impl PanelUi for TopPanel {
                     stats.horizontal(|ui| {
                         ui.label(format!(
                             "FPS: {:.1}",
-                            world.entities.get::<FrameHistoryState>().average_fps()
+                            world.resource::<FrameHistoryState>().average_fps()
                         ));
+                        ui.menu_button("Systems", |ui| system_stats_table(ui, &world.systems));
                         ui.add_space(15.);
                         ui.label(format!(
                             "Entities: {}",
@@ -110,3 +118,52 @@ impl PanelUi for TopPanel {
         });
     }
 }
+
+/// A table of how long each system and stage took, slowest system first.
+fn system_stats_table(ui: &mut egui::Ui, systems: &Systems) {
+    /// Adds the last, average and max of a time to a row.
+    fn time_cells(ui: &mut egui::Ui, time: &RollingStat) {
+        ui.label(format!("{:.3}", time.last()));
+        ui.label(format!("{:.3}", time.average()));
+        ui.label(format!("{:.3}", time.max()));
+    }
+
+    let mut system_stats = systems.system_stats().iter().collect::<Vec<_>>();
+    system_stats.sort_by(|(_, a), (_, b)| b.time.average().total_cmp(&a.time.average()));
+
+    egui::Grid::new("System stats")
+        .striped(true)
+        .show(ui, |ui| {
+            ui.strong("System");
+            ui.strong("Last (ms)");
+            ui.strong("Average (ms)");
+            ui.strong("Max (ms)");
+            ui.strong("Entities");
+            ui.end_row();
+
+            for (name, stats) in system_stats {
+                ui.label(name);
+                time_cells(ui, &stats.time);
+                ui.label(format!("{:.0}", stats.entities.average()));
+                ui.end_row();
+            }
+        });
+
+    ui.separator();
+
+    egui::Grid::new("Stage stats").striped(true).show(ui, |ui| {
+        ui.strong("Stage");
+        ui.strong("Last (ms)");
+        ui.strong("Average (ms)");
+        ui.strong("Max (ms)");
+        ui.end_row();
+
+        for schedule in systems.schedule_labels() {
+            for (index, time) in systems.stage_stats(&schedule).iter().enumerate() {
+                ui.label(format!("{schedule:?} {}", index + 1));
+                time_cells(ui, time);
+                ui.end_row();
+            }
+        }
+    });
+}
@@ -1,3 +1,4 @@
+use iridium_ecs::{ComponentBox, Entity, SceneOrigin};
 use iridium_graphics::Camera;
 use iridium_maths::VecN;
 
@@ -11,8 +12,8 @@ pub struct UiState {
     pub screen_size: (u32, u32),
     /// The scale factor of the egui UI.
     pub scale_factor: f32,
-    /// The id of the currently selected entity.
-    pub selected_entity: Option<u128>,
+    /// The currently selected entity.
+    pub selected_entity: Option<Entity>,
     /// The current play state.
     play_state: PlayState,
     /// The editor camera.
@@ -20,6 +21,8 @@ pub struct UiState {
     /// The start position of the camera pan.
     pub pan_start: Option<egui::Pos2>,
     /// Open scene.
+    ///
+    /// New entities are added to it.
     pub open_scene: Option<String>,
 }
 
@@ -70,4 +73,18 @@ impl UiState {
         // Eventually this will do quite a bit more, but that comes later.
         self.play_state = PlayState::Stop;
     }
+
+    /// The components a new entity starts with,
+    /// so it's saved with the open scene.
+    pub fn new_entity_components(&self) -> Vec<ComponentBox> {
+        self.open_scene
+            .iter()
+            .map(|scene| {
+                SceneOrigin {
+                    scene: scene.clone(),
+                }
+                .into()
+            })
+            .collect()
+    }
 }
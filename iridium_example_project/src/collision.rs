@@ -1,6 +1,6 @@
 use iridium_assets::Assets;
 use iridium_collision::Rect;
-use iridium_ecs::{query, Transform};
+use iridium_ecs::{Entity, GlobalTransform, Mut, Ref};
 use iridium_ecs_macros::{
     system_helper, Component, ComponentStorage, HasStableTypeId, InspectorUi,
 };
@@ -24,10 +24,17 @@ impl CollisionSystem {
     #[allow(clippy::similar_names)]
     fn system(
         _state: (),
-        entities: &iridium_ecs::Entities,
-        (_, wall, wall_transform, wall_r2d): (u128, &Wall, &Transform, &Renderable2D),
+        _entities: &iridium_ecs::Entities,
+        _resources: &iridium_ecs::Resources,
+        (_, wall, wall_transform, wall_r2d): (Entity, &Wall, &GlobalTransform, &Renderable2D),
         _assets: &Assets,
         _delta_time: f64,
+        balls: &mut [(
+            Entity,
+            Mut<Velocity>,
+            Ref<GlobalTransform>,
+            Ref<Renderable2D>,
+        )],
     ) -> Result<(), String> {
         let wall_rect = Rect::bounding_from_vertices(
             &wall_r2d
@@ -39,9 +46,7 @@ impl CollisionSystem {
         )
         .apply_transform(wall_transform);
 
-        for (_, velocity, ball_transform, ball_r2d) in
-            query!(entities, [mut Velocity; Transform, Renderable2D])
-        {
+        for (_, velocity, ball_transform, ball_r2d) in balls.iter_mut() {
             let ball_rect = Rect::bounding_from_vertices(
                 &ball_r2d
                     .mesh
@@ -50,7 +55,7 @@ impl CollisionSystem {
                     .map(|vertex| vertex.position)
                     .collect::<Vec<_>>(),
             )
-            .apply_transform(ball_transform);
+            .apply_transform(&**ball_transform);
 
             if ball_rect.is_colliding(&wall_rect) {
                 if wall.vertical {
@@ -65,5 +70,13 @@ impl CollisionSystem {
     }
 }
 
-#[system_helper((), par_iter, &Wall, &Transform, &Renderable2D)]
+// Each wall borrows the velocities mutably, so they can't be checked in parallel.
+#[system_helper(
+    (),
+    iter,
+    &Wall,
+    &GlobalTransform,
+    &Renderable2D;
+    balls: query(&mut Velocity, &GlobalTransform, &Renderable2D)
+)]
 impl System for CollisionSystem {}
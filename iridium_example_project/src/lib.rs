@@ -14,7 +14,7 @@ pub use assets::*;
 
 use iridium_assets::Assets;
 use iridium_core::ProjectSettings;
-use iridium_ecs::World;
+use iridium_ecs::{systems::ScheduleLabel, World};
 
 /// Returns the project settings.
 #[no_mangle]
@@ -34,13 +34,18 @@ pub fn init_system(world: &mut World, assets: &Assets) {
     world.entities.register_component_with_default::<Wall>();
     world.entities.register_component_with_default::<Movement>();
 
-    world.systems.add_system(VelocitySystem);
-    world.systems.add_system(MovementSystem);
-    world.systems.add_system(CollisionSystem);
-
-    world.systems.stages = vec![
-        vec!["VelocitySystem".to_string()],
-        vec!["MovementSystem".to_string()],
-        vec!["CollisionSystem".to_string()],
-    ];
+    // The physics runs at a fixed rate, so it's the same at any frame rate.
+    world
+        .systems
+        .add_system(VelocitySystem)
+        .in_schedule(ScheduleLabel::FixedUpdate);
+    world
+        .systems
+        .add_system(MovementSystem)
+        .in_schedule(ScheduleLabel::FixedUpdate);
+    world
+        .systems
+        .add_system(CollisionSystem)
+        .in_schedule(ScheduleLabel::FixedUpdate)
+        .after("MovementSystem");
 }
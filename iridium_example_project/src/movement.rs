@@ -1,5 +1,5 @@
 use iridium_core::{InputState, KeyCode};
-use iridium_ecs::Transform;
+use iridium_ecs::{Entity, Transform};
 use iridium_ecs_macros::{
     system_helper, Component, ComponentStorage, HasStableTypeId, InspectorUi,
 };
@@ -34,12 +34,13 @@ pub struct MovementSystem;
 impl MovementSystem {
     fn system(
         _: (),
-        entities: &iridium_ecs::Entities,
-        (_, transform, movement): (u128, &mut Transform, &Movement),
+        _entities: &iridium_ecs::Entities,
+        resources: &iridium_ecs::Resources,
+        (_, transform, movement): (Entity, &mut Transform, &Movement),
         _assets: &iridium_assets::Assets,
         delta_time: f64,
     ) -> Result<(), String> {
-        let input_state = entities.get::<InputState>();
+        let input_state = resources.get::<InputState>();
 
         if input_state.key(&movement.up).down() {
             *transform.position.y_mut() += movement.speed * delta_time as f32;
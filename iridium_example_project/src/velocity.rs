@@ -1,5 +1,5 @@
 use iridium_assets::Assets;
-use iridium_ecs::{Entities, Transform};
+use iridium_ecs::{Entities, Entity, Resources, Transform};
 use iridium_ecs_macros::{
     system_helper, Component, ComponentStorage, HasStableTypeId, InspectorUi,
 };
@@ -27,7 +27,8 @@ impl VelocitySystem {
     fn system(
         _state: (),
         _entities: &Entities,
-        (_, transform, velocity): (u128, &mut Transform, &mut Velocity),
+        _resources: &Resources,
+        (_, transform, velocity): (Entity, &mut Transform, &mut Velocity),
         _assets: &Assets,
         delta_time: f64,
     ) -> Result<(), String> {
@@ -3,7 +3,7 @@ use std::sync::Arc;
 use egui::Widget;
 use iridium_assets::{AssetBox, Assets};
 use iridium_ecs::{
-    storage::{ComponentStorage, StoredComponent, StoredComponentField},
+    storage::{ComponentStorage, StoredComponent, StoredValue},
     ui::InspectorUi,
     ComponentDefault,
 };
@@ -216,9 +216,9 @@ impl InspectorUi for Renderable2D {
 impl ComponentStorage for Renderable2D {
     fn from_stored(mut stored: StoredComponent, assets: &Assets) -> Option<Self> {
         Some(Self {
-            mesh: assets.get(&stored.get("mesh")?).ok()?,
+            mesh: assets.get(&stored.get::<String>("mesh")?).ok()?,
 
-            material: assets.get(&stored.get("material")?).ok()?,
+            material: assets.get(&stored.get::<String>("material")?).ok()?,
 
             vertex_shader_buffers: None,
             vertex_shader_bind_group: None,
@@ -235,8 +235,8 @@ impl ComponentStorage for Renderable2D {
         StoredComponent {
             type_name: "Renderable2D".to_string(),
             fields: fast_map! {
-                "mesh" => StoredComponentField::new(self.mesh.id().to_owned(), true),
-                "material" => StoredComponentField::new(self.material.id().to_owned(), true),
+                "mesh" => StoredValue::String(self.mesh.id().to_owned()),
+                "material" => StoredValue::String(self.material.id().to_owned()),
             },
         }
     }
@@ -273,6 +273,9 @@ impl Renderable2D {
     }
 
     /// Creates the live data needed at runtime and in editor that isn't stored.
+    ///
+    /// This does nothing if it already exists,
+    /// so `delete_live_data` needs calling first to rebuild it.
     pub fn create_live_data(&mut self, device: &wgpu::Device) {
         // The live data is all or nothing,
         // so if vertex_buffer is none, all the other fields should be none.
@@ -25,7 +25,7 @@ impl Vertex {
         self.position
             .as_bytes::<12>()
             .into_iter()
-            .chain(self.uv.as_bytes::<8>().into_iter())
+            .chain(self.uv.as_bytes::<8>())
             .enumerate()
             .for_each(|(i, b)| bytes[i] = b);
 
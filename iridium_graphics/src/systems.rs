@@ -3,8 +3,9 @@ use std::cmp::Ordering;
 use iridium_assets::Assets;
 use iridium_ecs::{
     query,
-    storage::{ComponentStorage, StoredComponent, StoredComponentField},
-    Entities, Name, Transform,
+    storage::{ComponentStorage, FieldStorage, StoredComponent},
+    systems::FixedTime,
+    Access, Entities, GlobalTransform, Name, QueryFilter, Resources, SystemTicks,
 };
 use iridium_ecs_macros::{Component, HasStableTypeId, InspectorUi};
 use iridium_map_utils::fast_map;
@@ -23,13 +24,18 @@ pub struct Renderer2DState {
     /// The camera GPU data.
     #[hidden]
     pub camera_gpu_data: Option<CameraGpuData>,
+    /// The tick of the last render,
+    /// `None` if nothing has been rendered yet.
+    #[hidden]
+    pub last_render_tick: Option<u32>,
 }
 
 impl ComponentStorage for Renderer2DState {
     fn from_stored(mut stored: StoredComponent, _assets: &iridium_assets::Assets) -> Option<Self> {
         Some(Self {
-            active_camera: stored.get("active_camera")?.parse().ok()?,
+            active_camera: stored.get("active_camera")?,
             camera_gpu_data: None,
+            last_render_tick: None,
         })
     }
 
@@ -37,7 +43,7 @@ impl ComponentStorage for Renderer2DState {
         StoredComponent {
             type_name: "Renderer2DState".to_string(),
             fields: fast_map! {
-                "active_camera" => StoredComponentField::new(self.active_camera.to_string(), true),
+                "active_camera" => self.active_camera.to_stored_value(),
             },
         }
     }
@@ -49,12 +55,44 @@ impl ComponentStorage for Renderer2DState {
 pub struct Renderer2DSystem;
 
 impl Renderer2DSystem {
+    /// Creates the live data of renderables added or changed since the last render.
+    ///
+    /// Changed renderables may have a new mesh or material,
+    /// so their old live data is deleted and rebuilt.
+    fn update_live_data(entities: &Entities, state: &mut Renderer2DState, device: &wgpu::Device) {
+        puffin::profile_function!();
+
+        // Changes made during the last render's tick are checked again,
+        // as systems can still run in that tick after the render.
+        let render_ticks = SystemTicks {
+            last_run: state.last_render_tick.unwrap_or(u32::MAX),
+            current: entities.change_tick(),
+        };
+        state.last_render_tick = Some(entities.change_tick().wrapping_sub(1));
+
+        for (_, [mut renderable_2d], []) in entities.query_by_type_id_filtered(
+            [Access::Write(
+                <Renderable2D as iridium_reflect::HasStableTypeId>::stable_type_id(),
+            )],
+            [],
+            &QueryFilter::default(),
+        ) {
+            if renderable_2d.ticks().is_changed(render_ticks) {
+                // Creating live data isn't a change to the renderable.
+                let renderable_2d = renderable_2d.get_mut_untracked::<Renderable2D>();
+                renderable_2d.delete_live_data();
+                renderable_2d.create_live_data(device);
+            }
+        }
+    }
+
     /// Runs the system.
     #[allow(clippy::too_many_arguments)]
     // This should probably be changed at some point.
     #[allow(clippy::too_many_lines)]
     pub fn run(
         entities: & /* 'entities */ Entities,
+        resources: &Resources,
         _assets: &Assets,
         device: &wgpu::Device,
         render_pass: & /* 'rpass */ mut wgpu::RenderPass,
@@ -81,7 +119,10 @@ impl Renderer2DSystem {
                 1.,
             );
 
-            let state = entities.get::<Renderer2DState>();
+            let mut state = resources.get_mut::<Renderer2DState>();
+
+            // Keeps the active camera borrowed while it's used.
+            let mut active_camera = None;
 
             let camera = {
                 puffin::profile_scope!("Camera data update");
@@ -92,9 +133,7 @@ impl Renderer2DSystem {
 
                     camera
                 } else {
-                    let mut active_camera = None;
-
-                    for (_, camera) in query!(entities, [mut Camera; ]) {
+                    for (_, mut camera) in query!(entities, [mut Camera; ]) {
                         *camera.viewport_size.x_mut() = size_pixels.0;
                         *camera.viewport_size.y_mut() = size_pixels.1;
 
@@ -103,20 +142,22 @@ impl Renderer2DSystem {
                         }
                     }
 
-                    match active_camera {
+                    match active_camera.as_deref_mut() {
                         Some(camera) => camera,
                         None => return,
                     }
                 }
             };
 
+            Self::update_live_data(entities, &mut state, device);
+
             let components = {
                 puffin::profile_scope!("Getting components");
 
                 let mut components = {
                     puffin::profile_scope!("Query");
 
-                    query!(entities, [mut Renderable2D; Transform, Name]).collect::<Vec<_>>()
+                    query!(entities, [; Renderable2D, GlobalTransform, Name]).collect::<Vec<_>>()
                 };
 
                 {
@@ -170,16 +211,23 @@ impl Renderer2DSystem {
 
         puffin::profile_scope!("Rendering");
 
-        for (_, renderable_2d, transform, _) in components {
-            // Check if runtime data is initialised, if not, create it.
-            renderable_2d.create_live_data(device);
+        // How far between the last fixed step and the next this frame is,
+        // so entities moved by fixed steps move smoothly.
+        #[allow(clippy::cast_possible_truncation)]
+        let alpha = resources
+            .try_get::<FixedTime>()
+            .map_or(1., |fixed_time| fixed_time.alpha() as f32);
+
+        // The components stay borrowed until the end of the function.
+        for (_, renderable_2d, transform, _) in &components {
+            let transform = transform.interpolated(alpha);
 
             // Extend the lifetime of renderable_2d for render_pass.set_pipeline.
             // This is safe because it's only used as the pipeline for the duration of this function.
             #[allow(clippy::useless_transmute)]
             let r2d = unsafe {
                 std::mem::transmute::<& /* 'entities */ Renderable2D, & /* 'rpass */ Renderable2D>(
-                    renderable_2d,
+                    &**renderable_2d,
                 )
             };
 
@@ -187,8 +235,8 @@ impl Renderer2DSystem {
                 .position
                 .as_bytes::<16>()
                 .into_iter()
-                .chain(transform.scale.as_bytes::<12>().into_iter())
-                .chain(transform.rotation.to_le_bytes().into_iter())
+                .chain(transform.scale.as_bytes::<12>())
+                .chain(transform.rotation.to_le_bytes())
                 .collect::<Vec<u8>>();
 
             queue.write_buffer(
@@ -236,3 +284,113 @@ impl Renderer2DSystem {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use iridium_assets::Assets;
+    use iridium_ecs::Entities;
+    use iridium_maths::VecN;
+    use iridium_reflect::HasStableTypeId;
+
+    use super::*;
+    use crate::{Material, Mesh, Shader, Vertex};
+
+    /// Gets a device, or `None` if there's no adapter, such as without a GPU.
+    fn device() -> Option<(wgpu::Device, wgpu::Queue)> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).ok()
+    }
+
+    /// A shader without inputs.
+    fn shader(device: &wgpu::Device, source: &str) -> Shader {
+        Shader {
+            inputs: vec![],
+            bind_group_layout: device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: None,
+                entries: &[],
+            }),
+            shader: device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            }),
+        }
+    }
+
+    /// A mesh with a triangle for each of `triangles`.
+    fn mesh(triangles: u32) -> Mesh {
+        Mesh {
+            vertices: (0..3)
+                .map(|_| Vertex::new(VecN::new([0.; 3]), VecN::new([0.; 2])))
+                .collect(),
+            indices: (0..triangles * 3).map(|index| index % 3).collect(),
+        }
+    }
+
+    #[test]
+    fn changing_the_mesh_rebuilds_live_data() {
+        let Some((device, _queue)) = device() else {
+            return;
+        };
+
+        let mut assets = Assets::new();
+        assets.add(
+            "vertex",
+            shader(
+                &device,
+                "@vertex fn vs_main() -> @builtin(position) vec4<f32> { return vec4<f32>(); }",
+            ),
+        );
+        assets.add(
+            "fragment",
+            shader(
+                &device,
+                "@fragment fn fs_main() -> @location(0) vec4<f32> { return vec4<f32>(); }",
+            ),
+        );
+        let material = Material::new(
+            &device,
+            wgpu::TextureFormat::Rgba8Unorm,
+            assets.get("vertex").unwrap(),
+            &CameraGpuData::new(&device),
+            assets.get("fragment").unwrap(),
+        );
+        assets.add("material", material);
+        assets.add("triangle", mesh(1));
+        assets.add("triangles", mesh(2));
+
+        let mut entities = Entities::default();
+        let entity = entities.new_entity(
+            None,
+            "Sprite",
+            vec![Renderable2D::new(
+                assets.get("triangle").unwrap(),
+                assets.get("material").unwrap(),
+            )
+            .into()],
+        );
+        let index_count = |entities: &Entities| {
+            entities
+                .entity_component(entity, Renderable2D::stable_type_id())
+                .unwrap()
+                .get::<Renderable2D>()
+                .index_count
+        };
+
+        let mut state = Renderer2DState::default();
+        Renderer2DSystem::update_live_data(&entities, &mut state, &device);
+        assert_eq!(index_count(&entities), Some(3));
+        entities.advance_tick();
+
+        entities
+            .entity_component_mut(entity, Renderable2D::stable_type_id())
+            .unwrap()
+            .get_mut::<Renderable2D>()
+            .mesh = assets.get("triangles").unwrap();
+        entities.advance_tick();
+
+        Renderer2DSystem::update_live_data(&entities, &mut state, &device);
+        assert_eq!(index_count(&entities), Some(6));
+    }
+}
@@ -1,6 +1,39 @@
 /// The type returns by `HasStableTypeId::STABLE_TYPE_ID`.
 pub type StableTypeId = u64;
 
+/// The stable type id of the type with this name.
+///
+/// This is what `#[derive(HasStableTypeId)]` uses,
+/// so ids can be looked up from type names, like when converting saved scenes.
+///
+/// The id is the 64 bit FNV-1a hash of the name's UTF-8 bytes.
+/// Ids are saved in binary scenes, so this must never change,
+/// which is why it doesn't use the standard library's hasher.
+#[must_use]
+pub const fn stable_type_id_from_name(type_name: &str) -> StableTypeId {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+    extend_stable_type_id(OFFSET_BASIS, type_name.as_bytes())
+}
+
+/// Continues the hash of a stable type id with more bytes,
+/// for ids made from several parts, like those of generic types.
+///
+/// The id of a name followed by the bytes is the same as hashing them all at once.
+#[must_use]
+pub const fn extend_stable_type_id(id: StableTypeId, bytes: &[u8]) -> StableTypeId {
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = id;
+    let mut index = 0;
+    while index < bytes.len() {
+        hash ^= bytes[index] as u64;
+        hash = hash.wrapping_mul(PRIME);
+        index += 1;
+    }
+    hash
+}
+
 /// A type id that doesn't change between compiler invocations.
 /// Calculated at compile time from the type name.
 pub trait HasStableTypeId {
@@ -22,3 +55,27 @@ impl HasStableTypeId for () {
         0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_type_ids_are_fnv_1a() {
+        // The FNV-1a 64 test vectors.
+        assert_eq!(stable_type_id_from_name(""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(stable_type_id_from_name("a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(stable_type_id_from_name("foobar"), 0x8594_4171_f739_67e8);
+
+        // Saved scenes depend on this staying the same.
+        assert_eq!(stable_type_id_from_name("Transform"), 0xc1ff_f4f3_56df_b2fb);
+    }
+
+    #[test]
+    fn extended_ids_hash_all_the_bytes() {
+        assert_eq!(
+            extend_stable_type_id(stable_type_id_from_name("foo"), b"bar"),
+            stable_type_id_from_name("foobar")
+        );
+    }
+}
@@ -99,8 +99,8 @@ impl App {
 
     /// Handles window input and passes it to the game.
     pub fn input(&mut self, world: &mut World, event: &WindowEvent) {
-        let input_state = world.entities.get::<InputState>();
-        let log = world.entities.get::<LogState>();
+        let mut input_state = world.resources.get_mut::<InputState>();
+        let mut log = world.resources.get_mut::<LogState>();
 
         match event {
             WindowEvent::Resized(physical_size) => {
@@ -188,6 +188,7 @@ impl App {
             // Run the rendering system for the entities in the world.
             Renderer2DSystem::run(
                 &world.entities,
+                &world.resources,
                 assets,
                 &self.device,
                 &mut render_pass,
@@ -0,0 +1,68 @@
+//! Converts a scene between the json5 and binary formats.
+//!
+//! ```text
+//! convert_scene <src> <dst> [project library]
+//! ```
+//!
+//! The formats are chosen by the files' extensions.
+//! The project library is needed to know the type names of the project's components
+//! when converting from binary, and defaults to the example project.
+//! It isn't loaded when converting from json5.
+
+use iridium_assets::Assets;
+use iridium_core::{InputState, LogState, Project};
+use iridium_ecs::{
+    storage::{convert_scene, SceneFormat},
+    systems::Systems,
+    Entities, World,
+};
+use iridium_graphics::{Camera, Renderable2D, Renderer2DState};
+
+fn main() {
+    let args = std::env::args().collect::<Vec<_>>();
+    let (Some(src), Some(dst)) = (args.get(1), args.get(2)) else {
+        eprintln!("Usage: convert_scene <src> <dst> [project library]");
+        std::process::exit(2);
+    };
+
+    // The project is only needed to know the type names in binary scenes.
+    // This needs to be defined before `world`, for reasons explained in `Project::load`.
+    let project = if SceneFormat::from_path(src) == SceneFormat::Binary {
+        let project_path = args
+            .get(3)
+            .map_or("target/debug/libiridium_example_project.so", String::as_str);
+        match Project::try_load(project_path) {
+            Ok(project) => Some(project),
+            Err(e) => {
+                eprintln!("Couldn't load the project library {project_path}: {e:?}");
+                eprintln!("Usage: convert_scene <src> <dst> [project library]");
+                std::process::exit(2);
+            }
+        }
+    } else {
+        None
+    };
+
+    // Register the default components, and the project's.
+    let mut world = World::new(Entities::default(), Systems::new());
+    world.entities.register_component::<Renderable2D>();
+    world.entities.register_component::<Renderer2DState>();
+    world.entities.register_component::<InputState>();
+    world.entities.register_component::<LogState>();
+    world.entities.register_component::<Camera>();
+    if let Some(project) = &project {
+        project.init_system(&mut world, &Assets::new());
+    }
+
+    match convert_scene(src, dst, &world.entities) {
+        Ok(diagnostics) => {
+            for diagnostic in diagnostics {
+                eprintln!("Skipped: {diagnostic}");
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to convert scene with error: {e:?}");
+            std::process::exit(1);
+        }
+    }
+}
@@ -49,20 +49,15 @@ fn main() {
     world.entities.register_component::<InputState>();
     world.entities.register_component::<LogState>();
     world.entities.register_component_with_default::<Camera>();
-    world.entities.add_components(
-        world
-            .entities
-            .entity_id_from_name("SystemState")
-            .expect("SystemState entity not found"),
-        vec![
-            Renderer2DState {
-                active_camera: String::new(),
-                camera_gpu_data: Some(camera_gpu_data),
-            }
-            .into(),
-            InputState::default().into(),
-        ],
-    );
+
+    // Add the engine resources.
+    world.insert_resource(Renderer2DState {
+        active_camera: String::new(),
+        camera_gpu_data: Some(camera_gpu_data),
+        last_render_tick: None,
+    });
+    world.insert_resource(InputState::default());
+    world.insert_resource(LogState::default());
 
     // Create the camera.
     world
@@ -75,8 +70,7 @@ fn main() {
     // Load the assets.
     if let Err(e) = project.load_assets(
         world
-            .entities
-            .get::<Renderer2DState>()
+            .resource::<Renderer2DState>()
             .camera_gpu_data
             .as_ref()
             .expect("CameraGpuData not found"),
@@ -90,6 +84,14 @@ fn main() {
 
     // Run the init system.
     project.init_system(&mut world, &assets);
+    world.insert_default_states();
+
+    // Report bad system ordering now, rather than when the systems first run.
+    let schedule_error = world.systems.check_schedule();
+    world
+        .resources
+        .get_mut::<LogState>()
+        .system_errors(schedule_error);
 
     // Open the default scene.
     let default_scene = project.project_settings.default_scene;
@@ -108,6 +110,10 @@ fn main() {
         } if window_id == window.id() => {
             // If the app didn't handle the event itself.
             if matches!(event, WindowEvent::CloseRequested) {
+                // The world is unloaded, so run the shutdown systems.
+                let errors = world.run_shutdown(&assets);
+                world.resources.get_mut::<LogState>().system_errors(errors);
+
                 // Exit the app.
                 *control_flow = ControlFlow::Exit;
             } else {
@@ -130,22 +136,25 @@ fn main() {
             last_time = std::time::Instant::now();
 
             // Run the systems.
-            world
-                .systems
-                .run_systems(&mut world.entities, delta_time, &assets);
+            let errors = world.run_systems(delta_time, &assets);
+            world.resources.get_mut::<LogState>().system_errors(errors);
 
             // Running the systems could've changed an asset id,
             // so these need to be updated here.
-            let log_state = world.entities.get::<LogState>();
             match world.entities.update_assets(&assets) {
-                Ok(count) if count > 0 => log_state.info(format!("Updated {count} assets")),
-                Err(error) => log_state.error(format!("Updating assets failed: {error}")),
+                Ok(count) if count > 0 => world
+                    .resources
+                    .get_mut::<LogState>()
+                    .info(format!("Updated {count} assets")),
+                Err(error) => world
+                    .resources
+                    .get_mut::<LogState>()
+                    .error(format!("Updating assets failed: {error}")),
                 _ => {}
             }
 
             // Process the input from last frame.
-            let input_state = world.entities.get::<InputState>();
-            input_state.process_old_inputs();
+            world.resources.get_mut::<InputState>().process_old_inputs();
 
             // Render the app and game.
             app.render(&mut world, &assets);